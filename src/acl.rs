@@ -0,0 +1,140 @@
+use crate::{client::VaultClient, error::Result, redact::Redacted, secret::TypedSecret};
+
+use serde::Deserialize;
+use std::fmt;
+use zeroize::ZeroizeOnDrop;
+
+/// ACL token of the consul or nomad secrets engine. Consul returns `token`/`accessor` and nomad
+/// `secret_id`/`accessor_id`
+#[derive(Deserialize, ZeroizeOnDrop)]
+pub struct AclToken {
+	/// the secret part of the token (`X-Consul-Token` or `X-Nomad-Token`)
+	#[serde(alias = "secret_id")]
+	pub token: String,
+	#[serde(alias = "accessor_id", default)]
+	pub accessor: String,
+}
+
+impl fmt::Debug for AclToken {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AclToken")
+			.field("token", &Redacted(&self.token))
+			.field("accessor", &self.accessor)
+			.finish()
+	}
+}
+
+impl VaultClient {
+	/// Generate an acl token for acl_role of the secrets engine mounted at mount
+	fn acl_token(&self, role: &str, mount: &str, acl_role: &str) -> Result<TypedSecret<AclToken>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), acl_role),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		self.leased(self.send(request)?)
+	}
+
+	/// Generate asynchronously an acl token for acl_role of the secrets engine mounted at mount
+	async fn acl_token_async(
+		&self,
+		role: &str,
+		mount: &str,
+		acl_role: &str,
+	) -> Result<TypedSecret<AclToken>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), acl_role),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		self.leased(self.send_async(request).await?)
+	}
+
+	/// Generate a consul token for consul_role of the consul engine mounted at mount
+	pub fn consul_creds(
+		&self,
+		role: &str,
+		mount: &str,
+		consul_role: &str,
+	) -> Result<TypedSecret<AclToken>> {
+		self.acl_token(role, mount, consul_role)
+	}
+
+	/// Generate asynchronously a consul token for consul_role of the consul engine
+	pub async fn consul_creds_async(
+		&self,
+		role: &str,
+		mount: &str,
+		consul_role: &str,
+	) -> Result<TypedSecret<AclToken>> {
+		self.acl_token_async(role, mount, consul_role).await
+	}
+
+	/// Generate a nomad token for nomad_role of the nomad engine mounted at mount
+	pub fn nomad_creds(
+		&self,
+		role: &str,
+		mount: &str,
+		nomad_role: &str,
+	) -> Result<TypedSecret<AclToken>> {
+		self.acl_token(role, mount, nomad_role)
+	}
+
+	/// Generate asynchronously a nomad token for nomad_role of the nomad engine
+	pub async fn nomad_creds_async(
+		&self,
+		role: &str,
+		mount: &str,
+		nomad_role: &str,
+	) -> Result<TypedSecret<AclToken>> {
+		self.acl_token_async(role, mount, nomad_role).await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn tokens_from_responses() {
+		let transport = Scripted::new();
+		transport
+			.on(
+				"GET",
+				"consul/creds/mesh",
+				200,
+				json!({
+					"lease_id": "consul/creds/mesh/2f6a614c",
+					"lease_duration": 3600,
+					"renewable": true,
+					"data": {
+						"token": "642783bf-1540-526f-d4de-fe1ac1aed6f0",
+						"accessor": "5cbd3ef7-05ce-4470-84c9-6409ff543917",
+						"local": false
+					}
+				}),
+			)
+			.on(
+				"GET",
+				"nomad/creds/deploy",
+				200,
+				json!({
+					"lease_id": "nomad/creds/deploy/7cb8df71",
+					"lease_duration": 2764800,
+					"renewable": true,
+					"data": {
+						"accessor_id": "c834ba40-8d84-b0c1-c084-3a31d3383c03",
+						"secret_id": "65af6f07-7f57-bb24-cdae-a27f86a894ce"
+					}
+				}),
+			);
+		let client = transport.client();
+		let consul = client.consul_creds(TOKEN_ROLE, "consul", "mesh").unwrap();
+		assert_eq!(consul.token, "642783bf-1540-526f-d4de-fe1ac1aed6f0");
+		let nomad = client.nomad_creds(TOKEN_ROLE, "nomad", "deploy").unwrap();
+		assert_eq!(nomad.accessor, "c834ba40-8d84-b0c1-c084-3a31d3383c03");
+	}
+}
@@ -0,0 +1,127 @@
+use crate::{
+	auth::Auth,
+	client::VaultClient,
+	error::Result,
+	method::read_token,
+};
+
+use std::{
+	fs,
+	path::Path,
+	sync::{
+		mpsc::{self, Receiver},
+		Arc, Condvar, Mutex,
+	},
+	thread,
+	time::{Duration, SystemTime},
+};
+
+/// Watches in a background thread the token file written by the file sink of a Vault Agent
+/// auto-auth, and replaces the tokens cached by the client with the new token each time the file
+/// changes. The results of the refreshes are delivered over a channel. The thread stops when the
+/// watcher or the receiver is dropped
+#[derive(Debug)]
+pub struct AgentSinkWatcher {
+	stop: Arc<(Mutex<bool>, Condvar)>,
+}
+
+/// Return the modification time of the file at path or None if it can't be read
+fn modified(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Read the token of the sink at path and cache it under all the roles already logged
+fn refresh(client: &VaultClient, path: &Path) -> Result<()> {
+	let token = read_token(path)?;
+	let roles: Vec<String> = client.auth_read().keys().cloned().collect();
+	for role in roles.iter() {
+		client.cache_auth(role, Auth::new(&token, None));
+	}
+	Ok(())
+}
+
+impl AgentSinkWatcher {
+	/// Start watching the token file at path every interval. The file is read immediately and
+	/// then each time its modification time changes
+	pub fn start(
+		client: &Arc<VaultClient>,
+		path: &str,
+		interval: Duration,
+	) -> (Self, Receiver<Result<()>>) {
+		let (tx, rx) = mpsc::channel();
+		let stop = Arc::new((Mutex::new(false), Condvar::new()));
+		let client = Arc::clone(client);
+		let path = Path::new(path).to_owned();
+		let thread_stop = Arc::clone(&stop);
+		thread::spawn(move || {
+			let mut last = None;
+			loop {
+				let current = modified(&path);
+				if last != Some(current) {
+					last = Some(current);
+					if tx.send(refresh(&client, &path)).is_err() {
+						return;
+					}
+				}
+				// wait for the interval or for the watcher to be dropped
+				let (lock, cvar) = &*thread_stop;
+				let stopped = lock.lock().unwrap_or_else(|e| e.into_inner());
+				let (stopped, _) = cvar
+					.wait_timeout_while(stopped, interval, |stopped| !*stopped)
+					.unwrap_or_else(|e| e.into_inner());
+				if *stopped {
+					return;
+				}
+			}
+		});
+		(Self { stop }, rx)
+	}
+}
+
+impl Drop for AgentSinkWatcher {
+	fn drop(&mut self) {
+		let (lock, cvar) = &*self.stop;
+		*lock.lock().unwrap_or_else(|e| e.into_inner()) = true;
+		cvar.notify_one();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{builder::VaultClientBuilder, transport::mock::Scripted};
+
+	#[test]
+	fn cached_tokens_follow_the_sink() {
+		let path = std::env::temp_dir().join(format!("vault-jwt-agent-{}", std::process::id()));
+		fs::write(&path, "hvs.first").unwrap();
+		let client = Arc::new(
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.agent_sink(path.to_str().unwrap())
+				.transport(Scripted::new())
+				.build()
+				.unwrap(),
+		);
+		assert_eq!(
+			client.login("app").unwrap().client_token.as_str(),
+			"hvs.first"
+		);
+
+		let (watcher, rx) =
+			AgentSinkWatcher::start(&client, path.to_str().unwrap(), Duration::from_millis(10));
+		rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+		// make sure the modification time changes on coarse file systems
+		thread::sleep(Duration::from_millis(20));
+		fs::write(&path, "hvs.second").unwrap();
+		let file = fs::File::options().write(true).open(&path).unwrap();
+		file.set_modified(SystemTime::now() + Duration::from_secs(1))
+			.unwrap();
+		rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+		assert_eq!(
+			client.auth("app").unwrap().client_token.as_str(),
+			"hvs.second"
+		);
+		drop(watcher);
+		fs::remove_file(path).unwrap();
+	}
+}
@@ -0,0 +1,201 @@
+use crate::{
+	auth::Auth,
+	client::{not_found_as_none, VaultClient},
+	error::Result,
+	secret::Secret,
+};
+
+use std::{future::Future, pin::Pin};
+
+/// Future returned by the async methods of `VaultApi`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Operations of a vault client used by applications. `VaultClient` implements it, and code
+/// taking a `&dyn VaultApi` (or a generic `impl VaultApi`) can be given a mock in unit tests
+pub trait VaultApi: Send + Sync {
+	/// Log in for role and return Auth
+	fn login(&self, role: &str) -> Result<Auth>;
+
+	/// Log in asynchronously for role and return Auth
+	fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>>;
+
+	/// Revoke the token of role
+	fn logout(&self, role: &str) -> Result<()>;
+
+	/// Revoke asynchronously the token of role
+	fn logout_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<()>>;
+
+	/// Renew the token of role
+	fn renew_token(&self, role: &str) -> Result<Auth>;
+
+	/// Renew asynchronously the token of role
+	fn renew_token_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>>;
+
+	/// Get a secret with the token of role
+	fn get_secret(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret>;
+
+	/// Get a secret asynchronously with the token of role
+	fn get_secret_async<'a>(
+		&'a self,
+		role: &'a str,
+		method: &'a str,
+		path: &'a str,
+		kwargs: Option<&'a Vec<(&'a str, &'a str)>>,
+	) -> BoxFuture<'a, Result<Secret>>;
+
+	/// Get a secret or None if nothing exists at path
+	fn try_get_secret(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Option<Secret>> {
+		not_found_as_none(self.get_secret(role, method, path, kwargs))
+	}
+
+	/// Get a secret asynchronously or None if nothing exists at path
+	fn try_get_secret_async<'a>(
+		&'a self,
+		role: &'a str,
+		method: &'a str,
+		path: &'a str,
+		kwargs: Option<&'a Vec<(&'a str, &'a str)>>,
+	) -> BoxFuture<'a, Result<Option<Secret>>> {
+		Box::pin(async move {
+			not_found_as_none(self.get_secret_async(role, method, path, kwargs).await)
+		})
+	}
+}
+
+impl VaultApi for VaultClient {
+	fn login(&self, role: &str) -> Result<Auth> {
+		VaultClient::login(self, role)
+	}
+
+	fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+		Box::pin(VaultClient::login_async(self, role))
+	}
+
+	fn logout(&self, role: &str) -> Result<()> {
+		VaultClient::logout(self, role)
+	}
+
+	fn logout_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<()>> {
+		Box::pin(VaultClient::logout_async(self, role))
+	}
+
+	fn renew_token(&self, role: &str) -> Result<Auth> {
+		VaultClient::renew_token(self, role)
+	}
+
+	fn renew_token_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+		Box::pin(VaultClient::renew_token_async(self, role))
+	}
+
+	fn get_secret(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		VaultClient::get_secret(self, role, method, path, kwargs)
+	}
+
+	fn get_secret_async<'a>(
+		&'a self,
+		role: &'a str,
+		method: &'a str,
+		path: &'a str,
+		kwargs: Option<&'a Vec<(&'a str, &'a str)>>,
+	) -> BoxFuture<'a, Result<Secret>> {
+		Box::pin(VaultClient::get_secret_async(
+			self, role, method, path, kwargs,
+		))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::error::Error;
+	use serde_json::json;
+
+	/// mock with a single secret
+	struct Mock;
+
+	impl VaultApi for Mock {
+		fn login(&self, role: &str) -> Result<Auth> {
+			Ok(Auth::new(role, None))
+		}
+
+		fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+			Box::pin(async move { self.login(role) })
+		}
+
+		fn logout(&self, _role: &str) -> Result<()> {
+			Ok(())
+		}
+
+		fn logout_async<'a>(&'a self, _role: &'a str) -> BoxFuture<'a, Result<()>> {
+			Box::pin(async { Ok(()) })
+		}
+
+		fn renew_token(&self, role: &str) -> Result<Auth> {
+			self.login(role)
+		}
+
+		fn renew_token_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+			self.login_async(role)
+		}
+
+		fn get_secret(
+			&self,
+			_role: &str,
+			_method: &str,
+			path: &str,
+			_kwargs: Option<&Vec<(&str, &str)>>,
+		) -> Result<Secret> {
+			match path {
+				"secret/app" => Ok(Secret::new(json!({"password": "hunter2"}), None)),
+				path => Err(Error::NotFound {
+					path: path.to_owned(),
+					errors: Vec::new(),
+				}),
+			}
+		}
+
+		fn get_secret_async<'a>(
+			&'a self,
+			role: &'a str,
+			method: &'a str,
+			path: &'a str,
+			kwargs: Option<&'a Vec<(&'a str, &'a str)>>,
+		) -> BoxFuture<'a, Result<Secret>> {
+			Box::pin(async move { self.get_secret(role, method, path, kwargs) })
+		}
+	}
+
+	/// application code using the api
+	fn password(vault: &dyn VaultApi) -> Result<Option<String>> {
+		Ok(vault
+			.try_get_secret("app", "GET", "secret/app", None)?
+			.and_then(|secret| secret.value["password"].as_str().map(str::to_owned)))
+	}
+
+	#[test]
+	fn mock_is_injected() {
+		assert_eq!(password(&Mock).unwrap().as_deref(), Some("hunter2"));
+		assert!(Mock
+			.try_get_secret("app", "GET", "secret/other", None)
+			.unwrap()
+			.is_none());
+	}
+}
@@ -0,0 +1,153 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// An audit device as returned by `sys/audit`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AuditDevice {
+	/// `file`, `syslog` or `socket`
+	#[serde(rename = "type")]
+	pub device_type: String,
+	#[serde(default)]
+	pub description: String,
+	#[serde(default)]
+	pub options: HashMap<String, String>,
+	#[serde(default)]
+	pub local: bool,
+	#[serde(default)]
+	pub path: String,
+}
+
+/// Return the path of an audit device
+fn audit_path(path: &str) -> String {
+	format!("sys/audit/{}", path.trim_matches('/'))
+}
+
+/// Body of an audit device activation
+fn enable_body(device_type: &str, options: &HashMap<String, String>) -> Value {
+	json!({ "type": device_type, "options": options })
+}
+
+/// Extract the audit devices from a response of `sys/audit`
+fn devices_from_value(mut value: Value) -> Result<HashMap<String, AuditDevice>> {
+	// older servers don't wrap the devices in data
+	let devices = match value.get_mut("data").map(Value::take) {
+		Some(data) if !data.is_null() => data,
+		_ => value,
+	};
+	serde_json::from_value(devices).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Extract the hash from a response of `sys/audit-hash/<path>`
+fn hash_from_value(value: &Value) -> Result<String> {
+	value["data"]["hash"]
+		.as_str()
+		.or_else(|| value["hash"].as_str())
+		.map(|s| s.to_owned())
+		.ok_or(Error::MissingField("hash"))
+}
+
+impl VaultClient {
+	/// Return the audit devices by path (with a trailing slash)
+	pub fn list_audit_devices(&self, role: &str) -> Result<HashMap<String, AuditDevice>> {
+		devices_from_value(self.request(role, "GET", "sys/audit", None)?)
+	}
+
+	/// Return asynchronously the audit devices by path
+	pub async fn list_audit_devices_async(
+		&self,
+		role: &str,
+	) -> Result<HashMap<String, AuditDevice>> {
+		devices_from_value(self.request_async(role, "GET", "sys/audit", None).await?)
+	}
+
+	/// Enable an audit device of device_type at path with its options (ex: `file_path` for
+	/// the `file` type)
+	pub fn enable_audit(
+		&self,
+		role: &str,
+		path: &str,
+		device_type: &str,
+		options: &HashMap<String, String>,
+	) -> Result<()> {
+		let body = enable_body(device_type, options);
+		self.request(role, "PUT", &audit_path(path), Some(body))
+			.map(|_| ())
+	}
+
+	/// Enable asynchronously an audit device of device_type at path
+	pub async fn enable_audit_async(
+		&self,
+		role: &str,
+		path: &str,
+		device_type: &str,
+		options: &HashMap<String, String>,
+	) -> Result<()> {
+		let body = enable_body(device_type, options);
+		self.request_async(role, "PUT", &audit_path(path), Some(body))
+			.await
+			.map(|_| ())
+	}
+
+	/// Disable the audit device at path
+	pub fn disable_audit(&self, role: &str, path: &str) -> Result<()> {
+		self.request(role, "DELETE", &audit_path(path), None)
+			.map(|_| ())
+	}
+
+	/// Disable asynchronously the audit device at path
+	pub async fn disable_audit_async(&self, role: &str, path: &str) -> Result<()> {
+		self.request_async(role, "DELETE", &audit_path(path), None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Hash input with the salt of the audit device at path, to compare it with a hashed value
+	/// of the audit log
+	pub fn audit_hash(&self, role: &str, path: &str, input: &str) -> Result<String> {
+		let path = format!("sys/audit-hash/{}", path.trim_matches('/'));
+		let value = self.request(role, "POST", &path, Some(json!({ "input": input })))?;
+		hash_from_value(&value)
+	}
+
+	/// Hash asynchronously input with the salt of the audit device at path
+	pub async fn audit_hash_async(&self, role: &str, path: &str, input: &str) -> Result<String> {
+		let path = format!("sys/audit-hash/{}", path.trim_matches('/'));
+		let value = self
+			.request_async(role, "POST", &path, Some(json!({ "input": input })))
+			.await?;
+		hash_from_value(&value)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn devices_from_response() {
+		let devices = devices_from_value(json!({
+			"file/": {
+				"type": "file",
+				"description": "",
+				"options": {"file_path": "/var/log/vault/audit.log"},
+				"local": false,
+				"path": "file/"
+			}
+		}))
+		.unwrap();
+		assert_eq!(
+			devices["file/"].options["file_path"],
+			"/var/log/vault/audit.log"
+		);
+		assert_eq!(
+			hash_from_value(&json!({"data": {"hash": "hmac-sha256:08ba35"}})).unwrap(),
+			"hmac-sha256:08ba35"
+		);
+	}
+}
@@ -1,29 +1,93 @@
-use crate::lease::Lease;
+use crate::{
+	error::{Error, Result},
+	lease::Lease,
+	redact::{parse_error, Redacted},
+};
 
-use std::time::Duration;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt, mem, sync::Arc, time::Duration};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Client token shared by the cache and the requests using it rather than copied for each of
+/// them. It is zeroed when the last of them is dropped
+pub type Token = Arc<Zeroizing<String>>;
+
+/// Login response as returned by vault
+#[derive(Debug, Deserialize)]
+pub struct AuthResponse {
+	pub auth: Option<AuthInfo>,
+}
+
+/// Auth part of a login response
+#[derive(Deserialize)]
+pub struct AuthInfo {
+	pub client_token: String,
+	#[serde(default)]
+	pub accessor: String,
+	#[serde(default)]
+	pub policies: Vec<String>,
+	#[serde(default)]
+	pub lease_duration: u64,
+	#[serde(default)]
+	pub renewable: bool,
+	#[serde(default)]
+	pub token_type: String,
+	#[serde(default)]
+	pub orphan: bool,
+	/// metadata set by the auth method (ex: `service_account_name` for kubernetes)
+	pub metadata: Option<HashMap<String, String>>,
+}
 
 /// tie an auth token to a lease
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct Auth {
-	pub client_token: String,
+	pub client_token: Token,
 	pub lease: Option<Lease>,
+	pub accessor: String,
+	pub policies: Vec<String>,
+	/// `service` or `batch`
+	pub token_type: String,
+	pub orphan: bool,
+	/// metadata set by the auth method at login
+	pub metadata: HashMap<String, String>,
 }
 
 impl Auth {
 	/// create new Auth with an optional duration
 	pub fn new(token: &str, dur: Option<Duration>) -> Self {
 		Self {
-			client_token: token.to_owned(),
+			client_token: Arc::new(Zeroizing::new(token.to_owned())),
 			lease: dur.and_then(|dur| Some(Lease::new(dur))),
+			accessor: String::new(),
+			policies: Vec::new(),
+			token_type: String::new(),
+			orphan: false,
+			metadata: HashMap::new(),
 		}
 	}
 
+	/// extract an Auth from a login response. Fails if the response has no client token
+	pub(crate) fn from_value(value: Value) -> Result<Self> {
+		let response: AuthResponse = serde_json::from_value(value).map_err(parse_error)?;
+		let info = response
+			.auth
+			.filter(|info| !info.client_token.is_empty())
+			.ok_or(Error::MissingField("client_token"))?;
+		Ok(Self::from(info))
+	}
+
 	/// check if the token is still valid
 	pub fn is_valid(&self) -> bool {
 		!self.client_token.is_empty() && self.lease.is_none()
 			|| self.lease.as_ref().filter(|l| l.is_valid()).is_some()
 	}
 
+	/// check if the token was given policy at login
+	pub fn has_policy(&self, policy: &str) -> bool {
+		self.policies.iter().any(|p| p == policy)
+	}
+
 	/// check if the token needs a renewal
 	pub fn to_renew(&self) -> bool {
 		self.lease.as_ref().filter(|l| l.to_renew()).is_some()
@@ -38,8 +102,119 @@ impl Auth {
 	}
 }
 
+impl fmt::Debug for AuthInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AuthInfo")
+			.field("client_token", &Redacted(&self.client_token))
+			.field("accessor", &self.accessor)
+			.field("policies", &self.policies)
+			.field("lease_duration", &self.lease_duration)
+			.field("renewable", &self.renewable)
+			.field("token_type", &self.token_type)
+			.field("orphan", &self.orphan)
+			.field("metadata", &self.metadata)
+			.finish()
+	}
+}
+
+/// The client token is redacted
+impl fmt::Debug for Auth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Auth")
+			.field("client_token", &Redacted(&self.client_token))
+			.field("lease", &self.lease)
+			.field("accessor", &self.accessor)
+			.field("policies", &self.policies)
+			.field("token_type", &self.token_type)
+			.field("orphan", &self.orphan)
+			.field("metadata", &self.metadata)
+			.finish()
+	}
+}
+
+/// Display the redacted token and its policies
+impl fmt::Display for Auth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"token {} with policies [{}]",
+			Redacted(&self.client_token),
+			self.policies.join(", ")
+		)
+	}
+}
+
+/// Scrub the token from memory
+impl Drop for AuthInfo {
+	fn drop(&mut self) {
+		self.client_token.zeroize();
+	}
+}
+
+impl From<AuthInfo> for Auth {
+	fn from(mut info: AuthInfo) -> Self {
+		Self {
+			lease: if info.renewable {
+				Some(Lease::new(Duration::from_secs(info.lease_duration)))
+			} else {
+				None
+			},
+			client_token: Arc::new(Zeroizing::new(mem::take(&mut info.client_token))),
+			accessor: mem::take(&mut info.accessor),
+			policies: mem::take(&mut info.policies),
+			token_type: mem::take(&mut info.token_type),
+			orphan: info.orphan,
+			metadata: info.metadata.take().unwrap_or_default(),
+		}
+	}
+}
+
+/// Token renewed for an increment
+#[derive(Debug, Clone)]
+pub struct Renewal {
+	pub auth: Auth,
+	/// the ttl granted is shorter than the increment because of the max ttl of the token: it
+	/// can't be renewed beyond and a new login is needed before it expires
+	pub capped: bool,
+}
+
+#[test]
+fn auth_from_login_response() {
+	let auth = Auth::from_value(serde_json::json!({
+		"auth": {
+			"client_token": "s.token",
+			"accessor": "accessor",
+			"policies": ["default", "app"],
+			"lease_duration": 3600,
+			"renewable": true,
+			"token_type": "service",
+			"orphan": true,
+			"metadata": {"role": "app", "service_account_name": "app"}
+		}
+	}))
+	.unwrap();
+	assert_eq!(auth.duration(), Some(Duration::from_secs(3600)));
+	assert_eq!(auth.policies, vec!["default", "app"]);
+	assert!(auth.has_policy("app") && !auth.has_policy("admin"));
+	assert_eq!(auth.metadata["service_account_name"], "app");
+	assert!(matches!(
+		Auth::from_value(serde_json::json!({"auth": null, "data": {}})),
+		Err(Error::MissingField("client_token"))
+	));
+}
+
 #[test]
 fn empty_token_is_invalid() {
 	let auth = Auth::new("", None);
 	assert_eq!(auth.is_valid(), false);
 }
+
+#[test]
+fn auth_debug_redacts_token() {
+	let auth = Auth::new("hvs.token", None);
+	assert!(!format!("{:?}", auth).contains("hvs.token"));
+	assert_eq!(
+		auth.to_string(),
+		"token <redacted 9 bytes sha256:1e4f1541> with policies []"
+	);
+}
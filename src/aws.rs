@@ -0,0 +1,536 @@
+use crate::{
+	api::BoxFuture,
+	auth::Auth,
+	base64,
+	client::VaultClient,
+	error::{Error, Result},
+	method::{login_path, AuthMethod, Login},
+	sha256::{hex, hmac_sha256, sha256},
+	transport::{fetch, fetch_async},
+};
+
+use http::Request;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::{
+	collections::BTreeMap,
+	env, fmt, fs,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Body of the signed sts request
+const GET_CALLER_IDENTITY: &str = "Action=GetCallerIdentity&Version=2011-06-15";
+
+/// Instance metadata service of ec2
+const IMDS_URL: &str = "http://169.254.169.254/latest";
+
+/// AWS credentials used to sign the sts request
+#[derive(Clone)]
+pub struct AwsCredentials {
+	pub access_key_id: String,
+	pub secret_access_key: String,
+	pub session_token: Option<String>,
+}
+
+/// Don't leak the secret parts of the credentials
+impl fmt::Debug for AwsCredentials {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AwsCredentials")
+			.field("access_key_id", &self.access_key_id)
+			.finish()
+	}
+}
+
+/// credentials part of an sts json response
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StsCredentials {
+	access_key_id: String,
+	secret_access_key: String,
+	session_token: String,
+}
+
+impl AwsCredentials {
+	pub fn new(access_key_id: &str, secret_access_key: &str, session_token: Option<&str>) -> Self {
+		Self {
+			access_key_id: access_key_id.to_owned(),
+			secret_access_key: secret_access_key.to_owned(),
+			session_token: session_token.map(|s| s.to_owned()),
+		}
+	}
+
+	/// Read the credentials from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+	/// `AWS_SESSION_TOKEN`
+	pub fn from_env() -> Option<Self> {
+		let access_key_id = env_var("AWS_ACCESS_KEY_ID")?;
+		let secret_access_key = env_var("AWS_SECRET_ACCESS_KEY")?;
+		Some(Self {
+			access_key_id,
+			secret_access_key,
+			session_token: env_var("AWS_SESSION_TOKEN"),
+		})
+	}
+
+	/// Exchange the web identity token of `AWS_WEB_IDENTITY_TOKEN_FILE` for temporary
+	/// credentials of `AWS_ROLE_ARN` (IAM roles for service accounts on EKS)
+	pub fn from_web_identity() -> Result<Self> {
+		let (status, body) = fetch(web_identity_request()?)?;
+		credentials_from_sts(status, &body)
+	}
+
+	/// Exchange asynchronously the web identity token for temporary credentials
+	pub async fn from_web_identity_async() -> Result<Self> {
+		let (status, body) = fetch_async(web_identity_request()?).await?;
+		credentials_from_sts(status, &body)
+	}
+
+	/// Return the credentials of the environment or of the web identity
+	pub fn resolve() -> Result<Self> {
+		match Self::from_env() {
+			Some(credentials) => Ok(credentials),
+			None => Self::from_web_identity(),
+		}
+	}
+
+	/// Return asynchronously the credentials of the environment or of the web identity
+	pub async fn resolve_async() -> Result<Self> {
+		match Self::from_env() {
+			Some(credentials) => Ok(credentials),
+			None => Self::from_web_identity_async().await,
+		}
+	}
+}
+
+/// Return the value of an environment variable if it is set and not empty
+fn env_var(name: &str) -> Option<String> {
+	env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Percent encode a query parameter value
+fn url_encode(value: &str) -> String {
+	value
+		.bytes()
+		.map(|b| match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+				(b as char).to_string()
+			}
+			b => format!("%{:02X}", b),
+		})
+		.collect()
+}
+
+/// Return the sts endpoint of a region
+fn sts_url(region: &str) -> String {
+	if region == "us-east-1" {
+		"https://sts.amazonaws.com/".to_owned()
+	} else {
+		format!("https://sts.{}.amazonaws.com/", region)
+	}
+}
+
+/// Build the (unsigned) AssumeRoleWithWebIdentity request
+fn web_identity_request() -> Result<Request<String>> {
+	let token_file = env_var("AWS_WEB_IDENTITY_TOKEN_FILE")
+		.ok_or(Error::MissingField("AWS_WEB_IDENTITY_TOKEN_FILE"))?;
+	let role_arn = env_var("AWS_ROLE_ARN").ok_or(Error::MissingField("AWS_ROLE_ARN"))?;
+	let session_name = env_var("AWS_ROLE_SESSION_NAME").unwrap_or_else(|| "vault-jwt".to_owned());
+	let token = fs::read_to_string(token_file)?;
+	let region = env_var("AWS_REGION").unwrap_or_else(|| "us-east-1".to_owned());
+	let uri = format!(
+		"{}?Action=AssumeRoleWithWebIdentity&Version=2011-06-15&RoleArn={}&RoleSessionName={}&WebIdentityToken={}",
+		sts_url(&region),
+		url_encode(&role_arn),
+		url_encode(&session_name),
+		url_encode(token.trim())
+	);
+	Request::get(uri)
+		.header("Accept", "application/json")
+		.body(String::new())
+		.map_err(|e| Error::HttpError { source: e })
+}
+
+/// Extract the credentials from an AssumeRoleWithWebIdentity json response
+fn credentials_from_sts(status: http::StatusCode, body: &str) -> Result<AwsCredentials> {
+	if !status.is_success() {
+		return Err(Error::from_status(
+			status,
+			"sts",
+			vec![body.trim().to_owned()],
+		));
+	}
+	let mut value: Value =
+		serde_json::from_str(body).map_err(|e| Error::ParseError { source: e })?;
+	let credentials: StsCredentials = serde_json::from_value(
+		value["AssumeRoleWithWebIdentityResponse"]["AssumeRoleWithWebIdentityResult"]
+			["Credentials"]
+			.take(),
+	)
+	.map_err(|e| Error::ParseError { source: e })?;
+	Ok(AwsCredentials {
+		access_key_id: credentials.access_key_id,
+		secret_access_key: credentials.secret_access_key,
+		session_token: Some(credentials.session_token),
+	})
+}
+
+/// Format a time as `YYYYMMDDTHHMMSSZ`
+fn amz_date(time: SystemTime) -> String {
+	let secs = time
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or(0);
+	// civil date from days since the epoch
+	let z = (secs / 86400) as i64 + 719_468;
+	let era = z / 146_097;
+	let doe = z - era * 146_097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+	let rem = secs % 86400;
+	format!(
+		"{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+		year,
+		month,
+		day,
+		rem / 3600,
+		rem % 3600 / 60,
+		rem % 60
+	)
+}
+
+/// Build the canonical request of signature version 4 (headers names must be lower case)
+fn canonical_request(
+	method: &str,
+	query: &str,
+	headers: &BTreeMap<String, String>,
+	body: &str,
+) -> String {
+	let canonical_headers: String = headers
+		.iter()
+		.map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+		.collect();
+	let signed_headers = headers.keys().cloned().collect::<Vec<_>>().join(";");
+	format!(
+		"{}\n/\n{}\n{}\n{}\n{}",
+		method,
+		query,
+		canonical_headers,
+		signed_headers,
+		hex(&sha256(body.as_bytes()))
+	)
+}
+
+/// Compute the signature version 4 of a canonical request
+fn sign(
+	secret_access_key: &str,
+	amz_date: &str,
+	region: &str,
+	service: &str,
+	canonical_request: &str,
+) -> String {
+	let date = &amz_date[..8];
+	let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+	let string_to_sign = format!(
+		"AWS4-HMAC-SHA256\n{}\n{}\n{}",
+		amz_date,
+		scope,
+		hex(&sha256(canonical_request.as_bytes()))
+	);
+	let key = hmac_sha256(
+		format!("AWS4{}", secret_access_key).as_bytes(),
+		date.as_bytes(),
+	);
+	let key = hmac_sha256(&key, region.as_bytes());
+	let key = hmac_sha256(&key, service.as_bytes());
+	let key = hmac_sha256(&key, b"aws4_request");
+	hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+}
+
+/// AWS IAM auth method. Logs in with an sts GetCallerIdentity request signed with the AWS
+/// credentials, so that vault can check the identity with AWS without knowing the credentials
+#[derive(Debug)]
+pub struct AwsIamAuth {
+	login_path: String,
+	credentials: Option<AwsCredentials>,
+	region: String,
+	server_id: Option<String>,
+}
+
+impl AwsIamAuth {
+	/// create an aws auth method mounted on `auth/aws/`
+	pub fn new() -> Self {
+		Self::with_mount("aws")
+	}
+
+	/// create an aws auth method given its mount point. Without explicit credentials they are
+	/// resolved at each login (see `AwsCredentials::resolve`)
+	pub fn with_mount(mount: &str) -> Self {
+		Self {
+			login_path: login_path(mount),
+			credentials: None,
+			region: "us-east-1".to_owned(),
+			server_id: None,
+		}
+	}
+
+	/// sign the request with the given credentials
+	pub fn credentials(mut self, credentials: AwsCredentials) -> Self {
+		self.credentials = Some(credentials);
+		self
+	}
+
+	/// use the sts endpoint of region (it must match the `sts_endpoint` configured in vault)
+	pub fn region(mut self, region: &str) -> Self {
+		self.region = region.to_owned();
+		self
+	}
+
+	/// value of the `X-Vault-AWS-IAM-Server-ID` header if required by the vault configuration
+	pub fn server_id(mut self, server_id: &str) -> Self {
+		self.server_id = Some(server_id.to_owned());
+		self
+	}
+
+	/// build the login body for role with a request signed at time
+	fn login_body(&self, credentials: &AwsCredentials, role: &str, time: SystemTime) -> Value {
+		let url = sts_url(&self.region);
+		let host = url.trim_start_matches("https://").trim_end_matches('/');
+		let amz_date = amz_date(time);
+		let mut headers = BTreeMap::new();
+		headers.insert(
+			"content-type".to_owned(),
+			"application/x-www-form-urlencoded; charset=utf-8".to_owned(),
+		);
+		headers.insert("host".to_owned(), host.to_owned());
+		headers.insert("x-amz-date".to_owned(), amz_date.clone());
+		if let Some(ref token) = credentials.session_token {
+			headers.insert("x-amz-security-token".to_owned(), token.clone());
+		}
+		if let Some(ref server_id) = self.server_id {
+			headers.insert("x-vault-aws-iam-server-id".to_owned(), server_id.clone());
+		}
+		let signature = sign(
+			&credentials.secret_access_key,
+			&amz_date,
+			&self.region,
+			"sts",
+			&canonical_request("POST", "", &headers, GET_CALLER_IDENTITY),
+		);
+		let authorization = format!(
+			"AWS4-HMAC-SHA256 Credential={}/{}/{}/sts/aws4_request, SignedHeaders={}, Signature={}",
+			credentials.access_key_id,
+			&amz_date[..8],
+			self.region,
+			headers.keys().cloned().collect::<Vec<_>>().join(";"),
+			signature
+		);
+		let mut request_headers: Map<String, Value> =
+			headers.into_iter().map(|(k, v)| (k, json!([v]))).collect();
+		request_headers.insert("authorization".to_owned(), json!([authorization]));
+		json!({
+			"role": role,
+			"iam_http_request_method": "POST",
+			"iam_request_url": base64::encode(url.as_bytes()),
+			"iam_request_body": base64::encode(GET_CALLER_IDENTITY.as_bytes()),
+			"iam_request_headers": base64::encode(Value::Object(request_headers).to_string().as_bytes()),
+		})
+	}
+}
+
+impl Default for AwsIamAuth {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl AuthMethod for AwsIamAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		let credentials = match self.credentials {
+			Some(ref credentials) => credentials.clone(),
+			None => AwsCredentials::resolve()?,
+		};
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: self.login_body(&credentials, role, SystemTime::now()),
+		})
+	}
+
+	/// resolve the credentials without blocking
+	fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Login>> {
+		Box::pin(async move {
+			let credentials = match self.credentials {
+				Some(ref credentials) => credentials.clone(),
+				None => AwsCredentials::resolve_async().await?,
+			};
+			Ok(Login::Request {
+				path: self.login_path.clone(),
+				body: self.login_body(&credentials, role, SystemTime::now()),
+			})
+		})
+	}
+}
+
+/// AWS EC2 auth method using the pkcs7 signed identity document of the instance
+#[derive(Debug)]
+pub struct AwsEc2Auth {
+	login_path: String,
+	pkcs7: String,
+	nonce: Option<String>,
+}
+
+impl AwsEc2Auth {
+	/// create an ec2 auth method given its mount point, the pkcs7 identity document (see
+	/// `fetch_pkcs7`) and the nonce used for reauthentication
+	pub fn new(mount: &str, pkcs7: &str, nonce: Option<&str>) -> Self {
+		Self {
+			login_path: login_path(mount),
+			pkcs7: pkcs7.replace('\n', ""),
+			nonce: nonce.map(|s| s.to_owned()),
+		}
+	}
+
+	/// Fetch the pkcs7 identity document from the instance metadata service (IMDSv2)
+	pub fn fetch_pkcs7() -> Result<String> {
+		let (_, token) = fetch(imds_token_request()?)?;
+		Ok(fetch(pkcs7_request(&token)?)?.1)
+	}
+
+	/// Fetch asynchronously the pkcs7 identity document from the instance metadata service
+	pub async fn fetch_pkcs7_async() -> Result<String> {
+		let (_, token) = fetch_async(imds_token_request()?).await?;
+		Ok(fetch_async(pkcs7_request(&token)?).await?.1)
+	}
+}
+
+/// Build the request of a session token of the instance metadata service
+fn imds_token_request() -> Result<Request<String>> {
+	Request::put(format!("{}/api/token", IMDS_URL))
+		.header("X-aws-ec2-metadata-token-ttl-seconds", "60")
+		.body(String::new())
+		.map_err(|e| Error::HttpError { source: e })
+}
+
+/// Build the request of the pkcs7 identity document with a session token
+fn pkcs7_request(token: &str) -> Result<Request<String>> {
+	Request::get(format!("{}/dynamic/instance-identity/pkcs7", IMDS_URL))
+		.header("X-aws-ec2-metadata-token", token.trim())
+		.body(String::new())
+		.map_err(|e| Error::HttpError { source: e })
+}
+
+impl AuthMethod for AwsEc2Auth {
+	fn login(&self, role: &str) -> Result<Login> {
+		let mut body = json!({"role": role, "pkcs7": &self.pkcs7});
+		if let Some(ref nonce) = self.nonce {
+			body["nonce"] = Value::from(nonce.as_str());
+		}
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body,
+		})
+	}
+}
+
+impl VaultClient {
+	/// Log in to the vault client for role with the aws auth method mounted on `auth/aws` using
+	/// the credentials of the environment or the web identity of the pod, and return Auth
+	pub fn login_aws_iam(&self, role: &str) -> Result<Auth> {
+		self.login_with(&AwsIamAuth::new(), role)
+	}
+
+	/// Log in asynchronously to the vault client for role with the aws auth method and return Auth
+	pub async fn login_aws_iam_async(&self, role: &str) -> Result<Auth> {
+		self.login_with_async(&AwsIamAuth::new(), role).await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+	use std::time::Duration;
+
+	#[test]
+	fn amz_date_format() {
+		let time = UNIX_EPOCH + Duration::from_secs(1_440_938_160);
+		assert_eq!(amz_date(time), "20150830T123600Z");
+		assert_eq!(amz_date(UNIX_EPOCH), "19700101T000000Z");
+	}
+
+	#[test]
+	fn sigv4_reference_example() {
+		// example of the aws signature version 4 documentation
+		let mut headers = BTreeMap::new();
+		headers.insert(
+			"content-type".to_owned(),
+			"application/x-www-form-urlencoded; charset=utf-8".to_owned(),
+		);
+		headers.insert("host".to_owned(), "iam.amazonaws.com".to_owned());
+		headers.insert("x-amz-date".to_owned(), "20150830T123600Z".to_owned());
+		let request = canonical_request("GET", "Action=ListUsers&Version=2010-05-08", &headers, "");
+		assert_eq!(
+			hex(&sha256(request.as_bytes())),
+			"f536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59"
+		);
+		assert_eq!(
+			sign(
+				"wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+				"20150830T123600Z",
+				"us-east-1",
+				"iam",
+				&request
+			),
+			"5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+		);
+	}
+
+	#[test]
+	fn iam_login_body() {
+		let credentials = AwsCredentials::new("AKID", "secret", Some("session"));
+		let body = AwsIamAuth::new().server_id("vault.example.com").login_body(
+			&credentials,
+			"app",
+			UNIX_EPOCH,
+		);
+		assert_eq!(body["role"], "app");
+		let headers = base64::decode(body["iam_request_headers"].as_str().unwrap()).unwrap();
+		let headers: Value = serde_json::from_slice(&headers).unwrap();
+		assert_eq!(headers["x-amz-security-token"][0], "session");
+		assert!(headers["authorization"][0].as_str().unwrap().starts_with(
+			"AWS4-HMAC-SHA256 Credential=AKID/19700101/us-east-1/sts/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-vault-aws-iam-server-id, Signature="
+		));
+	}
+
+	#[test]
+	fn async_login_signs_the_given_credentials() {
+		let transport = Scripted::new();
+		transport.on(
+			"POST",
+			"auth/aws/login",
+			200,
+			json!({"auth": {"client_token": "s.aws", "lease_duration": 60, "renewable": true}}),
+		);
+		let credentials = AwsCredentials::new("AKID", "secret", None);
+		let auth = futures_lite::future::block_on(
+			transport
+				.client()
+				.login_with_async(&AwsIamAuth::new().credentials(credentials), "app"),
+		)
+		.unwrap();
+		assert_eq!(auth.client_token.as_str(), "s.aws");
+		let body = transport.body("POST auth/aws/login").unwrap();
+		assert_eq!(body["role"], "app");
+		assert_eq!(body["iam_http_request_method"], "POST");
+	}
+
+	#[test]
+	fn url_encoding() {
+		assert_eq!(
+			url_encode("arn:aws:iam::123:role/app"),
+			"arn%3Aaws%3Aiam%3A%3A123%3Arole%2Fapp"
+		);
+	}
+}
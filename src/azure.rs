@@ -0,0 +1,238 @@
+use crate::{
+	auth::Auth,
+	client::VaultClient,
+	error::{Error, Result},
+	method::{login_path, AuthMethod, Login},
+	transport::{fetch, fetch_async},
+};
+
+use http::Request;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Instance metadata service of azure
+const IMDS_URL: &str = "http://169.254.169.254/metadata";
+
+/// Default resource of the managed identity token expected by vault
+pub const DEFAULT_RESOURCE: &str = "https://management.azure.com/";
+
+/// Azure auth method using a managed identity token and the instance metadata
+pub struct AzureAuth {
+	login_path: String,
+	jwt: String,
+	subscription_id: String,
+	resource_group_name: String,
+	vm_name: Option<String>,
+	vmss_name: Option<String>,
+}
+
+/// token response of the managed identity endpoint
+#[derive(Deserialize)]
+struct TokenResponse {
+	access_token: String,
+}
+
+/// compute part of the instance metadata
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Compute {
+	subscription_id: String,
+	resource_group_name: String,
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	vm_scale_set_name: String,
+}
+
+/// Build a request to the instance metadata service
+fn imds_request(path: &str) -> Result<Request<String>> {
+	Request::get(format!("{}/{}", IMDS_URL, path))
+		.header("Metadata", "true")
+		.body(String::new())
+		.map_err(|e| Error::HttpError { source: e })
+}
+
+/// Path of the managed identity token for resource
+fn token_path(resource: &str) -> String {
+	format!(
+		"identity/oauth2/token?api-version=2018-02-01&resource={}",
+		resource
+	)
+}
+
+/// Path of the instance compute metadata
+const COMPUTE_PATH: &str = "instance/compute?api-version=2021-02-01";
+
+/// Parse a json response of the instance metadata service
+fn parse<T: serde::de::DeserializeOwned>(status: http::StatusCode, body: &str) -> Result<T> {
+	if !status.is_success() {
+		return Err(Error::from_status(
+			status,
+			IMDS_URL,
+			vec![body.trim().to_owned()],
+		));
+	}
+	serde_json::from_str(body).map_err(|e| Error::ParseError { source: e })
+}
+
+/// The managed identity token is not shown
+impl fmt::Debug for AzureAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AzureAuth")
+			.field("login_path", &self.login_path)
+			.field("subscription_id", &self.subscription_id)
+			.field("resource_group_name", &self.resource_group_name)
+			.field("vm_name", &self.vm_name)
+			.field("vmss_name", &self.vmss_name)
+			.finish()
+	}
+}
+
+/// Scrub the managed identity token from memory
+impl Drop for AzureAuth {
+	fn drop(&mut self) {
+		self.jwt.zeroize();
+	}
+}
+
+impl AzureAuth {
+	/// create an azure auth method given its mount point, a managed identity token and the
+	/// location of the instance
+	pub fn new(
+		mount: &str,
+		jwt: &str,
+		subscription_id: &str,
+		resource_group_name: &str,
+		vm_name: Option<&str>,
+		vmss_name: Option<&str>,
+	) -> Self {
+		Self {
+			login_path: login_path(mount),
+			jwt: jwt.to_owned(),
+			subscription_id: subscription_id.to_owned(),
+			resource_group_name: resource_group_name.to_owned(),
+			vm_name: vm_name.map(|s| s.to_owned()),
+			vmss_name: vmss_name.map(|s| s.to_owned()),
+		}
+	}
+
+	/// build from a token and compute metadata
+	fn from_compute(mount: &str, token: TokenResponse, compute: Compute) -> Self {
+		let vm_name = Some(compute.name).filter(|s| !s.is_empty());
+		let vmss_name = Some(compute.vm_scale_set_name).filter(|s| !s.is_empty());
+		Self {
+			login_path: login_path(mount),
+			jwt: token.access_token,
+			subscription_id: compute.subscription_id,
+			resource_group_name: compute.resource_group_name,
+			// vault checks the scale set when it is given
+			vm_name: if vmss_name.is_some() { None } else { vm_name },
+			vmss_name,
+		}
+	}
+
+	/// create an azure auth method given its mount point with a managed identity token for
+	/// resource and the location of the instance fetched from the instance metadata service
+	pub fn from_metadata(mount: &str, resource: &str) -> Result<Self> {
+		let (status, body) = fetch(imds_request(&token_path(resource))?)?;
+		let token = parse(status, &body)?;
+		let (status, body) = fetch(imds_request(COMPUTE_PATH)?)?;
+		let compute = parse(status, &body)?;
+		Ok(Self::from_compute(mount, token, compute))
+	}
+
+	/// create asynchronously an azure auth method from the instance metadata service
+	pub async fn from_metadata_async(mount: &str, resource: &str) -> Result<Self> {
+		let (status, body) = fetch_async(imds_request(&token_path(resource))?).await?;
+		let token = parse(status, &body)?;
+		let (status, body) = fetch_async(imds_request(COMPUTE_PATH)?).await?;
+		let compute = parse(status, &body)?;
+		Ok(Self::from_compute(mount, token, compute))
+	}
+}
+
+impl AuthMethod for AzureAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		let mut body = json!({
+			"role": role,
+			"jwt": &self.jwt,
+			"subscription_id": &self.subscription_id,
+			"resource_group_name": &self.resource_group_name,
+		});
+		if let Some(ref vm_name) = self.vm_name {
+			body["vm_name"] = Value::from(vm_name.as_str());
+		}
+		if let Some(ref vmss_name) = self.vmss_name {
+			body["vmss_name"] = Value::from(vmss_name.as_str());
+		}
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body,
+		})
+	}
+}
+
+impl VaultClient {
+	/// Log in to the vault client for role with the azure auth method mounted on `auth/azure`
+	/// using the managed identity of the instance, and return Auth
+	pub fn login_azure(&self, role: &str) -> Result<Auth> {
+		// only query the instance metadata service when a login is needed
+		if let Some(auth) = self.logged_auth(role) {
+			return Ok(auth);
+		}
+		self.login_with(&AzureAuth::from_metadata("azure", DEFAULT_RESOURCE)?, role)
+	}
+
+	/// Log in asynchronously to the vault client for role with the azure auth method and return Auth
+	pub async fn login_azure_async(&self, role: &str) -> Result<Auth> {
+		if let Some(auth) = self.logged_auth(role) {
+			return Ok(auth);
+		}
+		let method = AzureAuth::from_metadata_async("azure", DEFAULT_RESOURCE).await?;
+		self.login_with_async(&method, role).await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+
+	#[test]
+	fn cached_role_skips_metadata() {
+		let client = Scripted::new().client();
+		assert_eq!(
+			client
+				.login_azure(TOKEN_ROLE)
+				.unwrap()
+				.client_token
+				.as_str(),
+			"s.token"
+		);
+	}
+
+	#[test]
+	fn azure_login_body_from_metadata() {
+		let token: TokenResponse = parse(
+			http::StatusCode::OK,
+			r#"{"access_token": "eyJ", "expires_in": "3599", "token_type": "Bearer"}"#,
+		)
+		.unwrap();
+		let compute: Compute = parse(
+			http::StatusCode::OK,
+			r#"{"name": "aks-nodepool1-0", "resourceGroupName": "rg", "subscriptionId": "sub", "vmScaleSetName": "aks-nodepool1"}"#,
+		)
+		.unwrap();
+		let method = AzureAuth::from_compute("azure", token, compute);
+		match method.login("app").unwrap() {
+			Login::Request { path, body } => {
+				assert_eq!(path, "/auth/azure/login");
+				assert_eq!(body["vmss_name"], "aks-nodepool1");
+				assert!(body.get("vm_name").is_none());
+			}
+			Login::Token(_) => panic!("azure login needs a request"),
+		}
+	}
+}
@@ -0,0 +1,86 @@
+use crate::{client::VaultClient, error::Result, redact::Redacted, secret::TypedSecret};
+
+use serde::Deserialize;
+use std::fmt;
+use zeroize::ZeroizeOnDrop;
+
+/// Service principal credentials of the azure secrets engine
+#[derive(Deserialize, ZeroizeOnDrop)]
+pub struct AzureCreds {
+	pub client_id: String,
+	pub client_secret: String,
+}
+
+impl fmt::Debug for AzureCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AzureCreds")
+			.field("client_id", &self.client_id)
+			.field("client_secret", &Redacted(&self.client_secret))
+			.finish()
+	}
+}
+
+impl VaultClient {
+	/// Generate credentials for the role azure_role of the azure engine mounted at mount
+	pub fn azure_creds(
+		&self,
+		role: &str,
+		mount: &str,
+		azure_role: &str,
+	) -> Result<TypedSecret<AzureCreds>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), azure_role),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		self.leased(self.send(request)?)
+	}
+
+	/// Generate asynchronously credentials for the role azure_role of the azure engine
+	pub async fn azure_creds_async(
+		&self,
+		role: &str,
+		mount: &str,
+		azure_role: &str,
+	) -> Result<TypedSecret<AzureCreds>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), azure_role),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		self.leased(self.send_async(request).await?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn creds_from_response() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"azure/creds/app",
+			200,
+			json!({
+				"lease_id": "azure/creds/app/2f6a614c",
+				"lease_duration": 3600,
+				"renewable": true,
+				"data": {
+					"client_id": "408bf248-dd4e-4be5-919a-7f6207a307ab",
+					"client_secret": "ad06228a-2db9-4e0a-8a5d-e047c7f32594"
+				}
+			}),
+		);
+		let creds = transport
+			.client()
+			.azure_creds(TOKEN_ROLE, "azure", "app")
+			.unwrap();
+		assert_eq!(creds.client_id, "408bf248-dd4e-4be5-919a-7f6207a307ab");
+		assert!(creds.metadata.renewable && creds.is_valid());
+	}
+}
@@ -0,0 +1,82 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes to standard base64 with padding
+pub fn encode(input: &[u8]) -> String {
+	let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+	for chunk in input.chunks(3) {
+		let b = [
+			chunk[0],
+			chunk.get(1).copied().unwrap_or(0),
+			chunk.get(2).copied().unwrap_or(0),
+		];
+		let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+		for i in 0..4 {
+			if i <= chunk.len() {
+				output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+			} else {
+				output.push('=');
+			}
+		}
+	}
+	output
+}
+
+/// Value of a base64 character
+fn decode_char(c: u8) -> Option<u32> {
+	match c {
+		b'A'..=b'Z' => Some((c - b'A') as u32),
+		b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+		b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+		b'+' => Some(62),
+		b'/' => Some(63),
+		_ => None,
+	}
+}
+
+/// Decode standard base64 (padding is optional). Returns None if input is not valid base64
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+	let input = input.trim_end_matches('=').as_bytes();
+	if input.len() % 4 == 1 {
+		return None;
+	}
+	let mut output = Vec::with_capacity(input.len() * 3 / 4);
+	for chunk in input.chunks(4) {
+		let mut n = 0u32;
+		for (i, c) in chunk.iter().enumerate() {
+			n |= decode_char(*c)? << (18 - 6 * i);
+		}
+		for i in 0..chunk.len() - 1 {
+			output.push((n >> (16 - 8 * i) & 0xff) as u8);
+		}
+	}
+	Some(output)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn encode_decode() {
+		for (plain, encoded) in [
+			("", ""),
+			("f", "Zg=="),
+			("fo", "Zm8="),
+			("foo", "Zm9v"),
+			("foob", "Zm9vYg=="),
+			("fooba", "Zm9vYmE="),
+			("foobar", "Zm9vYmFy"),
+		]
+		.iter()
+		{
+			assert_eq!(encode(plain.as_bytes()), *encoded);
+			assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+		}
+	}
+
+	#[test]
+	fn decode_invalid() {
+		assert_eq!(decode("Zm9v!"), None);
+		assert_eq!(decode("Z"), None);
+	}
+}
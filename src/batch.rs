@@ -0,0 +1,181 @@
+use crate::{client::VaultClient, error::Result, secret::Secret};
+
+use std::{
+	collections::HashMap,
+	future::Future,
+	pin::Pin,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Mutex,
+	},
+	task::{Context, Poll},
+	thread,
+};
+
+/// A secret to fetch with `get_secrets`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretRequest<'a> {
+	pub method: &'a str,
+	pub path: &'a str,
+	pub kwargs: Option<Vec<(&'a str, &'a str)>>,
+}
+
+impl<'a> SecretRequest<'a> {
+	pub fn new(method: &'a str, path: &'a str, kwargs: Option<Vec<(&'a str, &'a str)>>) -> Self {
+		Self {
+			method,
+			path,
+			kwargs,
+		}
+	}
+
+	/// read the secret at path
+	pub fn get(path: &'a str) -> Self {
+		Self::new("GET", path, None)
+	}
+}
+
+type SecretFuture<'a> = Pin<Box<dyn Future<Output = Result<Secret>> + Send + 'a>>;
+
+/// Future running at most parallelism secret fetches at a time
+struct Batch<'a, I> {
+	requests: I,
+	parallelism: usize,
+	running: Vec<(&'a str, SecretFuture<'a>)>,
+	results: HashMap<String, Result<Secret>>,
+}
+
+impl<'a, I> Future for Batch<'a, I>
+where
+	I: Iterator<Item = (&'a str, SecretFuture<'a>)> + Unpin,
+{
+	type Output = HashMap<String, Result<Secret>>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = self.get_mut();
+		loop {
+			while this.running.len() < this.parallelism {
+				match this.requests.next() {
+					Some(request) => this.running.push(request),
+					None => break,
+				}
+			}
+			if this.running.is_empty() {
+				return Poll::Ready(std::mem::take(&mut this.results));
+			}
+			let mut done = false;
+			let mut i = 0;
+			while i < this.running.len() {
+				if let Poll::Ready(res) = this.running[i].1.as_mut().poll(cx) {
+					let (path, _) = this.running.swap_remove(i);
+					this.results.insert(path.to_owned(), res);
+					done = true;
+				} else {
+					i += 1;
+				}
+			}
+			// start new fetches if some completed, otherwise wait to be woken up
+			if !done {
+				return Poll::Pending;
+			}
+		}
+	}
+}
+
+impl VaultClient {
+	/// Fetch many secrets with the token of role using at most parallelism threads (at least 1)
+	/// and return the results by path
+	pub fn get_secrets(
+		&self,
+		role: &str,
+		requests: &[SecretRequest<'_>],
+		parallelism: usize,
+	) -> HashMap<String, Result<Secret>> {
+		let next = AtomicUsize::new(0);
+		let results = Mutex::new(HashMap::with_capacity(requests.len()));
+		thread::scope(|scope| {
+			for _ in 0..parallelism.clamp(1, requests.len().max(1)) {
+				scope.spawn(|| {
+					while let Some(request) = requests.get(next.fetch_add(1, Ordering::Relaxed)) {
+						let res = self.get_secret(
+							role,
+							request.method,
+							request.path,
+							request.kwargs.as_ref(),
+						);
+						results
+							.lock()
+							.unwrap_or_else(|e| e.into_inner())
+							.insert(request.path.to_owned(), res);
+					}
+				});
+			}
+		});
+		results.into_inner().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Fetch asynchronously many secrets with the token of role running at most parallelism
+	/// requests at a time (at least 1) and return the results by path
+	pub async fn get_secrets_async(
+		&self,
+		role: &str,
+		requests: &[SecretRequest<'_>],
+		parallelism: usize,
+	) -> HashMap<String, Result<Secret>> {
+		let futures = requests.iter().map(|request| {
+			let future: SecretFuture<'_> = Box::pin(self.get_secret_async(
+				role,
+				request.method,
+				request.path,
+				request.kwargs.as_ref(),
+			));
+			(request.path, future)
+		});
+		Batch {
+			requests: futures,
+			parallelism: parallelism.max(1),
+			running: Vec::new(),
+			results: HashMap::with_capacity(requests.len()),
+		}
+		.await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::error::Error;
+	use serde_json::json;
+	use std::task::Waker;
+
+	#[test]
+	fn batch_collects_results_by_path() {
+		let paths = ["secret/a", "secret/b", "secret/c"];
+		let futures = paths.iter().map(|path| {
+			let future: SecretFuture<'_> = Box::pin(async move {
+				match *path {
+					"secret/b" => Err(Error::NotLogged),
+					path => Ok(Secret::new(json!({ "path": path }), None)),
+				}
+			});
+			(*path, future)
+		});
+		let mut batch = Batch {
+			requests: futures,
+			parallelism: 2,
+			running: Vec::new(),
+			results: HashMap::new(),
+		};
+		let mut cx = Context::from_waker(Waker::noop());
+		let results = match Pin::new(&mut batch).poll(&mut cx) {
+			Poll::Ready(results) => results,
+			Poll::Pending => panic!("ready futures should complete the batch"),
+		};
+		assert_eq!(results.len(), 3);
+		assert_eq!(
+			results["secret/a"].as_ref().unwrap().value["path"],
+			"secret/a"
+		);
+		assert!(results["secret/b"].is_err());
+	}
+}
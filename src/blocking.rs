@@ -0,0 +1,25 @@
+use std::future::Future;
+
+/// There is no separate blocking client: every api of `VaultClient` has a blocking method named
+/// like the async one without the `_async` suffix, sending its request with the sync api of the
+/// transport. They are re-exported here for the codebases looking for a blocking client
+pub use crate::{builder::VaultClientBuilder, client::VaultClient};
+
+/// Run a future of the async api to completion on the current thread, for the few apis (streams
+/// of logs or of raw responses) that are easier to consume asynchronously
+pub fn block_on<F: Future>(future: F) -> F::Output {
+	futures_lite::future::block_on(future)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+
+	#[test]
+	fn block_on_async_api() {
+		let client = Scripted::new().client();
+		let auth = block_on(client.login_async(crate::client::TOKEN_ROLE)).unwrap();
+		assert_eq!(auth.client_token.as_str(), "s.token");
+	}
+}
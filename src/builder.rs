@@ -0,0 +1,992 @@
+use crate::{
+	auth::Auth,
+	cache::SecretCache,
+	client::VaultClient,
+	client::TOKEN_ROLE,
+	endpoint::Endpoints,
+	error::{Error, Result},
+	flight::Flights,
+	intercept::Interceptor,
+	lease::RenewalPolicy,
+	listener::RenewalListener,
+	method::{login_path, AgentSinkAuth, AuthMethod, KubernetesAuth, TokenAuth},
+	metrics::MetricsRecorder,
+	redact::Redacted,
+	retry::RetryPolicy,
+	runtime::{Runtime, Threads},
+	transport::HttpTransport,
+};
+
+#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+use crate::transport::ReqwestTransport;
+#[cfg(feature = "isahc")]
+use crate::{transport::IsahcTransport, websocket::Connect};
+#[cfg(feature = "isahc")]
+use http::Uri;
+#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+use http::{header::CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+#[cfg(feature = "isahc")]
+use isahc::{
+	auth::{Authentication, Credentials},
+	config::{CaCertificate, ClientCertificate, Configurable, Dialer, PrivateKey, SslOption},
+	HttpClient,
+};
+#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+use reqwest::{Certificate, Identity, Proxy};
+#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+use std::convert::TryFrom;
+#[cfg(feature = "isahc")]
+use std::io::{self, Write};
+#[cfg(any(feature = "isahc", feature = "reqwest", feature = "rustls"))]
+use std::path::Path;
+use std::{
+	collections::HashMap,
+	env, fmt, fs,
+	path::PathBuf,
+	sync::{Arc, Mutex, RwLock},
+	time::Duration,
+};
+use zeroize::Zeroize;
+
+/// Path of the service account token mounted in kubernetes pods
+pub const SERVICE_ACCOUNT_TOKEN_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Api url of the requests sent through a unix socket, whose host is ignored
+const UNIX_SOCKET_URL: &str = "http://localhost/v1";
+
+/// Return the value of an environment variable if it is set and not empty
+fn env_var(name: &str) -> Option<String> {
+	env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Parse a boolean environment variable the way the vault cli does
+fn env_bool(name: &str) -> bool {
+	matches!(
+		env_var(name).as_deref(),
+		Some("1") | Some("t") | Some("T") | Some("true") | Some("TRUE") | Some("True")
+	)
+}
+
+/// Concatenate the certificates (`*.pem` and `*.crt`) of a directory into a bundle file as the
+/// http client only accepts a single ca file
+#[cfg(feature = "isahc")]
+fn ca_bundle(dir: &Path) -> Result<CaFile> {
+	CaFile::new(&ca_pems(dir)?)
+}
+
+/// Concatenate the certificates (`*.pem` and `*.crt`) of a directory
+#[cfg(any(feature = "isahc", feature = "reqwest", feature = "rustls"))]
+fn ca_pems(dir: &Path) -> Result<Vec<u8>> {
+	let mut paths = fs::read_dir(dir)
+		.map_err(Error::tls)?
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| {
+			matches!(
+				path.extension().and_then(|ext| ext.to_str()),
+				Some("pem") | Some("crt")
+			)
+		})
+		.collect::<Vec<_>>();
+	paths.sort();
+	let mut bundle = Vec::new();
+	for path in paths {
+		bundle.extend(fs::read(path).map_err(Error::tls)?);
+		bundle.push(b'\n');
+	}
+	Ok(bundle)
+}
+
+/// PEM certificates written to a file as the http client only reads certificate authorities from
+/// files. The file is only readable by the owner, in a new directory only accessible by the
+/// owner so that it can't be replaced. It must stay as it is read at each new connection, and is
+/// removed with its directory when dropped
+#[cfg(feature = "isahc")]
+#[derive(Debug)]
+pub(crate) struct CaFile {
+	dir: PathBuf,
+	pub(crate) path: PathBuf,
+}
+
+#[cfg(feature = "isahc")]
+impl CaFile {
+	fn new(pem: &[u8]) -> Result<Self> {
+		let mut attempts = 0;
+		let dir = loop {
+			let dir = env::temp_dir().join(format!("vault-jwt-ca-{:016x}", fastrand::u64(..)));
+			let mut builder = fs::DirBuilder::new();
+			#[cfg(unix)]
+			std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+			match builder.create(&dir) {
+				Ok(()) => break dir,
+				Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 8 => attempts += 1,
+				Err(e) => return Err(Error::tls(e)),
+			}
+		};
+		let ca_file = Self {
+			path: dir.join("ca.pem"),
+			dir,
+		};
+		let mut options = fs::OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		options
+			.open(&ca_file.path)
+			.and_then(|mut file| file.write_all(pem))
+			.map_err(Error::tls)?;
+		Ok(ca_file)
+	}
+}
+
+#[cfg(feature = "isahc")]
+impl Drop for CaFile {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.path);
+		let _ = fs::remove_dir(&self.dir);
+	}
+}
+
+/// Return the socket path of a `unix://<path>` url of a Vault Agent listener
+fn unix_socket_path(url: &str) -> Option<PathBuf> {
+	let path = url.strip_prefix("unix://")?;
+	Some(PathBuf::from(path.strip_suffix("/v1").unwrap_or(path)))
+}
+
+/// Return the api url of a vault server address as found in `VAULT_ADDR`
+fn api_url(addr: &str) -> String {
+	let addr = addr.trim_end_matches('/');
+	if addr.ends_with("/v1") {
+		addr.to_owned()
+	} else {
+		format!("{}/v1", addr)
+	}
+}
+
+/// Where to find the jwt used by the kubernetes auth method
+enum Jwt {
+	Path(String),
+	String(String),
+}
+
+impl fmt::Debug for Jwt {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Jwt::Path(path) => f.debug_tuple("Path").field(path).finish(),
+			Jwt::String(jwt) => f.debug_tuple("String").field(&Redacted(jwt)).finish(),
+		}
+	}
+}
+
+/// Scrub the jwt from memory
+impl Drop for Jwt {
+	fn drop(&mut self) {
+		if let Jwt::String(jwt) = self {
+			jwt.zeroize();
+		}
+	}
+}
+
+/// Client certificate and private key files presented to the server
+#[derive(Debug)]
+#[cfg_attr(
+	not(any(feature = "isahc", feature = "reqwest", feature = "rustls")),
+	allow(dead_code)
+)]
+struct ClientCert {
+	cert_path: String,
+	key_path: String,
+	key_password: Option<String>,
+}
+
+/// Builder of a VaultClient. The auth method is either given explicitly with `auth_method` or is
+/// the kubernetes auth method built from `login_mount` (or `login_path`) and `token_path` (or
+/// `token_string`)
+#[derive(Debug)]
+pub struct VaultClientBuilder {
+	url: String,
+	failover: Vec<String>,
+	method: Option<Box<dyn AuthMethod>>,
+	token: Option<String>,
+	login_path: Option<String>,
+	jwt: Option<Jwt>,
+	role_jwts: Vec<(String, Jwt)>,
+	cacert_path: Option<String>,
+	cacert_pem: Option<Vec<u8>>,
+	cacert_dir: Option<String>,
+	accept_invalid_certs: bool,
+	client_cert: Option<ClientCert>,
+	proxy: Option<String>,
+	proxy_credentials: Option<(String, String)>,
+	unix_socket: Option<PathBuf>,
+	namespace: Option<String>,
+	headers: Vec<(String, String)>,
+	connect_timeout: Option<Duration>,
+	timeout: Option<Duration>,
+	low_speed_timeout: Option<(u32, Duration)>,
+	retry: Option<RetryPolicy>,
+	cache_ttl: Option<Duration>,
+	auto_login: bool,
+	renewal: RenewalPolicy,
+	metrics: Option<Arc<dyn MetricsRecorder>>,
+	listeners: Vec<Arc<dyn RenewalListener>>,
+	interceptors: Vec<Arc<dyn Interceptor>>,
+	runtime: Arc<dyn Runtime>,
+	transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl VaultClientBuilder {
+	/// Create a builder for a client of the vault server at url
+	pub fn new(url: &str) -> Self {
+		Self {
+			url: url.to_owned(),
+			failover: Vec::new(),
+			method: None,
+			token: None,
+			login_path: None,
+			jwt: None,
+			role_jwts: Vec::new(),
+			cacert_path: None,
+			cacert_pem: None,
+			cacert_dir: None,
+			accept_invalid_certs: false,
+			client_cert: None,
+			proxy: None,
+			proxy_credentials: None,
+			unix_socket: None,
+			namespace: None,
+			headers: Vec::new(),
+			connect_timeout: None,
+			timeout: None,
+			low_speed_timeout: None,
+			retry: None,
+			cache_ttl: None,
+			auto_login: false,
+			renewal: RenewalPolicy::default(),
+			#[cfg(feature = "metrics")]
+			metrics: Some(Arc::new(crate::metrics::MetricsFacade)),
+			#[cfg(not(feature = "metrics"))]
+			metrics: None,
+			listeners: Vec::new(),
+			interceptors: Vec::new(),
+			runtime: Arc::new(Threads),
+			transport: None,
+		}
+	}
+
+	/// Create a builder for a client running in a kubernetes pod. It logs in with the kubernetes
+	/// auth method using the service account token of the pod and trusts the system certificate
+	/// authorities. `VAULT_ADDR` (overriding url), `VAULT_CACERT`, `VAULT_CACERT_BYTES` and
+	/// `VAULT_NAMESPACE` are used when they are set
+	pub fn in_cluster(url: &str) -> Self {
+		let url = env_var("VAULT_ADDR")
+			.map(|addr| api_url(&addr))
+			.unwrap_or_else(|| url.to_owned());
+		let mut builder = Self::new(&url).token_path(SERVICE_ACCOUNT_TOKEN_PATH);
+		if let Some(cacert) = env_var("VAULT_CACERT") {
+			builder = builder.ca_certificate(&cacert);
+		}
+		if let Some(pem) = env_var("VAULT_CACERT_BYTES") {
+			builder = builder.ca_certificate_pem(pem.as_bytes());
+		}
+		if let Some(namespace) = env_var("VAULT_NAMESPACE") {
+			builder = builder.namespace(&namespace);
+		}
+		builder
+	}
+
+	/// Create a builder configured like the vault cli from the `VAULT_AGENT_ADDR`, `VAULT_ADDR`
+	/// (defaults to `https://127.0.0.1:8200`), `VAULT_TOKEN`, `VAULT_CACERT`,
+	/// `VAULT_CACERT_BYTES`, `VAULT_CAPATH`, `VAULT_SKIP_VERIFY`, `VAULT_HTTP_PROXY` and
+	/// `VAULT_NAMESPACE` environment variables
+	pub fn from_env() -> Self {
+		let addr = env_var("VAULT_AGENT_ADDR")
+			.or_else(|| env_var("VAULT_ADDR"))
+			.unwrap_or_else(|| "https://127.0.0.1:8200".to_owned());
+		let mut builder = Self::new(&api_url(&addr));
+		if let Some(token) = env_var("VAULT_TOKEN") {
+			builder = builder.token(&token);
+		}
+		if let Some(cacert) = env_var("VAULT_CACERT") {
+			builder = builder.ca_certificate(&cacert);
+		}
+		if let Some(pem) = env_var("VAULT_CACERT_BYTES") {
+			builder = builder.ca_certificate_pem(pem.as_bytes());
+		}
+		if let Some(capath) = env_var("VAULT_CAPATH") {
+			builder = builder.ca_directory(&capath);
+		}
+		if let Some(proxy) = env_var("VAULT_HTTP_PROXY") {
+			builder = builder.proxy(&proxy);
+		}
+		if let Some(namespace) = env_var("VAULT_NAMESPACE") {
+			builder = builder.namespace(&namespace);
+		}
+		builder.danger_accept_invalid_certs(env_bool("VAULT_SKIP_VERIFY"))
+	}
+
+	/// Use a static token instead of logging in. The token is cached under `TOKEN_ROLE` and
+	/// `login` returns it for any role
+	pub fn token(mut self, token: &str) -> Self {
+		self.method = Some(Box::new(TokenAuth::new(token)));
+		self.token = Some(token.to_owned());
+		self
+	}
+
+	/// Use the token written by a Vault Agent file sink at path instead of logging in. The file
+	/// is read again at each login (see also `AgentSinkWatcher`)
+	pub fn agent_sink(mut self, path: &str) -> Self {
+		self.method = Some(Box::new(AgentSinkAuth::new(path)));
+		self
+	}
+
+	/// Set the auth method used by `login`
+	pub fn auth_method(mut self, method: Box<dyn AuthMethod>) -> Self {
+		self.method = Some(method);
+		self
+	}
+
+	/// Log in with the kubernetes auth method mounted at mount (`kubernetes` by default)
+	pub fn login_mount(mut self, mount: &str) -> Self {
+		self.login_path = Some(login_path(mount));
+		self
+	}
+
+	/// Log in with the kubernetes auth method at the full login path (ex: `/auth/kubernetes/login`)
+	pub fn login_path(mut self, path: &str) -> Self {
+		self.login_path = Some(path.to_owned());
+		self
+	}
+
+	/// Log in with the kubernetes auth method using the jwt read from the file at path. The file is
+	/// read again at each login so that rotated service account tokens are picked up
+	pub fn token_path(mut self, path: &str) -> Self {
+		self.jwt = Some(Jwt::Path(path.to_owned()));
+		self
+	}
+
+	/// Log in with the kubernetes auth method using the given jwt
+	pub fn token_string(mut self, jwt: &str) -> Self {
+		self.jwt = Some(Jwt::String(jwt.to_owned()));
+		self
+	}
+
+	/// Log in role with the kubernetes auth method using the jwt read from the file at path
+	/// (a projected token with a specific audience for instance) instead of the default one. A
+	/// default jwt is needed (`token_path` or `token_string`) and no other auth method can be set
+	pub fn role_token_path(mut self, role: &str, path: &str) -> Self {
+		self.role_jwts
+			.push((role.to_owned(), Jwt::Path(path.to_owned())));
+		self
+	}
+
+	/// Log in role with the kubernetes auth method using the given jwt instead of the default one.
+	/// As with `role_token_path`, a default jwt is needed
+	pub fn role_token_string(mut self, role: &str, jwt: &str) -> Self {
+		self.role_jwts
+			.push((role.to_owned(), Jwt::String(jwt.to_owned())));
+		self
+	}
+
+	/// Trust the certificate authority in the PEM file at path instead of the system trust store.
+	/// An empty path keeps the system trust store
+	pub fn ca_certificate(mut self, path: &str) -> Self {
+		self.cacert_path = Some(path.to_owned()).filter(|path| !path.is_empty());
+		self
+	}
+
+	/// Trust the certificate authorities in pem (the content of a PEM file) instead of the system
+	/// trust store. Ignored if a certificate authority file is given with `ca_certificate`
+	pub fn ca_certificate_pem(mut self, pem: &[u8]) -> Self {
+		self.cacert_pem = Some(pem.to_vec()).filter(|pem| !pem.is_empty());
+		self
+	}
+
+	/// Trust the certificate authorities in the PEM files (`*.pem` and `*.crt`) of the directory
+	/// at path. Ignored if a certificate authority file is given with `ca_certificate`
+	pub fn ca_directory(mut self, path: &str) -> Self {
+		self.cacert_dir = Some(path.to_owned()).filter(|path| !path.is_empty());
+		self
+	}
+
+	/// Disable the verification of the server certificate and host name. Only use this with
+	/// development servers as it makes the connection vulnerable to man in the middle attacks
+	pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+		self.accept_invalid_certs = accept;
+		self
+	}
+
+	/// Send the requests through the proxy at url (`http://`, `https://`, `socks4://`, `socks5://`
+	/// or `socks5h://`) instead of the proxy of the `http_proxy`, `https_proxy` and `all_proxy`
+	/// environment variables. An empty url disables the proxies
+	pub fn proxy(mut self, url: &str) -> Self {
+		self.proxy = Some(url.to_owned());
+		self
+	}
+
+	/// Authenticate to the proxy with username and password (basic authentication)
+	pub fn proxy_credentials(mut self, username: &str, password: &str) -> Self {
+		self.proxy_credentials = Some((username.to_owned(), password.to_owned()));
+		self
+	}
+
+	/// Connect to the unix socket at path, like a Vault Agent listener, instead of the host of
+	/// the url (unix only). A url of the form `unix://<path>` given to `new` does the same
+	pub fn unix_socket(mut self, path: &str) -> Self {
+		self.unix_socket = Some(PathBuf::from(path));
+		self
+	}
+
+	/// Present the client certificate in the PEM file at cert_path to the server, with the private
+	/// key in the PEM file at key_path optionally encrypted with key_password (mutual TLS)
+	pub fn client_certificate(
+		mut self,
+		cert_path: &str,
+		key_path: &str,
+		key_password: Option<&str>,
+	) -> Self {
+		self.client_cert = Some(ClientCert {
+			cert_path: cert_path.to_owned(),
+			key_path: key_path.to_owned(),
+			key_password: key_password.map(|s| s.to_owned()),
+		});
+		self
+	}
+
+	/// Send the namespace with every request
+	pub fn namespace(mut self, namespace: &str) -> Self {
+		self.namespace = Some(namespace.trim_matches('/').to_owned());
+		self
+	}
+
+	/// Send a header with every request (ex: `X-Vault-Request: true`, proxy authorization or
+	/// tracing headers). Invalid names or values are reported by `build`
+	pub fn default_header(mut self, name: &str, value: &str) -> Self {
+		self.headers.push((name.to_owned(), value.to_owned()));
+		self
+	}
+
+	/// Maximum time to establish a connection to the vault server
+	pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+		self.connect_timeout = Some(timeout);
+		self
+	}
+
+	/// Maximum time for a whole request (connection, sending and receiving)
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Abort a request if the transfer speed stays below low_speed bytes per second for timeout
+	pub fn low_speed_timeout(mut self, low_speed: u32, timeout: Duration) -> Self {
+		self.low_speed_timeout = Some((low_speed, timeout));
+		self
+	}
+
+	/// Send the requests to the next of urls (other nodes of the vault cluster) when the vault
+	/// server is unreachable or is a standby answering with a redirect
+	pub fn failover_urls(mut self, urls: &[&str]) -> Self {
+		self.failover
+			.extend(urls.iter().map(|url| (*url).to_owned()));
+		self
+	}
+
+	/// Retry requests on transient failures according to the policy
+	pub fn retry(mut self, policy: RetryPolicy) -> Self {
+		self.retry = Some(policy);
+		self
+	}
+
+	/// Cache the secrets read with `get_secret` (without arguments) by role and path. Secrets
+	/// with a lease are served from the cache until their renew delay, the others during ttl
+	pub fn secret_cache(mut self, ttl: Duration) -> Self {
+		self.cache_ttl = Some(ttl);
+		self
+	}
+
+	/// Log in with the auth method of the client when a request is made for a role whose token
+	/// is missing or expired, instead of failing with `NotLogged`. Concurrent logins of a role
+	/// are merged into one
+	pub fn auto_login(mut self, auto_login: bool) -> Self {
+		self.auto_login = auto_login;
+		self
+	}
+
+	/// Renew tokens and leases according to policy instead of at 2/3 of their duration
+	pub fn renewal_policy(mut self, policy: RenewalPolicy) -> Self {
+		self.renewal = policy;
+		self
+	}
+
+	/// Report the logins, renewals, secret fetches and requests of the client to recorder instead
+	/// of the `metrics` facade (with the `metrics` feature) or of nothing
+	pub fn metrics(mut self, recorder: Arc<dyn MetricsRecorder>) -> Self {
+		self.metrics = Some(recorder);
+		self
+	}
+
+	/// Notify listener of the token renewals and of the secrets refreshed by the watchers. Several
+	/// listeners can be added
+	pub fn listener(mut self, listener: Arc<dyn RenewalListener>) -> Self {
+		self.listeners.push(listener);
+		self
+	}
+
+	/// Pass the requests and responses through interceptor, after the interceptors already added
+	pub fn interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+		self.interceptors.push(interceptor);
+		self
+	}
+
+	/// Use the timer and spawner of runtime (threads by default) for the async apis and
+	/// background tasks
+	pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+		self.runtime = runtime;
+		self
+	}
+
+	/// Send the json requests with transport instead of the isahc (or reqwest) client configured
+	/// by the builder (the tls and timeout options only apply to the clients of the builder)
+	pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+		self.transport = Some(transport);
+		self
+	}
+
+	/// Build the vault client
+	pub fn build(mut self) -> Result<VaultClient> {
+		if let Some(path) = unix_socket_path(&self.url) {
+			self.unix_socket = Some(path);
+			self.url = UNIX_SOCKET_URL.to_owned();
+		}
+		#[cfg(feature = "isahc")]
+		let (transport, connect, ca_file) = self.connection()?;
+		#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+		let transport = match self.transport.take() {
+			Some(transport) => transport,
+			None => Arc::new(self.reqwest_transport()?),
+		};
+		#[cfg(not(any(feature = "isahc", feature = "reqwest", feature = "rustls")))]
+		let transport = self.transport.take().ok_or(Error::NoTransport)?;
+		let method = match (self.method, self.jwt) {
+			// the role jwts would be silently ignored
+			(Some(_), _) | (None, None) if !self.role_jwts.is_empty() => {
+				return Err(Error::RoleJwtsWithoutJwt)
+			}
+			(Some(method), _) => method,
+			(None, Some(jwt)) => {
+				let login_path = self.login_path.unwrap_or_else(|| login_path("kubernetes"));
+				let mut method = match &jwt {
+					Jwt::Path(path) => {
+						// fail early if the token is not readable, it is read again at each login
+						fs::metadata(path)?;
+						KubernetesAuth::with_token_path(&login_path, path)
+					}
+					Jwt::String(jwt) => KubernetesAuth::with_login_path(&login_path, jwt),
+				};
+				for (role, jwt) in self.role_jwts {
+					method = match &jwt {
+						Jwt::Path(path) => {
+							fs::metadata(path)?;
+							method.role_token_path(&role, path)
+						}
+						Jwt::String(jwt) => method.role_token(&role, jwt),
+					};
+				}
+				Box::new(method)
+			}
+			(None, None) => return Err(Error::NoAuthMethod),
+		};
+		let mut auth = HashMap::new();
+		if let Some(mut token) = self.token {
+			auth.insert(TOKEN_ROLE.to_owned(), Auth::new(&token, None));
+			token.zeroize();
+		}
+		let renewal = self.renewal;
+		Ok(VaultClient {
+			endpoints: Endpoints::new(&self.url, &self.failover),
+			url: self.url,
+			method,
+			namespace: self.namespace,
+			retry: self.retry,
+			auth: RwLock::new(auth),
+			cache: self.cache_ttl.map(|ttl| SecretCache::new(ttl, renewal)),
+			flights: Flights::default(),
+			renewal: self.renewal,
+			auto_login: self.auto_login,
+			identity_tokens: Mutex::new(HashMap::new()),
+			metrics: self.metrics,
+			listeners: self.listeners,
+			interceptors: self.interceptors,
+			runtime: self.runtime,
+			transport,
+			#[cfg(feature = "isahc")]
+			connect,
+			#[cfg(feature = "isahc")]
+			_ca_file: ca_file,
+		})
+	}
+
+	/// Build from the connection options the isahc transport (unless a transport is given), the
+	/// options of the websockets and the private copy of the certificate authorities
+	#[cfg(feature = "isahc")]
+	fn connection(&mut self) -> Result<(Arc<dyn HttpTransport>, Connect, Option<CaFile>)> {
+		let mut connect = Connect {
+			client_cert: self.client_cert.as_ref().map(|cert| {
+				(
+					cert.cert_path.clone(),
+					cert.key_path.clone(),
+					cert.key_password.clone(),
+				)
+			}),
+			accept_invalid_certs: self.accept_invalid_certs,
+			proxy: self.proxy.clone(),
+			unix_socket: self.unix_socket.clone(),
+			connect_timeout: self.connect_timeout,
+			headers: self.headers.clone(),
+			..Connect::default()
+		};
+		let mut builder = HttpClient::builder().default_header("Content-Type", "application/json");
+		for (name, value) in self.headers.iter() {
+			builder = builder.default_header(name.as_str(), value.as_str());
+		}
+		let mut ca_file = None;
+		if let Some(cacert) = self.cacert_path.take() {
+			connect.ca_file = Some(PathBuf::from(&cacert));
+			builder = builder.ssl_ca_certificate(CaCertificate::file(cacert));
+		} else if let Some(pem) = self.cacert_pem.take() {
+			ca_file = Some(CaFile::new(&pem)?);
+		} else if let Some(dir) = self.cacert_dir.take() {
+			ca_file = Some(ca_bundle(Path::new(&dir))?);
+		}
+		if let Some(ca_file) = ca_file.as_ref() {
+			connect.ca_file = Some(ca_file.path.clone());
+			builder = builder.ssl_ca_certificate(CaCertificate::file(&ca_file.path));
+		}
+		if let Some(cert) = self.client_cert.take() {
+			builder = builder.ssl_client_certificate(ClientCertificate::pem_file(
+				cert.cert_path,
+				PrivateKey::pem_file(cert.key_path, cert.key_password),
+			));
+		}
+		match self.proxy.as_deref() {
+			Some("") => builder = builder.proxy(None),
+			Some(proxy) => {
+				let proxy = proxy
+					.parse::<Uri>()
+					.map_err(|e| Error::HttpError { source: e.into() })?;
+				builder = builder.proxy(proxy);
+			}
+			None => {}
+		}
+		if let Some((username, mut password)) = self.proxy_credentials.take() {
+			builder = builder
+				.proxy_authentication(Authentication::basic())
+				.proxy_credentials(Credentials::new(username, password.as_str()));
+			password.zeroize();
+		}
+		#[cfg(unix)]
+		if let Some(path) = self.unix_socket.take() {
+			builder = builder.dial(Dialer::unix_socket(path));
+		}
+		if self.accept_invalid_certs {
+			builder = builder.ssl_options(
+				SslOption::DANGER_ACCEPT_INVALID_CERTS | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
+			);
+		}
+		if let Some(timeout) = self.connect_timeout {
+			builder = builder.connect_timeout(timeout);
+		}
+		if let Some(timeout) = self.timeout {
+			builder = builder.timeout(timeout);
+		}
+		if let Some((low_speed, timeout)) = self.low_speed_timeout {
+			builder = builder.low_speed_timeout(low_speed, timeout);
+		}
+		match self.transport.take() {
+			Some(transport) => Ok((transport, connect, ca_file)),
+			None => Ok((
+				Arc::new(IsahcTransport::new(builder.build()?)),
+				connect,
+				ca_file,
+			)),
+		}
+	}
+
+	/// Build the reqwest transport from the connection options, unix sockets and low speed
+	/// timeouts being unsupported
+	#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+	fn reqwest_transport(&mut self) -> Result<ReqwestTransport> {
+		if self.unix_socket.is_some() {
+			return Err(Error::Unsupported("unix socket"));
+		}
+		if self.low_speed_timeout.is_some() {
+			return Err(Error::Unsupported("low speed timeout"));
+		}
+		let mut headers = HeaderMap::new();
+		headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+		for (name, value) in self.headers.iter() {
+			headers.insert(
+				HeaderName::try_from(name.as_str()).map_err(http::Error::from)?,
+				HeaderValue::try_from(value.as_str()).map_err(http::Error::from)?,
+			);
+		}
+		let pem = if let Some(cacert) = self.cacert_path.take() {
+			Some(fs::read(cacert).map_err(Error::tls)?)
+		} else if let Some(pem) = self.cacert_pem.take() {
+			Some(pem)
+		} else if let Some(dir) = self.cacert_dir.take() {
+			Some(ca_pems(Path::new(&dir))?)
+		} else {
+			None
+		};
+		let cacerts = match pem {
+			Some(pem) => Some(Certificate::from_pem_bundle(&pem).map_err(Error::tls)?),
+			None => None,
+		};
+		let identity = match self.client_cert.take() {
+			Some(cert) if cert.key_password.is_some() => {
+				return Err(Error::Unsupported("encrypted private key"))
+			}
+			Some(cert) => {
+				let pem = fs::read(cert.cert_path).map_err(Error::tls)?;
+				let key = fs::read(cert.key_path).map_err(Error::tls)?;
+				// rustls reads the certificate and the key from the same pem
+				#[cfg(feature = "rustls")]
+				let identity = Identity::from_pem(&[pem, key].join(&b'\n')).map_err(Error::tls)?;
+				#[cfg(not(feature = "rustls"))]
+				let identity = Identity::from_pkcs8_pem(&pem, &key).map_err(Error::tls)?;
+				Some(identity)
+			}
+			None => None,
+		};
+		let proxy = match self.proxy.as_deref() {
+			Some("") | None => None,
+			Some(proxy) => {
+				let mut proxy = Proxy::all(proxy).map_err(Error::transport)?;
+				if let Some((username, mut password)) = self.proxy_credentials.take() {
+					proxy = proxy.basic_auth(&username, &password);
+					password.zeroize();
+				}
+				Some(proxy)
+			}
+		};
+		// both clients get the same options, their builders being distinct types
+		macro_rules! configure {
+			($builder:expr) => {{
+				let mut builder = $builder.default_headers(headers.clone());
+				#[cfg(feature = "rustls")]
+				{
+					builder = builder.use_rustls_tls();
+				}
+				if let Some(cacerts) = cacerts.as_ref() {
+					builder = builder.tls_built_in_root_certs(false);
+					for cacert in cacerts {
+						builder = builder.add_root_certificate(cacert.clone());
+					}
+				}
+				if let Some(identity) = identity.as_ref() {
+					builder = builder.identity(identity.clone());
+				}
+				builder = match (self.proxy.as_deref(), proxy.as_ref()) {
+					(Some(""), _) => builder.no_proxy(),
+					(_, Some(proxy)) => builder.proxy(proxy.clone()),
+					_ => builder,
+				};
+				// rustls doesn't check the hostnames of the certificates it doesn't verify
+				if self.accept_invalid_certs {
+					builder = builder.danger_accept_invalid_certs(true);
+					#[cfg(not(feature = "rustls"))]
+					{
+						builder = builder.danger_accept_invalid_hostnames(true);
+					}
+				}
+				if let Some(timeout) = self.connect_timeout {
+					builder = builder.connect_timeout(timeout);
+				}
+				if let Some(timeout) = self.timeout {
+					builder = builder.timeout(timeout);
+				}
+				builder.build().map_err(Error::transport)?
+			}};
+		}
+		Ok(ReqwestTransport::new(
+			configure!(reqwest::Client::builder()),
+			// unlike the async client, the blocking one has a default timeout
+			configure!(reqwest::blocking::Client::builder().timeout(None)),
+		))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+
+	#[test]
+	fn auth_method_is_mandatory() {
+		assert!(matches!(
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.transport(Scripted::new())
+				.build(),
+			Err(Error::NoAuthMethod)
+		));
+	}
+
+	#[test]
+	fn role_jwts_need_a_default_jwt() {
+		let builder = || {
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.role_token_string("app", "eyJ")
+				.transport(Scripted::new())
+		};
+		assert!(matches!(builder().build(), Err(Error::RoleJwtsWithoutJwt)));
+		assert!(matches!(
+			builder().agent_sink("/run/vault/token").build(),
+			Err(Error::RoleJwtsWithoutJwt)
+		));
+		assert!(builder().token_string("eyJ").build().is_ok());
+	}
+
+	#[test]
+	#[cfg(feature = "isahc")]
+	fn invalid_default_header() {
+		let builder = VaultClientBuilder::new("http://localhost:8200/v1")
+			.token("s.token")
+			.default_header("X-Vault-Request", "true");
+		assert!(builder.build().is_ok());
+		let builder = VaultClientBuilder::new("http://localhost:8200/v1")
+			.token("s.token")
+			.default_header("X Vault", "true");
+		assert!(matches!(builder.build(), Err(Error::ClientError { .. })));
+	}
+
+	#[test]
+	fn auto_login_with_static_token() {
+		let client = VaultClientBuilder::new("http://localhost:8200/v1")
+			.auth_method(Box::new(TokenAuth::new("s.token")))
+			.auto_login(true)
+			.transport(Scripted::new())
+			.build()
+			.unwrap();
+		assert!(client.auth("app").is_none());
+		assert_eq!(client.token("app").unwrap().as_str(), "s.token");
+		assert!(client.auth("app").is_some());
+	}
+
+	#[test]
+	fn api_url_from_addr() {
+		assert_eq!(api_url("https://vault:8200"), "https://vault:8200/v1");
+		assert_eq!(api_url("https://vault:8200/"), "https://vault:8200/v1");
+		assert_eq!(api_url("https://vault:8200/v1"), "https://vault:8200/v1");
+	}
+
+	#[test]
+	fn static_token_is_cached() {
+		let client = Scripted::new().client();
+		assert_eq!(client.token(TOKEN_ROLE).unwrap().as_str(), "s.token");
+	}
+
+	#[test]
+	#[cfg(feature = "isahc")]
+	fn ca_bundle_from_directory() {
+		let dir = env::temp_dir().join(format!("vault-jwt-capath-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(dir.join("b.crt"), "B").unwrap();
+		fs::write(dir.join("a.pem"), "A").unwrap();
+		fs::write(dir.join("README"), "ignored").unwrap();
+		let bundle = ca_bundle(&dir).unwrap();
+		assert_eq!(fs::read_to_string(&bundle.path).unwrap(), "A\nB\n");
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "isahc")]
+	fn ca_certificate_from_pem() {
+		let builder = VaultClientBuilder::new("http://localhost:8200/v1").ca_certificate_pem(b"");
+		assert!(builder.cacert_pem.is_none());
+		let client = VaultClientBuilder::new("https://localhost:8200/v1")
+			.token("s.token")
+			.ca_certificate_pem(b"-----BEGIN CERTIFICATE-----")
+			.build()
+			.unwrap();
+		let path = client._ca_file.as_ref().unwrap().path.clone();
+		assert_eq!(
+			fs::read_to_string(&path).unwrap(),
+			"-----BEGIN CERTIFICATE-----"
+		);
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+			assert_eq!(mode(&path), 0o600);
+			assert_eq!(mode(path.parent().unwrap()), 0o700);
+		}
+		drop(client);
+		assert!(!path.exists() && !path.parent().unwrap().exists());
+	}
+
+	#[test]
+	#[cfg(any(feature = "isahc", feature = "reqwest", feature = "rustls"))]
+	fn missing_ca_directory() {
+		assert!(matches!(
+			VaultClientBuilder::new("https://localhost:8200/v1")
+				.token("s.token")
+				.ca_directory("/nonexistent/certs")
+				.build(),
+			Err(Error::TlsError { .. })
+		));
+	}
+
+	#[test]
+	fn agent_unix_socket() {
+		assert_eq!(
+			unix_socket_path(&api_url("unix:///run/vault/agent.sock")),
+			Some(PathBuf::from("/run/vault/agent.sock"))
+		);
+		assert_eq!(unix_socket_path("http://localhost:8200/v1"), None);
+		let client = VaultClientBuilder::new("unix:///run/vault/agent.sock")
+			.token("s.token")
+			.transport(Scripted::new())
+			.build()
+			.unwrap();
+		assert_eq!(client.url, UNIX_SOCKET_URL);
+	}
+
+	#[test]
+	#[cfg(feature = "isahc")]
+	fn invalid_proxy() {
+		assert!(matches!(
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.token("s.token")
+				.proxy("http://proxy host")
+				.build(),
+			Err(Error::HttpError { .. })
+		));
+	}
+
+	#[test]
+	fn missing_token_file() {
+		assert!(matches!(
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.token_path("/nonexistent/token")
+				.transport(Scripted::new())
+				.build(),
+			Err(Error::TokenError { .. })
+		));
+	}
+
+	#[test]
+	#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+	fn reqwest_unsupported_options() {
+		assert!(VaultClientBuilder::new("http://localhost:8200/v1")
+			.token("s.token")
+			.build()
+			.is_ok());
+		assert!(matches!(
+			VaultClientBuilder::new("unix:///run/vault/agent.sock")
+				.token("s.token")
+				.build(),
+			Err(Error::Unsupported(_))
+		));
+	}
+}
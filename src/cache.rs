@@ -0,0 +1,158 @@
+use crate::{
+	client::{kwargs_body, VaultClient},
+	error::Result,
+	lease::RenewalPolicy,
+	secret::Secret,
+};
+
+use std::{
+	collections::HashMap,
+	sync::{Mutex, MutexGuard},
+	time::{Duration, SystemTime},
+};
+
+/// a cached secret and the time after which it must be fetched again
+#[derive(Debug)]
+struct Entry {
+	secret: Secret,
+	refresh: SystemTime,
+}
+
+/// In-memory cache of the secrets read with `get_secret`, keyed by role and path. A secret with
+/// a lease is served until its renew delay, a secret without lease during the cache ttl minus
+/// the jitter of the renewal policy
+#[derive(Debug)]
+pub(crate) struct SecretCache {
+	ttl: Duration,
+	policy: RenewalPolicy,
+	entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl SecretCache {
+	pub(crate) fn new(ttl: Duration, policy: RenewalPolicy) -> Self {
+		Self {
+			ttl,
+			policy,
+			entries: Mutex::new(HashMap::new()),
+		}
+	}
+
+	fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), Entry>> {
+		self.entries.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// return a copy of the secret of role at path if it doesn't need a refresh
+	pub(crate) fn get(&self, role: &str, path: &str) -> Option<Secret> {
+		self.lock()
+			.get(&(role.to_owned(), path.to_owned()))
+			.filter(|entry| SystemTime::now() < entry.refresh && entry.secret.is_valid())
+			.map(|entry| entry.secret.clone())
+	}
+
+	/// cache a copy of the secret of role at path
+	pub(crate) fn insert(&self, role: &str, path: &str, secret: &Secret) {
+		let refresh = match secret.renew_delay() {
+			Some(delay) if secret.has_lease() => SystemTime::now() + delay,
+			_ => SystemTime::now() + self.policy.jittered(self.ttl),
+		};
+		self.lock().insert(
+			(role.to_owned(), path.to_owned()),
+			Entry {
+				secret: secret.clone(),
+				refresh,
+			},
+		);
+	}
+
+	/// forget the secrets at path for all roles
+	pub(crate) fn remove(&self, path: &str) {
+		self.lock().retain(|(_, p), _| p != path);
+	}
+
+	pub(crate) fn clear(&self) {
+		self.lock().clear();
+	}
+}
+
+impl VaultClient {
+	/// Return the cache to use for a `get_secret` request. Only reads without arguments are cached
+	pub(crate) fn secret_cache(
+		&self,
+		method: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Option<&SecretCache> {
+		self.cache
+			.as_ref()
+			.filter(|_| method.eq_ignore_ascii_case("GET") && kwargs.is_none())
+	}
+
+	/// Remove the cached secrets at path (for all roles) so that the next `get_secret` fetches
+	/// them again
+	pub fn invalidate(&self, path: &str) {
+		if let Some(ref cache) = self.cache {
+			cache.remove(path);
+		}
+	}
+
+	/// Remove all the cached secrets
+	pub fn invalidate_all(&self) {
+		if let Some(ref cache) = self.cache {
+			cache.clear();
+		}
+	}
+
+	/// Get a secret from vault server bypassing the cache, and cache the new value
+	pub fn get_secret_uncached(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		let secret = self.fetch_with_body(role, method, path, &kwargs_body(kwargs))?;
+		if let Some(cache) = self.secret_cache(method, kwargs) {
+			cache.insert(role, path, &secret);
+		}
+		Ok(secret)
+	}
+
+	/// Get a secret asynchronously from vault server bypassing the cache, and cache the new value
+	pub async fn get_secret_uncached_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		let secret = self
+			.fetch_with_body_async(role, method, path, &kwargs_body(kwargs))
+			.await?;
+		if let Some(cache) = self.secret_cache(method, kwargs) {
+			cache.insert(role, path, &secret);
+		}
+		Ok(secret)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn cached_secrets() {
+		let cache = SecretCache::new(Duration::from_secs(60), RenewalPolicy::default());
+		cache.insert("app", "secret/foo", &Secret::new(json!({"a": 1}), None));
+		cache.insert(
+			"app",
+			"secret/bar",
+			&Secret::new(json!({"b": 2}), Some(Duration::from_secs(0))),
+		);
+		assert_eq!(cache.get("app", "secret/foo").unwrap().value["a"], 1);
+		assert!(cache.get("other", "secret/foo").is_none());
+		// an expired lease is never served
+		assert!(cache.get("app", "secret/bar").is_none());
+		cache.remove("secret/foo");
+		assert!(cache.get("app", "secret/foo").is_none());
+	}
+}
@@ -1,134 +1,778 @@
 use crate::{
-	auth::Auth,
+	auth::{Auth, Renewal, Token},
+	builder::VaultClientBuilder,
+	cache::SecretCache,
+	endpoint::{same_origin, should_fail_over, Endpoints},
 	error::{Error, Result, VaultErrors},
-	secret::Secret,
+	flight::Flights,
+	identity::IdentityToken,
+	intercept::Interceptor,
+	lease::RenewalPolicy,
+	listener::RenewalListener,
+	method::{AppRoleAuth, AuthMethod, CertAuth, JwtAuth, KubernetesAuth, Login, PasswordAuth},
+	metrics::{MetricsRecorder, LOGINS, RENEWALS},
+	retry::RetryPolicy,
+	runtime::Runtime,
+	scrub::scrub_value,
+	secret::{ResponseMetadata, Secret, TypedSecret},
+	trace::{self, Span},
+	transport::{AsyncBody, Body, HttpTransport},
 };
+#[cfg(feature = "isahc")]
+use crate::{builder::CaFile, websocket::Connect};
 
-use isahc::{
-	config::{CaCertificate, Configurable},
-	http::{Request, StatusCode},
-	AsyncReadResponseExt, HttpClient, ReadResponseExt,
+use http::{HeaderName, HeaderValue, Request, Response, StatusCode, Uri};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+	collections::HashMap,
+	future::Future,
+	io::Read,
+	ops::Deref,
+	sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
+	thread,
+	time::{Duration, Instant},
 };
-use serde_json::{Map, Value, json};
-use std::{collections::HashMap, time::Duration};
 
-/// Vault client that cache its auth tokens
+/// Maximum number of standby redirects followed by a request
+const MAX_REDIRECTS: usize = 5;
+
+/// Shortfall of a renewed ttl on the increment requested put down to the rounding of the server
+/// rather than to the max ttl of the token
+const CAP_TOLERANCE: Duration = Duration::from_secs(5);
+
+/// Role under which the static token of a client created with `VaultClient::with_token` is cached
+pub const TOKEN_ROLE: &str = "token";
+
+/// Vault client that cache its auth tokens. The client is `Send + Sync` and all the requests
+/// (including login) take `&self`, so a single client can be shared behind an `Arc`
 #[derive(Debug)]
 pub struct VaultClient {
+	/// url of the primary vault server
 	pub url: String,
-	login_path: String,
-	jwt: String,
-	client: HttpClient,
+	/// urls of the vault servers requests are sent to, the primary one first
+	pub(crate) endpoints: Endpoints,
+	/// auth method used by `login`
+	pub(crate) method: Box<dyn AuthMethod>,
+	/// vault enterprise namespace sent with every request
+	pub(crate) namespace: Option<String>,
+	/// retry policy applied to transient failures
+	pub(crate) retry: Option<RetryPolicy>,
+	/// http client of the json requests
+	pub(crate) transport: Arc<dyn HttpTransport>,
 	/// map a role to an authentification token
-	pub auth: HashMap<String, Auth>,
+	pub(crate) auth: RwLock<HashMap<String, Auth>>,
+	/// cache of the secrets read with `get_secret`
+	pub(crate) cache: Option<SecretCache>,
+	/// map a role to the lock taken while logging in
+	pub(crate) flights: Flights,
+	/// when tokens and leases are renewed
+	pub(crate) renewal: RenewalPolicy,
+	/// log in when a token is missing or expired instead of failing with `NotLogged`
+	pub(crate) auto_login: bool,
+	/// map a role and an oidc role to the last identity token issued
+	pub(crate) identity_tokens: Mutex<HashMap<(String, String), IdentityToken>>,
+	/// receiver of the measures of the client operations
+	pub(crate) metrics: Option<Arc<dyn MetricsRecorder>>,
+	/// timer and spawner of the async apis and background tasks
+	pub(crate) runtime: Arc<dyn Runtime>,
+	/// receivers of the token and secret renewals
+	pub(crate) listeners: Vec<Arc<dyn RenewalListener>>,
+	/// hooks on the requests and responses
+	pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+	/// connection options of the websockets
+	#[cfg(feature = "isahc")]
+	pub(crate) connect: Connect,
+	/// private copy of the certificate authorities given as PEM or as a directory
+	#[cfg(feature = "isahc")]
+	pub(crate) _ca_file: Option<CaFile>,
 }
 
 impl VaultClient {
-	/// Create a new vault client given an url, a jwt token and a ca certificate path
+	/// Create a new vault client given an url, a jwt token and a ca certificate path.
+	/// Without ca certificate path (or with an empty one) the system trust store is used
 	pub fn new(url: &str, login_path: &str, jwt: &str, cacert_path: Option<&str>) -> Result<Self> {
-		let mut builder = HttpClient::builder().default_header("Content-Type", "application/json");
+		let mut builder = VaultClientBuilder::new(url)
+			.login_path(login_path)
+			.token_string(jwt);
 		if let Some(cacert) = cacert_path {
-			builder = builder.ssl_ca_certificate(CaCertificate::file(cacert));
+			builder = builder.ca_certificate(cacert);
 		}
-		let client = builder.build()?;
-		Ok(VaultClient {
-			url: url.to_owned(),
-			login_path: login_path.to_owned(),
-			jwt: jwt.to_owned(),
-			client,
-			auth: HashMap::new(),
-		})
+		builder.build()
+	}
+
+	/// Create a new vault client given an url, the auth method used to log in and a ca certificate path
+	pub fn with_auth_method(
+		url: &str,
+		method: Box<dyn AuthMethod>,
+		cacert_path: Option<&str>,
+	) -> Result<Self> {
+		let mut builder = VaultClientBuilder::new(url).auth_method(method);
+		if let Some(cacert) = cacert_path {
+			builder = builder.ca_certificate(cacert);
+		}
+		builder.build()
+	}
+
+	/// Create a new vault client for a kubernetes pod using its service account token and the
+	/// `VAULT_ADDR`, `VAULT_CACERT` and `VAULT_NAMESPACE` environment variables when they are set
+	/// (see `VaultClientBuilder::in_cluster`)
+	pub fn in_cluster(url: &str) -> Result<Self> {
+		VaultClientBuilder::in_cluster(url).build()
+	}
+
+	/// Return a builder to configure a new vault client
+	pub fn builder(url: &str) -> VaultClientBuilder {
+		VaultClientBuilder::new(url)
+	}
+
+	/// Create a new vault client given an url, the mount point of the kubernetes auth method
+	/// (`kubernetes` for `auth/kubernetes/`), a jwt token and a ca certificate path
+	pub fn with_mount(url: &str, mount: &str, jwt: &str, cacert_path: Option<&str>) -> Result<Self> {
+		Self::with_auth_method(url, Box::new(KubernetesAuth::new(mount, jwt)), cacert_path)
+	}
+
+	/// Create a new vault client using a static token instead of logging in with a jwt.
+	/// The token is already cached under `TOKEN_ROLE` and `login` returns it for any role
+	pub fn with_token(url: &str, token: &str, cacert_path: Option<&str>) -> Result<Self> {
+		let mut builder = VaultClientBuilder::new(url).token(token);
+		if let Some(cacert) = cacert_path {
+			builder = builder.ca_certificate(cacert);
+		}
+		builder.build()
+	}
+
+	/// Create a new vault client configured like the vault cli from the standard environment
+	/// variables (see `VaultClientBuilder::from_env`)
+	pub fn from_env() -> Result<Self> {
+		VaultClientBuilder::from_env().build()
+	}
+
+	/// Set the namespace sent with every request (including login) or None to use the root namespace
+	pub fn set_namespace(&mut self, namespace: Option<&str>) {
+		self.namespace = namespace.map(|s| s.trim_matches('/').to_owned());
+	}
+
+	/// Return the namespace sent with every request
+	pub fn namespace(&self) -> Option<&str> {
+		self.namespace.as_deref()
+	}
+
+	/// Set the retry policy applied to transient failures, or None to never retry
+	pub fn set_retry_policy(&mut self, retry: Option<RetryPolicy>) {
+		self.retry = retry;
+	}
+
+	/// Change the auth method used by `login`
+	pub fn set_auth_method(&mut self, method: Box<dyn AuthMethod>) {
+		self.method = method;
 	}
 
 	pub fn is_logged(&self, role: &str) -> bool {
-		self.auth
+		self.auth_read()
 			.get(role)
 			.filter(|v| v.is_valid() && !v.to_renew())
 			.is_some()
 	}
 
+	/// Return a copy of the cached Auth of role
+	pub fn auth(&self, role: &str) -> Option<Auth> {
+		self.auth_read().get(role).cloned()
+	}
+
 	/// Log in to the vault client and return Auth.
-	pub fn login(&mut self, role: &str) -> Result<&Auth> {
-		if !self.is_logged(role) {
-			let url = format!("{}{}", &self.url, &self.login_path);
-			let body = json!({"role": role, "jwt": &self.jwt}).to_string();
-			let mut res = self
-				.client
-				.post(url, body)
-				.map_err(|e| Error::ClientError { source: e })?;
-			let status = res.status();
-			if status == StatusCode::OK {
-				// parse vault response and cache important information
-				let auth_value: Value = res.json().map_err(|e| Error::ParseError { source: e })?;
-				let lease_duration = auth_value["auth"]["lease_duration"]
-					.as_u64()
-					.unwrap_or(0u64);
-				let renewable = auth_value["auth"]["renewable"].as_bool().unwrap_or(false);
-				let auth = Auth::new(
-					auth_value["auth"]["client_token"].as_str().unwrap_or(""),
-					if renewable {
-						Some(Duration::from_secs(lease_duration))
-					} else {
-						None
-					},
-				);
-				// insert and forget old value if any
-				let _ = self.auth.insert(role.to_owned(), auth);
-			} else {
-				// parse vault error
-				let errors: VaultErrors =
-					res.json().map_err(|e| Error::ParseError { source: e })?;
-				return Err(Error::VaultError(status, errors.errors.join("\n")));
+	pub fn login(&self, role: &str) -> Result<Auth> {
+		self.login_with(self.method.as_ref(), role)
+	}
+
+	/// Log in asynchronously to the vault client and return Auth.
+	pub async fn login_async(&self, role: &str) -> Result<Auth> {
+		self.login_with_async(self.method.as_ref(), role).await
+	}
+
+	/// Log in to the vault client with the given auth method instead of the default one and return Auth.
+	/// The token is cached under role
+	pub fn login_with(&self, method: &dyn AuthMethod, role: &str) -> Result<Auth> {
+		let _span = Span::login(role).enter();
+		// login if we are not already logged in or if it's time to renew token
+		if let Some(auth) = self.logged_auth(role) {
+			return Ok(auth);
+		}
+		// only one login per role at a time: concurrent callers wait for it and reuse its token
+		let _flight = self.flights.acquire(role);
+		if let Some(auth) = self.logged_auth(role) {
+			return Ok(auth);
+		}
+		let auth = self.fetch_auth(method, role);
+		self.count_result(LOGINS, role, &auth);
+		Ok(self.cache_auth(role, auth?))
+	}
+
+	/// Log in asynchronously to the vault client with the given auth method and return Auth.
+	pub async fn login_with_async(&self, method: &dyn AuthMethod, role: &str) -> Result<Auth> {
+		Span::login(role)
+			.instrument(async {
+				if let Some(auth) = self.logged_auth(role) {
+					return Ok(auth);
+				}
+				let _flight = self.flights.acquire_async(role).await;
+				if let Some(auth) = self.logged_auth(role) {
+					return Ok(auth);
+				}
+				let auth = self.fetch_auth_async(method, role).await;
+				self.count_result(LOGINS, role, &auth);
+				Ok(self.cache_auth(role, auth?))
+			})
+			.await
+	}
+
+	/// Log in to the vault client with the approle auth method and return Auth.
+	/// The token is cached under the role_id and can be used with `get_secret` like any other role
+	pub fn login_approle(&self, role_id: &str, secret_id: &str) -> Result<Auth> {
+		self.login_with(&AppRoleAuth::new(role_id, secret_id), role_id)
+	}
+
+	/// Log in asynchronously to the vault client with the approle auth method and return Auth.
+	pub async fn login_approle_async(&self, role_id: &str, secret_id: &str) -> Result<Auth> {
+		self.login_with_async(&AppRoleAuth::new(role_id, secret_id), role_id)
+			.await
+	}
+
+	/// Log in to the vault client for role with the jwt auth method mounted at mount and return
+	/// Auth. The token is cached under role
+	pub fn login_jwt(&self, mount: &str, role: &str, jwt: &str) -> Result<Auth> {
+		self.login_with(&JwtAuth::new(mount, jwt), role)
+	}
+
+	/// Log in asynchronously to the vault client with the jwt auth method and return Auth.
+	pub async fn login_jwt_async(&self, mount: &str, role: &str, jwt: &str) -> Result<Auth> {
+		self.login_with_async(&JwtAuth::new(mount, jwt), role).await
+	}
+
+	/// Log in to the vault client with the userpass auth method mounted at mount and return Auth.
+	/// The token is cached under username
+	pub fn login_userpass(&self, mount: &str, username: &str, password: &str) -> Result<Auth> {
+		self.login_with(&PasswordAuth::new(mount, username, password), username)
+	}
+
+	/// Log in asynchronously to the vault client with the userpass auth method and return Auth.
+	pub async fn login_userpass_async(
+		&self,
+		mount: &str,
+		username: &str,
+		password: &str,
+	) -> Result<Auth> {
+		self.login_with_async(&PasswordAuth::new(mount, username, password), username)
+			.await
+	}
+
+	/// Log in to the vault client with the ldap auth method mounted at mount and return Auth.
+	/// The token is cached under username
+	pub fn login_ldap(&self, mount: &str, username: &str, password: &str) -> Result<Auth> {
+		self.login_with(&PasswordAuth::new(mount, username, password), username)
+	}
+
+	/// Log in asynchronously to the vault client with the ldap auth method and return Auth.
+	pub async fn login_ldap_async(
+		&self,
+		mount: &str,
+		username: &str,
+		password: &str,
+	) -> Result<Auth> {
+		self.login_with_async(&PasswordAuth::new(mount, username, password), username)
+			.await
+	}
+
+	/// Log in to the vault client with the cert auth method using the client certificate of the
+	/// connection and return Auth. name is the certificate role (empty to try all of them)
+	pub fn login_cert(&self, name: &str) -> Result<Auth> {
+		self.login_with(&CertAuth::new(), name)
+	}
+
+	/// Log in asynchronously to the vault client with the cert auth method and return Auth.
+	pub async fn login_cert_async(&self, name: &str) -> Result<Auth> {
+		self.login_with_async(&CertAuth::new(), name).await
+	}
+
+	/// Return the cached Auth of role if it is valid and doesn't need a renewal
+	pub(crate) fn logged_auth(&self, role: &str) -> Option<Auth> {
+		self.auth(role)
+			.filter(|auth| auth.is_valid() && !auth.to_renew())
+	}
+
+	/// Cache auth under role (forgetting the old value if any) and return a copy of it
+	pub(crate) fn cache_auth(&self, role: &str, mut auth: Auth) -> Auth {
+		auth.lease = auth
+			.lease
+			.take()
+			.map(|lease| self.renewal.lease(lease.lease_duration));
+		let _ = self.auth_write().insert(role.to_owned(), auth.clone());
+		auth
+	}
+
+	/// Lock the auth cache for reading
+	pub(crate) fn auth_read(&self) -> RwLockReadGuard<'_, HashMap<String, Auth>> {
+		self.auth.read().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Lock the auth cache for writing
+	pub(crate) fn auth_write(&self) -> RwLockWriteGuard<'_, HashMap<String, Auth>> {
+		self.auth.write().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Get a new Auth for role using method
+	fn fetch_auth(&self, method: &dyn AuthMethod, role: &str) -> Result<Auth> {
+		match method.login(role)? {
+			Login::Request { path, body } => self.post_login(&path, None, body),
+			Login::Token(token) => Ok(Auth::new(&token, None)),
+		}
+	}
+
+	/// Get asynchronously a new Auth for role using method
+	async fn fetch_auth_async(&self, method: &dyn AuthMethod, role: &str) -> Result<Auth> {
+		match method.login_async(role).await? {
+			Login::Request { path, body } => self.post_login_async(&path, None, body).await,
+			Login::Token(token) => Ok(Auth::new(&token, None)),
+		}
+	}
+
+	/// Renew the token of role with `auth/token/renew-self` and update its lease
+	pub fn renew_token(&self, role: &str) -> Result<Auth> {
+		let _span = Span::renewal(role).enter();
+		let token = self.token(role)?;
+		let auth = self.post_login("/auth/token/renew-self", Some(&token), json!({}));
+		self.count_result(RENEWALS, role, &auth);
+		self.notify_renewal(role, &auth);
+		Ok(self.cache_auth(role, auth?))
+	}
+
+	/// Renew asynchronously the token of role with `auth/token/renew-self` and update its lease
+	pub async fn renew_token_async(&self, role: &str) -> Result<Auth> {
+		Span::renewal(role)
+			.instrument(async {
+				let token = self.token_async(role).await?;
+				let auth = self
+					.post_login_async("/auth/token/renew-self", Some(&token), json!({}))
+					.await;
+				self.count_result(RENEWALS, role, &auth);
+				self.notify_renewal(role, &auth);
+				Ok(self.cache_auth(role, auth?))
+			})
+			.await
+	}
+
+	/// Renew the token of role for increment and update its lease. The renewal is flagged as
+	/// capped when the ttl granted is shorter because of the max ttl of the token: the token
+	/// can't be renewed beyond and a new login is needed before it expires
+	pub fn renew_token_increment(&self, role: &str, increment: Duration) -> Result<Renewal> {
+		let _span = Span::renewal(role).enter();
+		let token = self.token(role)?;
+		let auth = self.post_login(
+			"/auth/token/renew-self",
+			Some(&token),
+			json!({ "increment": increment.as_secs() }),
+		);
+		self.count_result(RENEWALS, role, &auth);
+		self.notify_renewal(role, &auth);
+		Ok(capped(self.cache_auth(role, auth?), increment))
+	}
+
+	/// Renew asynchronously the token of role for increment and update its lease
+	pub async fn renew_token_increment_async(
+		&self,
+		role: &str,
+		increment: Duration,
+	) -> Result<Renewal> {
+		Span::renewal(role)
+			.instrument(async {
+				let token = self.token_async(role).await?;
+				let auth = self
+					.post_login_async(
+						"/auth/token/renew-self",
+						Some(&token),
+						json!({ "increment": increment.as_secs() }),
+					)
+					.await;
+				self.count_result(RENEWALS, role, &auth);
+				self.notify_renewal(role, &auth);
+				Ok(capped(self.cache_auth(role, auth?), increment))
+			})
+			.await
+	}
+
+	/// Revoke the token of role with `auth/token/revoke-self` and remove it from the cache
+	pub fn logout(&self, role: &str) -> Result<()> {
+		let token = self
+			.auth_read()
+			.get(role)
+			.map(|auth| auth.client_token.clone())
+			.ok_or(Error::NotLogged)?;
+		let request =
+			self.vault_request("POST", "auth/token/revoke-self", Some(&token), String::new())?;
+		self.send(request)?;
+		self.forget_token(role, &token);
+		Ok(())
+	}
+
+	/// Revoke asynchronously the token of role with `auth/token/revoke-self` and remove it from the cache
+	pub async fn logout_async(&self, role: &str) -> Result<()> {
+		let token = self
+			.auth_read()
+			.get(role)
+			.map(|auth| auth.client_token.clone())
+			.ok_or(Error::NotLogged)?;
+		let request =
+			self.vault_request("POST", "auth/token/revoke-self", Some(&token), String::new())?;
+		self.send_async(request).await?;
+		self.forget_token(role, &token);
+		Ok(())
+	}
+
+	/// Revoke all cached tokens (including a static token given to `with_token`) and empty the cache.
+	/// All the tokens are revoked even if some revocation fails, in which case the last error is returned
+	pub fn logout_all(&self) -> Result<()> {
+		let roles: Vec<String> = self.auth_read().keys().cloned().collect();
+		let mut res = Ok(());
+		for role in roles.iter() {
+			if let Err(e) = self.logout(role) {
+				res = Err(e);
 			}
 		}
+		res
+	}
 
-		Ok(self.auth.get(role).unwrap())
+	/// Revoke asynchronously all cached tokens and empty the cache
+	pub async fn logout_all_async(&self) -> Result<()> {
+		let roles: Vec<String> = self.auth_read().keys().cloned().collect();
+		let mut res = Ok(());
+		for role in roles.iter() {
+			if let Err(e) = self.logout_async(role).await {
+				res = Err(e);
+			}
+		}
+		res
 	}
 
-	/// Log in asynchronously to the vault client and return Auth.
-	pub async fn login_async(&mut self, role: &str) -> Result<&Auth> {
-		// login if we are not already logged in or if it's time to renew token
+	/// Remove the token of role from the cache unless it has been replaced by a new login
+	fn forget_token(&self, role: &str, token: &str) {
+		let mut auth = self.auth_write();
+		if auth.get(role).is_some_and(|auth| auth.client_token.as_str() == token) {
+			auth.remove(role);
+		}
+	}
 
-		if !self.is_logged(role) {
-			let url = format!("{}{}", &self.url, &self.login_path);
-			let body = json!({"role": role, "jwt": &self.jwt}).to_string();
-			let mut res = self
-				.client
-				.post_async(url, body)
-				.await
-				.map_err(|e| Error::ClientError { source: e })?;
-			let status = res.status();
-			if status == StatusCode::OK {
-				// parse vault response and cache important information
-				let auth_value: Value = res
-					.json()
-					.await
-					.map_err(|e| Error::ParseError { source: e })?;
-				let lease_duration = auth_value["auth"]["lease_duration"]
-					.as_u64()
-					.unwrap_or(0u64);
-				let renewable = auth_value["auth"]["renewable"].as_bool().unwrap_or(false);
-				let auth = Auth::new(
-					auth_value["auth"]["client_token"].as_str().unwrap_or(""),
-					if renewable {
-						Some(Duration::from_secs(lease_duration))
-					} else {
-						None
-					},
-				);
-				// insert and forget old value if any
-				let _ = self.auth.insert(role.to_owned(), auth);
-			} else {
-				// parse vault error
-				let errors: VaultErrors = res
-					.json()
-					.await
-					.map_err(|e| Error::ParseError { source: e })?;
-				return Err(Error::VaultError(status, errors.errors.join("\n")));
+	/// Return a guard giving access to the client that revokes the token of role when dropped
+	pub fn revoke_on_drop(&self, role: &str) -> RevokeOnDrop<'_> {
+		RevokeOnDrop {
+			client: self,
+			role: role.to_owned(),
+		}
+	}
+
+	/// Post a login request and return the parsed Auth. The request is not retried as a login
+	/// failing after the server committed it would create another token
+	fn post_login(&self, login_path: &str, token: Option<&str>, mut body: Value) -> Result<Auth> {
+		let request = self.vault_request("POST", login_path, token, body.to_string())?;
+		scrub_value(&mut body);
+		Auth::from_value(self.send(request)?)
+	}
+
+	/// Post asynchronously a login request and return the parsed Auth
+	async fn post_login_async(
+		&self,
+		login_path: &str,
+		token: Option<&str>,
+		mut body: Value,
+	) -> Result<Auth> {
+		let request = self.vault_request("POST", login_path, token, body.to_string())?;
+		scrub_value(&mut body);
+		Auth::from_value(self.send_async(request).await?)
+	}
+
+	/// Send a request to any vault endpoint with the token of role and return the json response
+	/// (Null for empty responses). path is relative to the vault url (ex: `sys/leases/lookup`)
+	pub fn request(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: Option<Value>,
+	) -> Result<Value> {
+		let request = self.vault_request(
+			method,
+			path,
+			Some(&self.token(role)?),
+			body.map(|body| body.to_string()).unwrap_or_default(),
+		)?;
+		self.send(request)
+	}
+
+	/// Send asynchronously a request to any vault endpoint with the token of role
+	pub async fn request_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: Option<Value>,
+	) -> Result<Value> {
+		let request = self.vault_request(
+			method,
+			path,
+			Some(&self.token_async(role).await?),
+			body.map(|body| body.to_string()).unwrap_or_default(),
+		)?;
+		self.send_async(request).await
+	}
+
+	/// Return the cached token of role. With auto login, log in first when the token is
+	/// missing or expired
+	pub(crate) fn token(&self, role: &str) -> Result<Token> {
+		match self.cached_token(role) {
+			Err(Error::NotLogged) if self.auto_login => Ok(self.login(role)?.client_token),
+			res => res,
+		}
+	}
+
+	/// Return the cached token of role, logging in asynchronously with auto login
+	pub(crate) async fn token_async(&self, role: &str) -> Result<Token> {
+		match self.cached_token(role) {
+			Err(Error::NotLogged) if self.auto_login => {
+				Ok(self.login_async(role).await?.client_token)
 			}
+			res => res,
+		}
+	}
+
+	/// Return the cached token of role. With auto login an expired token is considered missing
+	fn cached_token(&self, role: &str) -> Result<Token> {
+		self.auth_read()
+			.get(role)
+			.filter(|auth| !self.auto_login || auth.is_valid())
+			.map(|auth| auth.client_token.clone())
+			.ok_or(Error::NotLogged)
+	}
+
+	/// Build a request to a path relative to the vault url with an optional token
+	pub(crate) fn vault_request<B>(
+		&self,
+		method: &str,
+		path: &str,
+		token: Option<&str>,
+		body: B,
+	) -> Result<Request<B>> {
+		let uri = format!(
+			"{}/{}",
+			self.endpoints.active(),
+			path.trim_start_matches('/')
+		);
+		let mut builder = Request::builder().uri(uri).method(method);
+		if let Some(token) = token {
+			builder = builder.header("X-Vault-Token", token);
+		}
+		if let Some(ref namespace) = self.namespace {
+			builder = builder.header("X-Vault-Namespace", namespace.as_str());
 		}
+		builder.body(body).map_err(|e| Error::HttpError { source: e })
+	}
 
-		Ok(self.auth.get(role).unwrap())
+	/// Send an idempotent request and return the parsed json response or Null if there is no
+	/// content. The request is sent again on transient failures according to the retry policy, so
+	/// it must not be used for writes creating something (tokens, certificates, leases...) that
+	/// the server may have committed before the failure
+	pub(crate) fn send_idempotent(&self, request: Request<String>) -> Result<Value> {
+		let policy = match self.retry {
+			Some(ref policy) => policy,
+			None => return self.send(request),
+		};
+		let mut attempt = 1;
+		loop {
+			match self.send(clone_request(&request)) {
+				Err(e) if policy.should_retry(attempt, &e) => {
+					log::debug!(
+						"retrying request to {}: {}",
+						trace::redact_path(request.uri().path()),
+						e
+					);
+					thread::sleep(policy.delay(attempt));
+					attempt += 1;
+				}
+				res => return res,
+			}
+		}
+	}
+
+	/// Send asynchronously an idempotent request and return the parsed json response or Null if
+	/// there is no content. The request is sent again on transient failures according to the
+	/// retry policy
+	pub(crate) async fn send_idempotent_async(&self, request: Request<String>) -> Result<Value> {
+		let policy = match self.retry {
+			Some(ref policy) => policy,
+			None => return self.send_async(request).await,
+		};
+		let mut attempt = 1;
+		loop {
+			match self.send_async(clone_request(&request)).await {
+				Err(e) if policy.should_retry(attempt, &e) => {
+					log::debug!(
+						"retrying request to {}: {}",
+						trace::redact_path(request.uri().path()),
+						e
+					);
+					self.runtime.sleep(policy.delay(attempt)).await;
+					attempt += 1;
+				}
+				res => return res,
+			}
+		}
+	}
+
+	/// Send a request once and return the parsed json response or Null if there is no content.
+	/// The request is sent to the next vault server when one is unreachable or a standby
+	pub(crate) fn send(&self, mut request: Request<String>) -> Result<Value> {
+		for _ in 1..self.endpoints.len() {
+			match self.send_to(clone_request(&request)) {
+				Err(e) if should_fail_over(&e) && self.endpoints.fail_over(&mut request) => {}
+				res => return res,
+			}
+		}
+		self.send_to(request)
+	}
+
+	/// Send asynchronously a request once and return the parsed json response or Null if there
+	/// is no content. The request is sent to the next vault server when one is unreachable or a
+	/// standby
+	pub(crate) async fn send_async(&self, mut request: Request<String>) -> Result<Value> {
+		for _ in 1..self.endpoints.len() {
+			match self.send_to_async(clone_request(&request)).await {
+				Err(e) if should_fail_over(&e) && self.endpoints.fail_over(&mut request) => {}
+				res => return res,
+			}
+		}
+		self.send_to_async(request).await
+	}
+
+	/// Send a request to its server and return the parsed json response or Null if there is no
+	/// content. The redirects of standby nodes to the active node are followed
+	fn send_to(&self, mut request: Request<String>) -> Result<Value> {
+		let path = request.uri().path().to_owned();
+		let mut redirects = 0;
+		let res = loop {
+			let start = Instant::now();
+			let attempt = self.before_send(clone_request(&request))?;
+			let (method, uri) = (attempt.method().clone(), attempt.uri().clone());
+			let res = self.after_send(&method, &uri, self.transport.send(attempt));
+			let status = res.as_ref().ok().map(|res| res.status());
+			self.observe(request.method(), start, status);
+			trace::request(request.method(), &path, start, status);
+			let res = res?;
+			// replay the request (body and token) on the active node
+			match redirect_uri(request.uri(), &res)
+				.filter(|uri| self.may_follow(request.uri(), uri))
+			{
+				Some(uri) if redirects < MAX_REDIRECTS => {
+					log::debug!(
+						"following redirect of {} to {}",
+						trace::redact_path(&path),
+						uri.authority().map_or("", |authority| authority.as_str())
+					);
+					*request.uri_mut() = uri;
+					redirects += 1;
+				}
+				_ => break res,
+			}
+		};
+		parse_response(&path, res)
+	}
+
+	/// Send asynchronously a request to its server and return the parsed json response or Null
+	/// if there is no content. The redirects of standby nodes to the active node are followed
+	async fn send_to_async(&self, mut request: Request<String>) -> Result<Value> {
+		let path = request.uri().path().to_owned();
+		let mut redirects = 0;
+		let res = loop {
+			let start = Instant::now();
+			let attempt = self.before_send(clone_request(&request))?;
+			let (method, uri) = (attempt.method().clone(), attempt.uri().clone());
+			let res = self.transport.send_async(attempt).await;
+			let res = self.after_send(&method, &uri, res);
+			let status = res.as_ref().ok().map(|res| res.status());
+			self.observe(request.method(), start, status);
+			trace::request(request.method(), &path, start, status);
+			let res = res?;
+			match redirect_uri(request.uri(), &res)
+				.filter(|uri| self.may_follow(request.uri(), uri))
+			{
+				Some(uri) if redirects < MAX_REDIRECTS => {
+					log::debug!(
+						"following redirect of {} to {}",
+						trace::redact_path(&path),
+						uri.authority().map_or("", |authority| authority.as_str())
+					);
+					*request.uri_mut() = uri;
+					redirects += 1;
+				}
+				_ => break res,
+			}
+		};
+		parse_response(&path, res)
+	}
+
+	/// Check if the redirect of a request sent to from can be replayed with its token on to: only
+	/// redirects to the same scheme and host, or to one of the vault urls of the client, are
+	/// followed so that the token is never sent to another host or in clear
+	fn may_follow(&self, from: &Uri, to: &Uri) -> bool {
+		let trusted = same_origin(from, to) || self.endpoints.contains_origin(to);
+		if !trusted {
+			log::warn!(
+				"not following the redirect of {} to the unknown {}",
+				trace::redact_path(from.path()),
+				to.authority().map_or("", |authority| authority.as_str())
+			);
+		}
+		trusted
+	}
+
+	/// Send a request once and return the response with its body unread, for bodies that are
+	/// not json or too large to be buffered. Errors are parsed as usual
+	pub(crate) fn send_raw<B: Into<Body>>(&self, request: Request<B>) -> Result<Response<Body>> {
+		let path = request.uri().path().to_owned();
+		let request = self.before_send(request.map(Into::into))?;
+		let (method, uri) = (request.method().clone(), request.uri().clone());
+		let res = self.transport.send_stream(request);
+		let mut res = self.after_send(&method, &uri, res)?;
+		let status = res.status();
+		if status.is_success() {
+			Ok(res)
+		} else {
+			let mut body = Vec::new();
+			res.body_mut()
+				.read_to_end(&mut body)
+				.map_err(Error::transport)?;
+			Err(vault_error(status, &path, &String::from_utf8_lossy(&body)))
+		}
+	}
+
+	/// Send asynchronously a request once and return the response with its body unread
+	pub(crate) async fn send_raw_async<B: Into<AsyncBody>>(
+		&self,
+		request: Request<B>,
+	) -> Result<Response<AsyncBody>> {
+		let path = request.uri().path().to_owned();
+		let request = self.before_send(request.map(Into::into))?;
+		let (method, uri) = (request.method().clone(), request.uri().clone());
+		let res = self.transport.send_stream_async(request).await;
+		let mut res = self.after_send(&method, &uri, res)?;
+		let status = res.status();
+		if status.is_success() {
+			Ok(res)
+		} else {
+			let body = res
+				.body_mut()
+				.read_bytes()
+				.await
+				.map_err(Error::transport)?;
+			Err(vault_error(status, &path, &String::from_utf8_lossy(&body)))
+		}
 	}
 
 	/// Get a secret from vault server and reschedule a renew with role if necessary
@@ -139,55 +783,122 @@ impl VaultClient {
 		path: &str,
 		kwargs: Option<&Vec<(&str, &str)>>,
 	) -> Result<Secret> {
-		if let Some(auth) = self.auth.get(role) {
-			let uri = format!("{}/{}", &self.url, path);
-			// transform the kwargs into a json object
-			let body = kwargs
-				.map(|kwargs| {
-					kwargs.iter().fold(Map::new(), |mut m, (k, v)| {
-						m.insert((*k).to_owned(), Value::String((*v).to_owned()));
-						m
-					})
-				})
-				.map(|o| Value::Object(o))
-				.unwrap_or(Value::Null);
-			// build the request
-			let request = Request::builder()
-				.uri(uri)
-				.method(method)
-				.header("X-Vault-Token", auth.client_token.as_str())
-				.body(body.to_string())
-				.map_err(|e| Error::HttpError { source: e })?;
-			// async send the request
-			let mut res = self
-				.client
-				.send(request)
-				.map_err(|e| Error::ClientError { source: e })?;
-			// handle the response
-			let status = res.status();
-			return if status == StatusCode::OK {
-				// parse vault response
-				let mut secret_value: Value =
-					res.json().map_err(|e| Error::ParseError { source: e })?;
-
-				let duration = secret_value
-					.get("lease_duration")
-					.map(|o| o.as_u64().unwrap_or(0u64))
-					.filter(|o| *o != 0u64)
-					.map(|o| Duration::from_secs(o * 2 / 3));
-				// return the parsed secret (only the data part)
-				Ok(Secret::new(secret_value["data"].take(), duration))
-			} else {
-				// parse vault error
-				let errors: VaultErrors =
-					res.json().map_err(|e| Error::ParseError { source: e })?;
-				Err(Error::VaultError(status, errors.errors.join("\n")))
-			};
-		} else {
-			Err(Error::NotLogged)
+		let _span = Span::secret(role, path).enter();
+		match self
+			.secret_cache(method, kwargs)
+			.and_then(|cache| cache.get(role, path))
+		{
+			Some(secret) => {
+				let secret = Ok(secret);
+				self.count_fetch(role, true, &secret);
+				secret
+			}
+			None => {
+				let secret = self.get_secret_uncached(role, method, path, kwargs);
+				self.count_fetch(role, false, &secret);
+				secret
+			}
 		}
 	}
 
+	/// Get a secret from vault server sending any serializable body (arrays, numbers, nested
+	/// objects...) instead of string arguments
+	pub fn get_secret_with_body<B: Serialize + ?Sized>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: &B,
+	) -> Result<Secret> {
+		self.fetch_secret(role, path, || {
+			self.fetch_with_body(role, method, path, body)
+		})
+	}
+
+	/// Fetch a secret sending body without tracing nor counting the fetch
+	pub(crate) fn fetch_with_body<B: Serialize + ?Sized>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: &B,
+	) -> Result<Secret> {
+		let body = serde_json::to_string(body).map_err(|e| Error::ParseError { source: e })?;
+		let request = self.vault_request(method, path, Some(&self.token(role)?), body)?;
+		Ok(secret_from_value(self.send_idempotent(request)?, &self.renewal))
+	}
+
+	/// Run fetch in the span of the secret of role at path, counting it as a cache miss
+	fn fetch_secret(
+		&self,
+		role: &str,
+		path: &str,
+		fetch: impl FnOnce() -> Result<Secret>,
+	) -> Result<Secret> {
+		let _span = Span::secret(role, path).enter();
+		let secret = fetch();
+		self.count_fetch(role, false, &secret);
+		secret
+	}
+
+	/// Run asynchronously fetch in the span of the secret of role at path, counting it as a cache
+	/// miss
+	async fn fetch_secret_async(
+		&self,
+		role: &str,
+		path: &str,
+		fetch: impl Future<Output = Result<Secret>>,
+	) -> Result<Secret> {
+		Span::secret(role, path)
+			.instrument(async {
+				let secret = fetch.await;
+				self.count_fetch(role, false, &secret);
+				secret
+			})
+			.await
+	}
+
+	/// Get a secret from vault server bypassing the cache and adding headers to the request
+	/// (overriding the default ones)
+	pub fn get_secret_with_headers(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+		headers: &[(&str, &str)],
+	) -> Result<Secret> {
+		self.fetch_secret(role, path, || {
+			let body = kwargs_body(kwargs).to_string();
+			let mut request = self.vault_request(method, path, Some(&self.token(role)?), body)?;
+			add_headers(&mut request, headers)?;
+			Ok(secret_from_value(self.send_idempotent(request)?, &self.renewal))
+		})
+	}
+
+	/// Get a secret asynchronously from vault server bypassing the cache and adding headers to
+	/// the request
+	pub async fn get_secret_with_headers_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+		headers: &[(&str, &str)],
+	) -> Result<Secret> {
+		self.fetch_secret_async(role, path, async {
+			let body = kwargs_body(kwargs).to_string();
+			let mut request =
+				self.vault_request(method, path, Some(&self.token_async(role).await?), body)?;
+			add_headers(&mut request, headers)?;
+			Ok(secret_from_value(
+				self.send_idempotent_async(request).await?,
+				&self.renewal,
+			))
+		})
+		.await
+	}
+
 	/// Get a secret asynchronously from vault server and reschedule a renew with role if necessary
 	pub async fn get_secret_async(
 		&self,
@@ -196,112 +907,575 @@ impl VaultClient {
 		path: &str,
 		kwargs: Option<&Vec<(&str, &str)>>,
 	) -> Result<Secret> {
-		if let Some(auth) = self.auth.get(role) {
-			let uri = format!("{}/{}", &self.url, path);
-			// transform the kwargs into a json object
-			let body = kwargs
-				.map(|kwargs| {
-					kwargs.iter().fold(Map::new(), |mut m, (k, v)| {
-						m.insert((*k).to_owned(), Value::String((*v).to_owned()));
-						m
-					})
-				})
-				.map(|o| Value::Object(o))
-				.unwrap_or(Value::Null);
-			// build the request
-			let request = Request::builder()
-				.uri(uri)
-				.method(method)
-				.header("X-Vault-Token", auth.client_token.as_str())
-				.body(body.to_string())
-				.map_err(|e| Error::HttpError { source: e })?;
-			// async send the request
-			let mut res = self
-				.client
-				.send_async(request)
-				.await
-				.map_err(|e| Error::ClientError { source: e })?;
-			// handle the response
-			let status = res.status();
-			return if status == StatusCode::OK {
-				// parse vault response
-				let mut secret_value: Value = res
-					.json()
-					.await
-					.map_err(|e| Error::ParseError { source: e })?;
-
-				let duration = secret_value
-					.get("lease_duration")
-					.map(|o| o.as_u64().unwrap_or(0u64))
-					.filter(|o| *o != 0u64)
-					.map(|o| Duration::from_secs(o * 2 / 3));
-				// return the parsed secret (only the data part)
-				Ok(Secret::new(secret_value["data"].take(), duration))
-			} else {
-				// parse vault error
-				let errors: VaultErrors = res
-					.json()
-					.await
-					.map_err(|e| Error::ParseError { source: e })?;
-				Err(Error::VaultError(status, errors.errors.join("\n")))
-			};
-		} else {
-			Err(Error::NotLogged)
-		}
+		Span::secret(role, path)
+			.instrument(async {
+				match self
+					.secret_cache(method, kwargs)
+					.and_then(|cache| cache.get(role, path))
+				{
+					Some(secret) => {
+						let secret = Ok(secret);
+						self.count_fetch(role, true, &secret);
+						secret
+					}
+					None => {
+						let secret = self
+							.get_secret_uncached_async(role, method, path, kwargs)
+							.await;
+						self.count_fetch(role, false, &secret);
+						secret
+					}
+				}
+			})
+			.await
+	}
+
+	/// Get a secret asynchronously from vault server sending any serializable body
+	pub async fn get_secret_with_body_async<B: Serialize + ?Sized>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: &B,
+	) -> Result<Secret> {
+		self.fetch_secret_async(
+			role,
+			path,
+			self.fetch_with_body_async(role, method, path, body),
+		)
+		.await
+	}
+
+	/// Fetch asynchronously a secret sending body without tracing nor counting the fetch
+	pub(crate) async fn fetch_with_body_async<B: Serialize + ?Sized>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: &B,
+	) -> Result<Secret> {
+		let body = serde_json::to_string(body).map_err(|e| Error::ParseError { source: e })?;
+		let request =
+			self.vault_request(method, path, Some(&self.token_async(role).await?), body)?;
+		Ok(secret_from_value(
+			self.send_idempotent_async(request).await?,
+			&self.renewal,
+		))
+	}
+
+	/// Get a secret from vault server or None if nothing exists at path
+	pub fn try_get_secret(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Option<Secret>> {
+		not_found_as_none(self.get_secret(role, method, path, kwargs))
+	}
+
+	/// Get a secret asynchronously from vault server or None if nothing exists at path
+	pub async fn try_get_secret_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Option<Secret>> {
+		not_found_as_none(self.get_secret_async(role, method, path, kwargs).await)
+	}
+
+	/// Get a secret from vault server in namespace instead of the namespace of the client
+	pub fn get_secret_in_namespace(
+		&self,
+		role: &str,
+		namespace: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		self.fetch_secret(role, path, || {
+			let body = kwargs_body(kwargs);
+			let mut request =
+				self.vault_request(method, path, Some(&self.token(role)?), body.to_string())?;
+			set_namespace(&mut request, namespace)?;
+			Ok(secret_from_value(self.send_idempotent(request)?, &self.renewal))
+		})
+	}
+
+	/// Get a secret asynchronously from vault server in namespace instead of the namespace of the client
+	pub async fn get_secret_in_namespace_async(
+		&self,
+		role: &str,
+		namespace: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		self.fetch_secret_async(role, path, async {
+			let body = kwargs_body(kwargs);
+			let mut request = self.vault_request(
+				method,
+				path,
+				Some(&self.token_async(role).await?),
+				body.to_string(),
+			)?;
+			set_namespace(&mut request, namespace)?;
+			Ok(secret_from_value(
+				self.send_idempotent_async(request).await?,
+				&self.renewal,
+			))
+		})
+		.await
+	}
+
+	/// Get a secret from vault server and deserialize its data into T
+	pub fn get_secret_as<T: DeserializeOwned>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<TypedSecret<T>> {
+		self.get_secret(role, method, path, kwargs)?.deserialize()
+	}
+
+	/// Get a secret asynchronously from vault server and deserialize its data into T
+	pub async fn get_secret_as_async<T: DeserializeOwned>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<TypedSecret<T>> {
+		self.get_secret_async(role, method, path, kwargs)
+			.await?
+			.deserialize()
+	}
+
+	/// Extract the credentials of a dynamic secrets engine response keeping their lease
+	pub(crate) fn leased<T: DeserializeOwned>(&self, value: Value) -> Result<TypedSecret<T>> {
+		secret_from_value(value, &self.renewal).deserialize()
+	}
+
+	/// List the keys under path (folders end with a `/`)
+	pub fn list_secrets(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}?list=true", path.trim_end_matches('/')),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		keys_from_value(self.send(request)?)
+	}
+
+	/// List asynchronously the keys under path (folders end with a `/`)
+	pub async fn list_secrets_async(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}?list=true", path.trim_end_matches('/')),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		keys_from_value(self.send_async(request).await?)
 	}
 
 	/// Return a hashmap of mountpoints and backend type concatenated with `options.version` if present
 	/// the given role should have read access to vault api /sys/mounts
 	pub fn get_mounts(&self, role: &str) -> Result<HashMap<String, String>> {
-		if let Some(auth) = self.auth.get(role) {
-			let uri = format!("{}/sys/mounts", &self.url);
-			let request = Request::builder()
-				.uri(uri)
-				.method("GET")
-				.header("X-Vault-Token", auth.client_token.as_str())
-				.body(())
-				.map_err(|e| Error::HttpError { source: e })?;
-			let mut res = self
-				.client
-				.send(request)
-				.map_err(|e| Error::ClientError { source: e })?;
-			let status = res.status();
-			return if status == StatusCode::OK {
-				// parse vault response
-				let mounts_value: Value =
-					res.json().map_err(|e| Error::ParseError { source: e })?;
-
-				if let Some(Value::Object(map)) = mounts_value.get("data") {
-					let mut mounts = HashMap::new();
-					for (mount_point, mount) in map.iter() {
-						let mount_type = mount
-							.get("type")
-							.and_then(|v| v.as_str())
-							.ok_or_else(|| Error::UndefinedMountType(mount_point.to_owned()))?;
-						let mount_version = mount
-							.get("options")
-							.and_then(|v| v.get("version"))
-							.and_then(|v| v.as_str());
-						mounts.insert(
-							mount_point.to_owned(),
-							mount_version
-								.and_then(|s| Some(mount_type.to_owned() + s))
-								.or(Some(mount_type.to_owned()))
-								.unwrap(),
-						);
-					}
-					Ok(mounts)
-				} else {
-					Err(Error::MountsNotFound)
-				}
-			} else {
-				// parse vault error
-				let errors: VaultErrors =
-					res.json().map_err(|e| Error::ParseError { source: e })?;
-				Err(Error::VaultError(status, errors.errors.join("\n")))
-			};
+		let request = self.vault_request("GET", "sys/mounts", Some(&self.token(role)?), String::new())?;
+		let mounts_value = self.send(request)?;
+		if let Some(Value::Object(map)) = mounts_value.get("data") {
+			let mut mounts = HashMap::new();
+			for (mount_point, mount) in map.iter() {
+				let mount_type = mount
+					.get("type")
+					.and_then(|v| v.as_str())
+					.ok_or_else(|| Error::UndefinedMountType(mount_point.to_owned()))?;
+				let mount_version = mount
+					.get("options")
+					.and_then(|v| v.get("version"))
+					.and_then(|v| v.as_str());
+				mounts.insert(
+					mount_point.to_owned(),
+					mount_version
+						.and_then(|s| Some(mount_type.to_owned() + s))
+						.or(Some(mount_type.to_owned()))
+						.unwrap(),
+				);
+			}
+			Ok(mounts)
 		} else {
+			Err(Error::MountsNotFound)
+		}
+	}
+}
+
+/// Guard that revokes the token of a role when dropped
+#[derive(Debug)]
+pub struct RevokeOnDrop<'a> {
+	client: &'a VaultClient,
+	role: String,
+}
+
+impl<'a> Deref for RevokeOnDrop<'a> {
+	type Target = VaultClient;
+
+	fn deref(&self) -> &Self::Target {
+		self.client
+	}
+}
+
+impl<'a> Drop for RevokeOnDrop<'a> {
+	fn drop(&mut self) {
+		if let Err(e) = self.client.logout(&self.role) {
+			log::warn!("unable to revoke token of role {}: {}", self.role, e);
+		}
+	}
+}
+
+/// Build an error from the body of a vault error response, which may not be json when
+/// it comes from a proxy
+pub(crate) fn vault_error(status: StatusCode, path: &str, body: &str) -> Error {
+	let errors = match serde_json::from_str::<VaultErrors>(body) {
+		Ok(errors) => errors.errors,
+		Err(_) if body.trim().is_empty() => Vec::new(),
+		Err(_) => vec![body.trim().to_owned()],
+	};
+	Error::from_status(status, path, errors)
+}
+
+/// Add headers to a request replacing existing ones
+fn add_headers<B>(request: &mut Request<B>, headers: &[(&str, &str)]) -> Result<()> {
+	for (name, value) in headers.iter() {
+		let name = HeaderName::from_bytes(name.as_bytes())
+			.map_err(|e| Error::HttpError { source: e.into() })?;
+		let value =
+			HeaderValue::from_str(value).map_err(|e| Error::HttpError { source: e.into() })?;
+		request.headers_mut().insert(name, value);
+	}
+	Ok(())
+}
+
+/// Turn a not found error into None
+pub(crate) fn not_found_as_none<T>(res: Result<T>) -> Result<Option<T>> {
+	match res {
+		Ok(value) => Ok(Some(value)),
+		Err(Error::NotFound { .. }) => Ok(None),
+		Err(e) => Err(e),
+	}
+}
+
+/// Copy a request to be able to send it again
+fn clone_request(request: &Request<String>) -> Request<String> {
+	let mut clone = Request::new(request.body().clone());
+	*clone.method_mut() = request.method().clone();
+	*clone.uri_mut() = request.uri().clone();
+	*clone.version_mut() = request.version();
+	*clone.headers_mut() = request.headers().clone();
+	clone
+}
+
+/// Return the renewal of auth, capped if its ttl is shorter than the increment requested
+fn capped(auth: Auth, increment: Duration) -> Renewal {
+	let capped = auth
+		.lease
+		.as_ref()
+		.is_some_and(|lease| lease.lease_duration + CAP_TOLERANCE < increment);
+	Renewal { auth, capped }
+}
+
+/// Parse the json body of a response to path, Null if there is no content, or the error
+fn parse_response(path: &str, res: Response<Vec<u8>>) -> Result<Value> {
+	let status = res.status();
+	if status == StatusCode::OK {
+		// parse vault response
+		serde_json::from_slice(res.body()).map_err(|e| Error::ParseError { source: e })
+	} else if status == StatusCode::NO_CONTENT {
+		Ok(Value::Null)
+	} else {
+		// parse vault error
+		Err(vault_error(
+			status,
+			path,
+			&String::from_utf8_lossy(res.body()),
+		))
+	}
+}
+
+/// Return the uri a standby node redirects a request to uri, resolving relative locations
+fn redirect_uri<B>(uri: &Uri, res: &Response<B>) -> Option<Uri> {
+	if res.status() != StatusCode::TEMPORARY_REDIRECT {
+		return None;
+	}
+	let location = res.headers().get("Location")?.to_str().ok()?;
+	let location: Uri = location.parse().ok()?;
+	if location.scheme().is_some() {
+		return Some(location);
+	}
+	let mut parts = location.into_parts();
+	parts.scheme = uri.scheme().cloned();
+	parts.authority = uri.authority().cloned();
+	Uri::from_parts(parts).ok()
+}
+
+/// Override the namespace of a request
+fn set_namespace(request: &mut Request<String>, namespace: &str) -> Result<()> {
+	let value = HeaderValue::from_str(namespace.trim_matches('/'))
+		.map_err(|e| Error::HttpError { source: e.into() })?;
+	request.headers_mut().insert("X-Vault-Namespace", value);
+	Ok(())
+}
+
+/// Transform the kwargs into a json object
+pub(crate) fn kwargs_body(kwargs: Option<&Vec<(&str, &str)>>) -> Value {
+	kwargs
+		.map(|kwargs| {
+			kwargs.iter().fold(Map::new(), |mut m, (k, v)| {
+				m.insert((*k).to_owned(), Value::String((*v).to_owned()));
+				m
+			})
+		})
+		.map(|o| Value::Object(o))
+		.unwrap_or(Value::Null)
+}
+
+/// Extract the keys of a list response
+pub(crate) fn keys_from_value(mut value: Value) -> Result<Vec<String>> {
+	serde_json::from_value(value["data"]["keys"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Extract a Secret (only the data part) from a vault response, shortening its lease duration
+/// to the renew delay of policy
+pub(crate) fn secret_from_value(mut secret_value: Value, policy: &RenewalPolicy) -> Secret {
+	let duration = secret_value
+		.get("lease_duration")
+		.map(|o| o.as_u64().unwrap_or(0u64))
+		.filter(|o| *o != 0u64)
+		.map(|o| policy.renew_delay(Duration::from_secs(o)));
+	let mut secret = Secret::new(secret_value["data"].take(), duration);
+	secret.lease_id = secret_value
+		.get("lease_id")
+		.and_then(|v| v.as_str())
+		.filter(|s| !s.is_empty())
+		.map(|s| s.to_owned());
+	secret.metadata = ResponseMetadata::from_value(&secret_value);
+	secret
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+
+	#[test]
+	fn client_is_send_sync() {
+		fn assert_send_sync<T: Send + Sync>() {}
+		assert_send_sync::<VaultClient>();
+	}
+
+	#[test]
+	fn tokens_are_not_copied_per_request() {
+		let client = Scripted::new().client();
+		let token = client.token(TOKEN_ROLE).unwrap();
+		assert!(Arc::ptr_eq(&token, &client.token(TOKEN_ROLE).unwrap()));
+	}
+
+	#[test]
+	fn token_kept_until_revoked() {
+		let transport = Scripted::new();
+		transport
+			.on("POST", "auth/token/revoke-self", 403, json!({"errors": ["denied"]}))
+			.on("POST", "auth/token/revoke-self", 204, Value::Null);
+		let client = transport.client();
+		assert!(matches!(client.logout(TOKEN_ROLE), Err(Error::Forbidden { .. })));
+		assert!(client.auth(TOKEN_ROLE).is_some());
+		client.logout(TOKEN_ROLE).unwrap();
+		assert!(client.auth(TOKEN_ROLE).is_none());
+	}
+
+	/// Auth method slow enough to have concurrent logins overlap
+	#[derive(Debug)]
+	struct SlowAuth;
+
+	impl AuthMethod for SlowAuth {
+		fn login(&self, _role: &str) -> Result<Login> {
+			thread::sleep(Duration::from_millis(100));
+			Ok(Login::Request {
+				path: "auth/slow/login".to_owned(),
+				body: json!({}),
+			})
+		}
+	}
+
+	#[test]
+	fn concurrent_logins_sent_once() {
+		let transport = Scripted::new();
+		transport.on(
+			"POST",
+			"auth/slow/login",
+			200,
+			json!({"auth": {"client_token": "s.login", "lease_duration": 3600, "renewable": true}}),
+		);
+		let client = Arc::new(transport.client());
+		let logins: Vec<_> = (0..2)
+			.map(|_| {
+				let client = Arc::clone(&client);
+				thread::spawn(move || {
+					client
+						.login_with(&SlowAuth, "app")
+						.map(|auth| auth.client_token.clone())
+				})
+			})
+			.collect();
+		for login in logins {
+			assert_eq!(login.join().unwrap().unwrap().as_str(), "s.login");
+		}
+		assert_eq!(transport.requests(), ["POST auth/slow/login"]);
+		assert_eq!(client.flights.len(), 0);
+	}
+
+	#[test]
+	fn only_idempotent_requests_retried() {
+		let transport = Scripted::new();
+		transport
+			.on("GET", "secret/foo", 503, json!({"errors": []}))
+			.on("GET", "secret/foo", 200, json!({"data": {"foo": "bar"}}))
+			.on("POST", "auth/token/create", 503, json!({"errors": []}))
+			.on("POST", "auth/slow/login", 503, json!({"errors": []}));
+		let client = transport
+			.builder()
+			.retry(RetryPolicy::new(3, Duration::from_millis(1)))
+			.build()
+			.unwrap();
+		client.get_secret(TOKEN_ROLE, "GET", "secret/foo", None).unwrap();
+		assert!(client
+			.request(TOKEN_ROLE, "POST", "auth/token/create", None)
+			.is_err());
+		assert!(client.login_with(&SlowAuth, "app").is_err());
+		assert_eq!(
+			transport.requests(),
+			[
+				"GET secret/foo",
+				"GET secret/foo",
+				"POST auth/token/create",
+				"POST auth/slow/login"
+			]
+		);
+	}
+
+	#[test]
+	fn renewal_capped_by_max_ttl() {
+		let auth = Auth::new("s.token", Some(Duration::from_secs(600)));
+		assert!(!capped(auth.clone(), Duration::from_secs(600)).capped);
+		// rounded down by the server
+		assert!(!capped(auth.clone(), Duration::from_secs(601)).capped);
+		let renewal = capped(auth, Duration::from_secs(3600));
+		assert!(renewal.capped);
+		assert_eq!(renewal.auth.client_token.as_str(), "s.token");
+		assert!(!capped(Auth::new("s.root", None), Duration::from_secs(3600)).capped);
+	}
+
+	#[test]
+	fn error_from_non_json_body() {
+		assert!(matches!(
+			vault_error(StatusCode::BAD_GATEWAY, "/v1/secret/foo", "<html>bad gateway</html>"),
+			Error::VaultError { status: StatusCode::BAD_GATEWAY, errors, .. } if errors == ["<html>bad gateway</html>"]
+		));
+		assert!(matches!(
+			vault_error(StatusCode::FORBIDDEN, "/v1/secret/foo", r#"{"errors":["permission denied"]}"#),
+			Error::Forbidden { path, errors } if path == "/v1/secret/foo" && errors == ["permission denied"]
+		));
+	}
+
+	#[test]
+	fn not_found_is_none() {
+		assert!(matches!(
+			not_found_as_none::<()>(Err(Error::from_status(
+				StatusCode::NOT_FOUND,
+				"/v1/secret/foo",
+				vec![]
+			))),
+			Ok(None)
+		));
+		assert!(matches!(
+			not_found_as_none::<()>(Err(Error::NotLogged)),
 			Err(Error::NotLogged)
+		));
+	}
+
+	#[test]
+	fn request_headers_override() {
+		let mut request = Request::get("http://localhost:8200/v1/secret/foo")
+			.header("X-Vault-Request", "false")
+			.body(())
+			.unwrap();
+		add_headers(&mut request, &[("X-Vault-Request", "true")]).unwrap();
+		assert_eq!(request.headers()["x-vault-request"], "true");
+		assert!(add_headers(&mut request, &[("X Vault", "true")]).is_err());
+	}
+
+	#[test]
+	fn keys_from_list_response() {
+		let keys = keys_from_value(json!({"data": {"keys": ["foo", "foo/"]}})).unwrap();
+		assert_eq!(keys, vec!["foo", "foo/"]);
+	}
+
+	#[test]
+	fn secret_lease_is_jittered() {
+		let policy = RenewalPolicy::default().jitter(0.5);
+		let value = json!({"lease_id": "db/creds/app/abcd", "lease_duration": 300, "data": {}});
+		for _ in 0..10 {
+			let delay = secret_from_value(value.clone(), &policy)
+				.renew_delay()
+				.unwrap();
+			assert!(delay <= Duration::from_secs(134) && delay >= Duration::from_secs(66));
 		}
+		let secret = secret_from_value(value, &RenewalPolicy::new(0.5));
+		assert_eq!(secret.duration(), Some(Duration::from_secs(150)));
+	}
+
+	#[test]
+	fn standby_redirect_uri() {
+		let uri: Uri = "https://vault-1:8200/v1/secret/data/app".parse().unwrap();
+		let res = Response::builder()
+			.status(StatusCode::TEMPORARY_REDIRECT)
+			.header("Location", "https://vault-0:8200/v1/secret/data/app")
+			.body(())
+			.unwrap();
+		assert_eq!(
+			redirect_uri(&uri, &res).unwrap(),
+			"https://vault-0:8200/v1/secret/data/app"
+		);
+		let res = Response::builder()
+			.status(StatusCode::TEMPORARY_REDIRECT)
+			.header("Location", "/v1/secret/data/app?version=2")
+			.body(())
+			.unwrap();
+		assert_eq!(
+			redirect_uri(&uri, &res).unwrap(),
+			"https://vault-1:8200/v1/secret/data/app?version=2"
+		);
+		let res = Response::builder()
+			.status(StatusCode::OK)
+			.header("Location", "/v1/secret/data/app")
+			.body(())
+			.unwrap();
+		assert!(redirect_uri(&uri, &res).is_none());
+	}
+
+	#[test]
+	fn redirects_only_followed_to_vault() {
+		let client = Scripted::new()
+			.builder()
+			.failover_urls(&["https://vault-1:8200/v1"])
+			.build()
+			.unwrap();
+		let from: Uri = "http://localhost:8200/v1/secret/foo".parse().unwrap();
+		let to = |uri: &str| uri.parse::<Uri>().unwrap();
+		assert!(client.may_follow(&from, &to("http://localhost:8200/v1/secret/foo")));
+		assert!(client.may_follow(&from, &to("https://vault-1:8200/v1/secret/foo")));
+		assert!(!client.may_follow(&from, &to("http://vault-1:8200/v1/secret/foo")));
+		assert!(!client.may_follow(&from, &to("https://evil:8200/v1/secret/foo")));
 	}
 }
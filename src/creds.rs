@@ -0,0 +1,114 @@
+use crate::redact::Redacted;
+
+use serde::Deserialize;
+use std::fmt;
+use zeroize::ZeroizeOnDrop;
+
+// The credentials are converted from a generic `Secret` with `TypedSecret::try_from`, keeping
+// its lease
+
+/// Credentials of the rabbitmq secrets engine (`<mount>/creds/<role>`)
+#[derive(Clone, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct RabbitMqCreds {
+	pub username: String,
+	pub password: String,
+}
+
+impl fmt::Debug for RabbitMqCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RabbitMqCreds")
+			.field("username", &self.username)
+			.field("password", &Redacted(&self.password))
+			.finish()
+	}
+}
+
+/// Credentials of a postgresql role of the database secrets engine
+#[derive(Clone, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct PostgresCreds {
+	pub username: String,
+	pub password: String,
+}
+
+impl fmt::Debug for PostgresCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PostgresCreds")
+			.field("username", &self.username)
+			.field("password", &Redacted(&self.password))
+			.finish()
+	}
+}
+
+/// Credentials of a mysql role of the database secrets engine
+#[derive(Clone, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct MysqlCreds {
+	pub username: String,
+	pub password: String,
+}
+
+impl fmt::Debug for MysqlCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MysqlCreds")
+			.field("username", &self.username)
+			.field("password", &Redacted(&self.password))
+			.finish()
+	}
+}
+
+/// Programmatic api key of the mongodb atlas secrets engine (`<mount>/creds/<role>`)
+#[derive(Clone, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct MongoDbAtlasCreds {
+	pub public_key: String,
+	pub private_key: String,
+}
+
+impl fmt::Debug for MongoDbAtlasCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MongoDbAtlasCreds")
+			.field("public_key", &self.public_key)
+			.field("private_key", &Redacted(&self.private_key))
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		error::Error,
+		secret::{Secret, TypedSecret},
+	};
+	use serde_json::json;
+	use std::{convert::TryFrom, time::Duration};
+
+	#[test]
+	fn creds_from_secret() {
+		let mut secret = Secret::new(
+			json!({"username": "v-app-readonly-x1", "password": "A1a-pass"}),
+			Some(Duration::from_secs(3600)),
+		);
+		secret.lease_id = Some("database/creds/readonly/2f6a614c".to_owned());
+		let creds = TypedSecret::<PostgresCreds>::try_from(secret.clone()).unwrap();
+		assert_eq!(creds.username, "v-app-readonly-x1");
+		assert_eq!(creds.duration(), Some(Duration::from_secs(3600)));
+		assert_eq!(
+			creds.lease_id.as_deref(),
+			Some("database/creds/readonly/2f6a614c")
+		);
+		assert!(matches!(
+			TypedSecret::<MongoDbAtlasCreds>::try_from(secret),
+			Err(Error::ParseError { .. })
+		));
+	}
+
+	#[test]
+	fn creds_debug_is_redacted() {
+		let creds: PostgresCreds = serde_json::from_value(
+			json!({"username": "v-app-readonly-x1", "password": "A1a-pass"}),
+		)
+		.unwrap();
+		let debug = format!("{:?}", creds);
+		assert!(debug.contains("v-app-readonly-x1"));
+		assert!(!debug.contains("A1a-pass"));
+	}
+}
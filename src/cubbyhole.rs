@@ -0,0 +1,104 @@
+use crate::{client::VaultClient, error::Result};
+
+use serde_json::{Map, Value};
+
+/// mount point of the cubbyhole engine
+const CUBBYHOLE: &str = "cubbyhole";
+
+/// The cubbyhole is a kv1 store private to each token: the secrets written with the token of a
+/// role can only be read back with the same token and are destroyed when the token expires
+impl VaultClient {
+	/// Get the data of a secret at path in the cubbyhole of the token of role
+	pub fn cubbyhole_get(&self, role: &str, path: &str) -> Result<Map<String, Value>> {
+		self.kv1_get(role, CUBBYHOLE, path)
+	}
+
+	/// Get asynchronously the data of a secret at path in the cubbyhole of the token of role
+	pub async fn cubbyhole_get_async(&self, role: &str, path: &str) -> Result<Map<String, Value>> {
+		self.kv1_get_async(role, CUBBYHOLE, path).await
+	}
+
+	/// Write a secret at path in the cubbyhole of the token of role
+	pub fn cubbyhole_put(&self, role: &str, path: &str, data: &Map<String, Value>) -> Result<()> {
+		self.kv1_put(role, CUBBYHOLE, path, data)
+	}
+
+	/// Write asynchronously a secret at path in the cubbyhole of the token of role
+	pub async fn cubbyhole_put_async(
+		&self,
+		role: &str,
+		path: &str,
+		data: &Map<String, Value>,
+	) -> Result<()> {
+		self.kv1_put_async(role, CUBBYHOLE, path, data).await
+	}
+
+	/// Delete a secret at path in the cubbyhole of the token of role
+	pub fn cubbyhole_delete(&self, role: &str, path: &str) -> Result<()> {
+		self.kv1_delete(role, CUBBYHOLE, path)
+	}
+
+	/// Delete asynchronously a secret at path in the cubbyhole of the token of role
+	pub async fn cubbyhole_delete_async(&self, role: &str, path: &str) -> Result<()> {
+		self.kv1_delete_async(role, CUBBYHOLE, path).await
+	}
+
+	/// List the keys under path in the cubbyhole of the token of role
+	pub fn cubbyhole_list(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		self.list_secrets(
+			role,
+			&format!("{}/{}", CUBBYHOLE, path.trim_start_matches('/')),
+		)
+	}
+
+	/// List asynchronously the keys under path in the cubbyhole of the token of role
+	pub async fn cubbyhole_list_async(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		self.list_secrets_async(
+			role,
+			&format!("{}/{}", CUBBYHOLE, path.trim_start_matches('/')),
+		)
+		.await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn cubbyhole_requests_target_the_cubbyhole_mount() {
+		let transport = Scripted::new();
+		transport
+			.on(
+				"GET",
+				"cubbyhole/app",
+				200,
+				json!({"data": {"keys": ["db", "tls/"]}}),
+			)
+			.on("DELETE", "cubbyhole/app/db", 204, Value::Null)
+			.on(
+				"GET",
+				"cubbyhole/app/db",
+				200,
+				json!({"data": {"password": "pass"}}),
+			);
+		let client = transport.client();
+		assert_eq!(
+			client.cubbyhole_list(TOKEN_ROLE, "/app").unwrap(),
+			["db", "tls/"]
+		);
+		let data = futures_lite::future::block_on(client.cubbyhole_get_async(TOKEN_ROLE, "app/db"));
+		assert_eq!(data.unwrap()["password"], "pass");
+		client.cubbyhole_delete(TOKEN_ROLE, "app/db").unwrap();
+		assert_eq!(
+			transport.requests(),
+			[
+				"GET cubbyhole/app",
+				"GET cubbyhole/app/db",
+				"DELETE cubbyhole/app/db"
+			]
+		);
+	}
+}
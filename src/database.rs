@@ -0,0 +1,92 @@
+use crate::{client::VaultClient, error::Result, redact::Redacted, secret::TypedSecret};
+
+use serde::Deserialize;
+use std::fmt;
+use zeroize::ZeroizeOnDrop;
+
+/// Dynamic database credentials of the database secrets engine
+#[derive(Deserialize, ZeroizeOnDrop)]
+pub struct DatabaseCreds {
+	pub username: String,
+	pub password: String,
+}
+
+impl fmt::Debug for DatabaseCreds {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("DatabaseCreds")
+			.field("username", &self.username)
+			.field("password", &Redacted(&self.password))
+			.finish()
+	}
+}
+
+impl VaultClient {
+	/// Generate credentials for the database role db_role of the database engine mounted at mount
+	pub fn database_creds(
+		&self,
+		role: &str,
+		mount: &str,
+		db_role: &str,
+	) -> Result<TypedSecret<DatabaseCreds>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), db_role),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		self.leased(self.send(request)?)
+	}
+
+	/// Generate asynchronously credentials for the database role db_role
+	pub async fn database_creds_async(
+		&self,
+		role: &str,
+		mount: &str,
+		db_role: &str,
+	) -> Result<TypedSecret<DatabaseCreds>> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}/creds/{}", mount.trim_matches('/'), db_role),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		self.leased(self.send_async(request).await?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+	use std::time::Duration;
+
+	#[test]
+	fn creds_from_response() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"database/creds/readonly",
+			200,
+			json!({
+				"lease_id": "database/creds/readonly/2f6a614c",
+				"lease_duration": 3600,
+				"renewable": true,
+				"data": {
+					"username": "root-1430158508-126",
+					"password": "132ae3ef-5a64-7499-351e-bfe59f3a2a21"
+				}
+			}),
+		);
+		let creds = transport
+			.client()
+			.database_creds(TOKEN_ROLE, "database", "readonly")
+			.unwrap();
+		assert_eq!(creds.username, "root-1430158508-126");
+		assert_eq!(creds.metadata.lease_duration, Duration::from_secs(3600));
+		assert_eq!(
+			creds.lease_id.as_deref(),
+			Some("database/creds/readonly/2f6a614c")
+		);
+		assert!(creds.is_valid() && !creds.to_renew());
+	}
+}
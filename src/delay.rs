@@ -0,0 +1,150 @@
+use std::{
+	cmp::{Ordering, Reverse},
+	collections::BinaryHeap,
+	future::Future,
+	pin::Pin,
+	sync::{Arc, Condvar, Mutex, OnceLock},
+	task::{Context, Poll, Waker},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// State shared between a Delay and the timer thread
+#[derive(Default)]
+struct State {
+	done: bool,
+	waker: Option<Waker>,
+}
+
+/// Pending delay in the timer queue, ordered by deadline
+struct Entry {
+	deadline: Instant,
+	state: Arc<Mutex<State>>,
+}
+
+impl PartialEq for Entry {
+	fn eq(&self, other: &Self) -> bool {
+		self.deadline == other.deadline
+	}
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Entry {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.deadline.cmp(&other.deadline)
+	}
+}
+
+/// Queue of pending delays served by a single thread, started with the first delay
+#[derive(Default)]
+struct Timer {
+	queue: Mutex<BinaryHeap<Reverse<Entry>>>,
+	changed: Condvar,
+}
+
+impl Timer {
+	/// Return the timer of the process, starting its thread if necessary
+	fn get() -> &'static Timer {
+		static TIMER: OnceLock<Timer> = OnceLock::new();
+		let mut started = false;
+		let timer = TIMER.get_or_init(|| {
+			started = true;
+			Timer::default()
+		});
+		if started {
+			thread::spawn(move || timer.run());
+		}
+		timer
+	}
+
+	/// Add an entry to the queue and wake the timer thread if it is the next one to expire
+	fn add(&self, entry: Entry) {
+		let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+		let first = queue.peek().is_none_or(|Reverse(next)| entry < *next);
+		queue.push(Reverse(entry));
+		if first {
+			self.changed.notify_one();
+		}
+	}
+
+	/// Complete the delays as their deadline passes
+	fn run(&self) {
+		let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+		loop {
+			let next = queue.peek().map(|Reverse(entry)| entry.deadline);
+			let now = Instant::now();
+			queue = match next {
+				None => self.changed.wait(queue).unwrap_or_else(|e| e.into_inner()),
+				Some(deadline) if deadline > now => {
+					let res = self.changed.wait_timeout(queue, deadline - now);
+					res.unwrap_or_else(|e| e.into_inner()).0
+				}
+				Some(_) => {
+					if let Some(Reverse(entry)) = queue.pop() {
+						let mut state = entry.state.lock().unwrap_or_else(|e| e.into_inner());
+						state.done = true;
+						if let Some(waker) = state.waker.take() {
+							waker.wake();
+						}
+					}
+					continue;
+				}
+			};
+		}
+	}
+}
+
+/// Future that completes after a duration. The delays are served by a timer thread shared by
+/// the whole process so that the crate doesn't depend on the timer of any async runtime
+pub(crate) struct Delay {
+	state: Arc<Mutex<State>>,
+}
+
+impl Delay {
+	pub(crate) fn new(dur: Duration) -> Self {
+		let state = Arc::new(Mutex::new(State::default()));
+		Timer::get().add(Entry {
+			deadline: Instant::now() + dur,
+			state: Arc::clone(&state),
+		});
+		Self { state }
+	}
+}
+
+impl Future for Delay {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		if state.done {
+			Poll::Ready(())
+		} else {
+			state.waker = Some(cx.waker().clone());
+			Poll::Pending
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn delays_complete_in_deadline_order() {
+		let start = Instant::now();
+		let long = Delay::new(Duration::from_millis(500));
+		let short = Delay::new(Duration::from_millis(20));
+		futures_lite::future::block_on(short);
+		assert!(start.elapsed() >= Duration::from_millis(20));
+		assert!(!long.state.lock().unwrap().done);
+		futures_lite::future::block_on(long);
+		assert!(start.elapsed() >= Duration::from_millis(500));
+	}
+}
@@ -0,0 +1,213 @@
+use crate::{
+	builder::VaultClientBuilder,
+	client::{VaultClient, TOKEN_ROLE},
+	error::{Error, Result},
+	kubernetes::{KubernetesConfig, KubernetesRole},
+	method::{AppRoleAuth, KubernetesAuth},
+	mounts::MountConfig,
+};
+
+use serde_json::json;
+use std::{
+	env, io,
+	net::TcpListener,
+	process::{Child, Command, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// Time given to a dev server to answer its health check
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the dev server was started
+#[derive(Debug)]
+enum Server {
+	Process(Child),
+	/// id of a docker container
+	Container(String),
+}
+
+/// A vault server in dev mode (in memory, unsealed, with a known root token) for end to end
+/// tests. The server is stopped when dropped
+#[derive(Debug)]
+pub struct DevVault {
+	server: Server,
+	/// api url of the server (ex: `http://127.0.0.1:41234/v1`)
+	pub url: String,
+	pub root_token: String,
+}
+
+/// Return a free local port
+fn free_port() -> Result<u16> {
+	Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+impl DevVault {
+	/// Start a dev server with the vault binary found in `VAULT_BIN` or in the `PATH`
+	pub fn start() -> Result<Self> {
+		let port = free_port()?;
+		let root_token = format!("root-{:016x}", fastrand::u64(..));
+		let child = Command::new(env::var("VAULT_BIN").unwrap_or_else(|_| "vault".to_owned()))
+			.args(["server", "-dev"])
+			.arg(format!("-dev-root-token-id={}", root_token))
+			.arg(format!("-dev-listen-address=127.0.0.1:{}", port))
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()?;
+		Self::wait(Server::Process(child), port, root_token)
+	}
+
+	/// Start a dev server in a docker container of image (ex: `hashicorp/vault:1.15`)
+	pub fn start_container(image: &str) -> Result<Self> {
+		let port = free_port()?;
+		let root_token = format!("root-{:016x}", fastrand::u64(..));
+		let output = Command::new("docker")
+			.args(["run", "-d", "--rm", "--cap-add=IPC_LOCK", "-p"])
+			.arg(format!("127.0.0.1:{}:8200", port))
+			.arg("-e")
+			.arg(format!("VAULT_DEV_ROOT_TOKEN_ID={}", root_token))
+			.arg(image)
+			.output()?;
+		if !output.status.success() {
+			let message = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+			return Err(io::Error::other(message).into());
+		}
+		let id = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+		Self::wait(Server::Container(id), port, root_token)
+	}
+
+	/// wait for the server listening on port to be ready
+	fn wait(server: Server, port: u16, root_token: String) -> Result<Self> {
+		let vault = Self {
+			server,
+			url: format!("http://127.0.0.1:{}/v1", port),
+			root_token,
+		};
+		let client = vault.root_client()?;
+		let start = Instant::now();
+		loop {
+			match client.health() {
+				Ok(_) => return Ok(vault),
+				Err(e) if start.elapsed() > START_TIMEOUT => return Err(e),
+				Err(_) => thread::sleep(Duration::from_millis(200)),
+			}
+		}
+	}
+
+	/// Return a client logged in with the root token under `TOKEN_ROLE`, to configure the server
+	pub fn root_client(&self) -> Result<VaultClient> {
+		VaultClient::with_token(&self.url, &self.root_token, None)
+	}
+
+	/// Enable the approle auth method (if not done yet), create the approle name with policies
+	/// and return a client logged in under name with its role id and a new secret id
+	pub fn approle_client(&self, name: &str, policies: &[&str]) -> Result<VaultClient> {
+		let root = self.root_client()?;
+		match root.enable_auth_method(TOKEN_ROLE, "approle", "approle", &MountConfig::default()) {
+			// already enabled
+			Err(Error::VaultError { status, .. }) if status.as_u16() == 400 => {}
+			res => res?,
+		}
+		let path = format!("auth/approle/role/{}", name);
+		root.request(
+			TOKEN_ROLE,
+			"POST",
+			&path,
+			Some(json!({ "token_policies": policies })),
+		)?;
+		let role_id = root.request(TOKEN_ROLE, "GET", &format!("{}/role-id", path), None)?;
+		let secret_id = root.request(TOKEN_ROLE, "POST", &format!("{}/secret-id", path), None)?;
+		let method = AppRoleAuth::new(
+			role_id["data"]["role_id"]
+				.as_str()
+				.ok_or(Error::MissingField("role_id"))?,
+			secret_id["data"]["secret_id"]
+				.as_str()
+				.ok_or(Error::MissingField("secret_id"))?,
+		);
+		let client = VaultClientBuilder::new(&self.url)
+			.auth_method(Box::new(method))
+			.build()?;
+		client.login(name)?;
+		Ok(client)
+	}
+
+	/// Enable the kubernetes auth method (if not done yet) with config, create the kubernetes
+	/// role name and return a client logged in under name with jwt. The api server of config
+	/// must be reachable from the vault server to review jwt
+	pub fn kubernetes_client(
+		&self,
+		config: &KubernetesConfig,
+		name: &str,
+		kube_role: &KubernetesRole,
+		jwt: &str,
+	) -> Result<VaultClient> {
+		let root = self.root_client()?;
+		match root.enable_auth_method(
+			TOKEN_ROLE,
+			"kubernetes",
+			"kubernetes",
+			&MountConfig::default(),
+		) {
+			Err(Error::VaultError { status, .. }) if status.as_u16() == 400 => {}
+			res => res?,
+		}
+		root.configure_kubernetes_auth(TOKEN_ROLE, "kubernetes", config)?;
+		root.write_kubernetes_role(TOKEN_ROLE, "kubernetes", name, kube_role)?;
+		let client = VaultClientBuilder::new(&self.url)
+			.auth_method(Box::new(KubernetesAuth::new("kubernetes", jwt)))
+			.build()?;
+		client.login(name)?;
+		Ok(client)
+	}
+}
+
+/// Stop the server
+impl Drop for DevVault {
+	fn drop(&mut self) {
+		match self.server {
+			Server::Process(ref mut child) => {
+				let _ = child.kill();
+				let _ = child.wait();
+			}
+			Server::Container(ref id) => {
+				let _ = Command::new("docker")
+					.args(["rm", "-f", id])
+					.stdout(Stdio::null())
+					.stderr(Stdio::null())
+					.status();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	#[ignore = "needs a vault binary"]
+	fn approle_client_reads_secret() {
+		let vault = DevVault::start().unwrap();
+		let root = vault.root_client().unwrap();
+		root.request(
+			TOKEN_ROLE,
+			"POST",
+			"sys/policy/read-app",
+			Some(json!({"policy": "path \"secret/data/app\" { capabilities = [\"read\"] }"})),
+		)
+		.unwrap();
+		root.request(
+			TOKEN_ROLE,
+			"POST",
+			"secret/data/app",
+			Some(json!({"data": {"password": "hunter2"}})),
+		)
+		.unwrap();
+		let client = vault.approle_client("app", &["read-app"]).unwrap();
+		let secret = client
+			.get_secret("app", "GET", "secret/data/app", None)
+			.unwrap();
+		assert_eq!(secret.value["data"]["password"], "hunter2");
+	}
+}
@@ -0,0 +1,147 @@
+use crate::error::Error;
+
+use http::{Request, StatusCode, Uri};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Urls of the nodes of a vault cluster. Requests are sent to the active url, and to the next one
+/// when it is unreachable or answers with a standby redirect
+#[derive(Debug)]
+pub(crate) struct Endpoints {
+	urls: Vec<String>,
+	active: AtomicUsize,
+}
+
+impl Endpoints {
+	/// create the endpoints from the primary url and the urls to fail over to
+	pub(crate) fn new(url: &str, failover: &[String]) -> Self {
+		let mut urls = vec![url.trim_end_matches('/').to_owned()];
+		urls.extend(
+			failover
+				.iter()
+				.map(|url| url.trim_end_matches('/').to_owned()),
+		);
+		Self {
+			urls,
+			active: AtomicUsize::new(0),
+		}
+	}
+
+	/// number of urls
+	pub(crate) fn len(&self) -> usize {
+		self.urls.len()
+	}
+
+	/// url to which requests are sent
+	pub(crate) fn active(&self) -> &str {
+		&self.urls[self.active.load(Ordering::Relaxed) % self.urls.len()]
+	}
+
+	/// Make the url following the one request was sent to active and rebase request on it.
+	/// Return false if request was not sent to one of the urls
+	pub(crate) fn fail_over<B>(&self, request: &mut Request<B>) -> bool {
+		let uri = request.uri().to_string();
+		let (from, path) = match self
+			.urls
+			.iter()
+			.enumerate()
+			.find_map(|(i, url)| uri.strip_prefix(url.as_str()).map(|path| (i, path)))
+		{
+			Some(found) => found,
+			None => return false,
+		};
+		let to = (from + 1) % self.urls.len();
+		let uri: Uri = match format!("{}{}", self.urls[to], path).parse() {
+			Ok(uri) => uri,
+			Err(_) => return false,
+		};
+		// another request may already have failed over
+		let _ = self
+			.active
+			.compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed);
+		log::warn!(
+			"vault at {} is unavailable, failing over to {}",
+			self.urls[from],
+			self.urls[to]
+		);
+		*request.uri_mut() = uri;
+		true
+	}
+
+	/// Check if uri has the scheme and host of one of the urls
+	pub(crate) fn contains_origin(&self, uri: &Uri) -> bool {
+		self.urls
+			.iter()
+			.filter_map(|url| url.parse::<Uri>().ok())
+			.any(|url| same_origin(&url, uri))
+	}
+}
+
+/// Check if two uris have the same scheme and host (with port)
+pub(crate) fn same_origin(a: &Uri, b: &Uri) -> bool {
+	a.scheme() == b.scheme() && a.authority() == b.authority()
+}
+
+/// Check if an error means that the node is unreachable or a standby redirecting to the active
+/// node, and the request should be sent to another node
+pub(crate) fn should_fail_over(error: &Error) -> bool {
+	match error {
+		#[cfg(feature = "isahc")]
+		Error::ClientError { source } => source.is_network() || source.is_timeout(),
+		Error::TransportError { .. } => true,
+		error => error.status() == Some(StatusCode::TEMPORARY_REDIRECT),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn fail_over_to_next_url() {
+		let endpoints = Endpoints::new(
+			"https://vault-0:8200/v1",
+			&[
+				"https://vault-1:8200/v1/".to_owned(),
+				"https://vault-2:8200/v1".to_owned(),
+			],
+		);
+		let mut request = Request::get("https://vault-1:8200/v1/secret/data/app?version=2")
+			.body(())
+			.unwrap();
+		assert!(endpoints.fail_over(&mut request));
+		assert_eq!(
+			request.uri(),
+			"https://vault-2:8200/v1/secret/data/app?version=2"
+		);
+		// the request was not sent to the active url which is unchanged
+		assert_eq!(endpoints.active(), "https://vault-0:8200/v1");
+		assert!(endpoints.fail_over(&mut request));
+		assert_eq!(
+			request.uri(),
+			"https://vault-0:8200/v1/secret/data/app?version=2"
+		);
+		assert_eq!(endpoints.active(), "https://vault-0:8200/v1");
+
+		let mut request = Request::get("https://vault-0:8200/v1/sys/health")
+			.body(())
+			.unwrap();
+		assert!(endpoints.fail_over(&mut request));
+		assert_eq!(endpoints.active(), "https://vault-1:8200/v1");
+		let mut request = Request::get("https://other:8200/v1/sys/health")
+			.body(())
+			.unwrap();
+		assert!(!endpoints.fail_over(&mut request));
+	}
+
+	#[test]
+	fn origin_of_the_urls() {
+		let endpoints = Endpoints::new(
+			"https://vault-0:8200/v1",
+			&["https://vault-1:8200/v1".to_owned()],
+		);
+		assert!(endpoints.contains_origin(&"https://vault-1:8200/v1/sys/health".parse().unwrap()));
+		assert!(!endpoints.contains_origin(&"http://vault-1:8200/v1/sys/health".parse().unwrap()));
+		assert!(!endpoints.contains_origin(&"https://vault-1:443/v1/sys/health".parse().unwrap()));
+		assert!(!endpoints.contains_origin(&"https://evil:8200/v1/sys/health".parse().unwrap()));
+	}
+}
@@ -0,0 +1,169 @@
+use crate::{
+	error::{Error, Result},
+	secret::{raw_value, Secret},
+};
+
+use serde_json::Value;
+use std::{collections::BTreeMap, env};
+
+/// Rules mapping the fields of a secret to environment variables. By default every field is
+/// exported under its name in upper case with the characters other than letters and digits
+/// replaced by `_` (`api-key` gives `API_KEY`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvMapping {
+	/// prefix of the generated variable names (ex: `DB_`)
+	pub prefix: String,
+	/// only export these fields, under an explicit variable name when one is given
+	pub fields: Option<Vec<(String, Option<String>)>>,
+}
+
+/// Return the environment variable name of a field
+fn env_name(prefix: &str, field: &str) -> String {
+	let name: String = field
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() {
+				c.to_ascii_uppercase()
+			} else {
+				'_'
+			}
+		})
+		.collect();
+	format!("{}{}", prefix, name)
+}
+
+impl EnvMapping {
+	/// export every field of the secret
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// prefix the generated variable names with prefix
+	pub fn prefix(mut self, prefix: &str) -> Self {
+		self.prefix = prefix.to_owned();
+		self
+	}
+
+	/// export the field (only the selected fields are exported once one is selected)
+	pub fn field(mut self, field: &str) -> Self {
+		self.fields
+			.get_or_insert_with(Vec::new)
+			.push((field.to_owned(), None));
+		self
+	}
+
+	/// export the field as the variable name (the prefix is not added)
+	pub fn field_as(mut self, field: &str, name: &str) -> Self {
+		self.fields
+			.get_or_insert_with(Vec::new)
+			.push((field.to_owned(), Some(name.to_owned())));
+		self
+	}
+
+	/// Return the variables of secret. Fails if a selected field is missing or if the secret
+	/// is not an object
+	pub fn apply(&self, secret: &Secret) -> Result<BTreeMap<String, String>> {
+		let map = secret
+			.value
+			.as_object()
+			.ok_or_else(|| Error::TemplateError("secret is not an object".to_owned()))?;
+		match self.fields {
+			None => Ok(map
+				.iter()
+				.map(|(field, value)| (env_name(&self.prefix, field), raw_value(value)))
+				.collect()),
+			Some(ref fields) => fields
+				.iter()
+				.map(|(field, name)| {
+					let value = map.get(field).ok_or_else(|| {
+						Error::TemplateError(format!("no field \"{}\" in secret", field))
+					})?;
+					let name = name
+						.clone()
+						.unwrap_or_else(|| env_name(&self.prefix, field));
+					Ok((name, raw_value(value)))
+				})
+				.collect(),
+		}
+	}
+}
+
+impl Secret {
+	/// Return the fields of the secret as environment variables (see `EnvMapping`)
+	pub fn to_env_map(&self) -> Result<BTreeMap<String, String>> {
+		EnvMapping::new().apply(self)
+	}
+}
+
+/// Render vars as a dotenv file (`KEY="value"` lines)
+pub fn to_dotenv(vars: &BTreeMap<String, String>) -> String {
+	vars.iter()
+		.map(|(name, value)| {
+			format!(
+				"{}={}\n",
+				name,
+				Value::from(value.as_str()).to_string().replace('$', "\\$")
+			)
+		})
+		.collect()
+}
+
+/// Render vars as a shell script exporting them (`.envrc` of direnv)
+pub fn to_envrc(vars: &BTreeMap<String, String>) -> String {
+	vars.iter()
+		.map(|(name, value)| format!("export {}='{}'\n", name, value.replace('\'', "'\\''")))
+		.collect()
+}
+
+/// Set vars in the environment of the process, to be inherited by the child processes. Only call
+/// this before starting threads that read the environment
+pub fn export(vars: &BTreeMap<String, String>) {
+	for (name, value) in vars.iter() {
+		env::set_var(name, value);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	fn secret() -> Secret {
+		Secret::new(
+			json!({"username": "app", "api-key": "it's $ecret", "port": 5432}),
+			None,
+		)
+	}
+
+	#[test]
+	fn env_names_and_selection() {
+		let vars = secret().to_env_map().unwrap();
+		assert_eq!(
+			vars.keys().collect::<Vec<_>>(),
+			vec!["API_KEY", "PORT", "USERNAME"]
+		);
+		assert_eq!(vars["PORT"], "5432");
+		let vars = EnvMapping::new()
+			.prefix("DB_")
+			.field("username")
+			.field_as("api-key", "TOKEN")
+			.apply(&secret())
+			.unwrap();
+		assert_eq!(
+			vars.keys().collect::<Vec<_>>(),
+			vec!["DB_USERNAME", "TOKEN"]
+		);
+		assert!(EnvMapping::new()
+			.field("password")
+			.apply(&secret())
+			.is_err());
+	}
+
+	#[test]
+	fn dotenv_and_envrc() {
+		let mut vars = BTreeMap::new();
+		vars.insert("TOKEN".to_owned(), "it's \"$ecret\"".to_owned());
+		assert_eq!(to_dotenv(&vars), "TOKEN=\"it's \\\"\\$ecret\\\"\"\n");
+		assert_eq!(to_envrc(&vars), "export TOKEN='it'\\''s \"$ecret\"'\n");
+	}
+}
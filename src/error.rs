@@ -1,4 +1,4 @@
-use isahc::http::StatusCode;
+use http::StatusCode;
 #[cfg(feature = "nom")]
 use nom::error::ErrorKind;
 use serde::Deserialize;
@@ -9,28 +9,78 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
 	#[error("not logged to vault server")]
 	NotLogged,
-	#[error("http error code {0}\n{1}")]
-	VaultError(StatusCode, String),
+	#[error("no auth method given to the client builder")]
+	NoAuthMethod,
+	#[error("no http client: the isahc and reqwest features are disabled and no transport given")]
+	NoTransport,
+	#[cfg(any(feature = "reqwest", feature = "rustls"))]
+	#[error("{0} not supported by the reqwest transport")]
+	Unsupported(&'static str),
+	#[error("role jwts are only used by the kubernetes auth method with a default jwt")]
+	RoleJwtsWithoutJwt,
+	#[error("permission denied on {path}: {}", .errors.join(", "))]
+	Forbidden { path: String, errors: Vec<String> },
+	#[error("nothing found at {path}")]
+	NotFound { path: String, errors: Vec<String> },
+	#[error("vault is sealed (request to {path})")]
+	Sealed { path: String, errors: Vec<String> },
+	#[error("rate limited on {path}: {}", .errors.join(", "))]
+	RateLimited { path: String, errors: Vec<String> },
+	#[error("http error code {status} on {path}\n{}", .errors.join("\n"))]
+	VaultError {
+		status: StatusCode,
+		path: String,
+		errors: Vec<String>,
+	},
 	#[error("token error")]
 	TokenError {
 		#[from]
 		source: std::io::Error,
 	},
+	#[error("unable to load the tls certificates")]
+	TlsError {
+		source: Box<dyn std::error::Error + Send + Sync>,
+	},
 	#[error(transparent)]
 	HttpError {
 		#[from]
-		source: isahc::http::Error,
+		source: http::Error,
 	},
+	#[cfg(feature = "isahc")]
 	#[error("client error")]
 	ClientError {
 		#[from]
 		source: isahc::error::Error,
 	},
+	#[error("transport error")]
+	TransportError {
+		source: Box<dyn std::error::Error + Send + Sync>,
+	},
+	#[error("unable to write {path}")]
+	SinkError {
+		path: String,
+		source: std::io::Error,
+	},
+	#[error("unable to run {command}")]
+	ExecError {
+		command: String,
+		source: std::io::Error,
+	},
+	#[error("template error: {0}")]
+	TemplateError(String),
 	#[error("response parse error")]
 	ParseError {
 		#[from]
 		source: serde_json::error::Error,
 	},
+	#[cfg(feature = "yaml")]
+	#[error("yaml parse error")]
+	YamlError {
+		#[from]
+		source: serde_yaml::Error,
+	},
+	#[error("invalid secret spec: {0}")]
+	SpecError(String),
 	#[error("unable to get vault mount")]
 	MountsNotFound,
 	#[error("undefined mount type {0}")]
@@ -43,6 +93,21 @@ pub enum Error {
 	NoArgs(String),
 	#[error("missing a \":\" to separate arguments from path \"{0}\"")]
 	NoPath(String),
+	#[error("invalid base64 data")]
+	InvalidBase64,
+	#[error("response body is not valid utf-8")]
+	InvalidUtf8,
+	#[error("the otp doesn't match the encoded root token")]
+	InvalidOtp,
+	#[error("batch item error: {0}")]
+	BatchItemError(String),
+	#[error("the secret is not an object of fields")]
+	SecretNotObject,
+	#[error("missing field \"{0}\" in vault response")]
+	MissingField(&'static str),
+	#[cfg(feature = "kube")]
+	#[error("not running in a kubernetes pod")]
+	NotInCluster,
 	#[cfg(feature = "nom")]
 	#[error("extra data after path \"{0}\"")]
 	ExtraData(String),
@@ -54,8 +119,93 @@ pub enum Error {
 	Incomplete,
 }
 
+impl Error {
+	/// Build an error from the status and the error messages of a vault response to path
+	pub fn from_status(status: StatusCode, path: &str, errors: Vec<String>) -> Self {
+		let path = path.to_owned();
+		match status {
+			StatusCode::FORBIDDEN => Error::Forbidden { path, errors },
+			StatusCode::NOT_FOUND => Error::NotFound { path, errors },
+			StatusCode::TOO_MANY_REQUESTS => Error::RateLimited { path, errors },
+			StatusCode::SERVICE_UNAVAILABLE
+				if errors.iter().any(|e| e.to_lowercase().contains("sealed")) =>
+			{
+				Error::Sealed { path, errors }
+			}
+			status => Error::VaultError {
+				status,
+				path,
+				errors,
+			},
+		}
+	}
+
+	/// Build an error from a failure of the transport, like an i/o error while reading a body
+	pub(crate) fn transport<E: Into<Box<dyn std::error::Error + Send + Sync>>>(source: E) -> Self {
+		Error::TransportError {
+			source: source.into(),
+		}
+	}
+
+	/// Build an error from a failure to read or to parse the certificates of the tls connections
+	#[cfg(any(feature = "isahc", feature = "reqwest", feature = "rustls"))]
+	pub(crate) fn tls<E: Into<Box<dyn std::error::Error + Send + Sync>>>(source: E) -> Self {
+		Error::TlsError {
+			source: source.into(),
+		}
+	}
+
+	/// Return the http status of the vault response that caused the error, if any
+	pub fn status(&self) -> Option<StatusCode> {
+		match self {
+			Error::Forbidden { .. } => Some(StatusCode::FORBIDDEN),
+			Error::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+			Error::Sealed { .. } => Some(StatusCode::SERVICE_UNAVAILABLE),
+			Error::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+			Error::VaultError { status, .. } => Some(*status),
+			_ => None,
+		}
+	}
+
+	/// Return the error messages of the vault response that caused the error
+	pub fn vault_errors(&self) -> &[String] {
+		match self {
+			Error::Forbidden { errors, .. }
+			| Error::NotFound { errors, .. }
+			| Error::Sealed { errors, .. }
+			| Error::RateLimited { errors, .. }
+			| Error::VaultError { errors, .. } => errors,
+			_ => &[],
+		}
+	}
+}
+
 /// Vault errors deserialized
 #[derive(Debug, Deserialize)]
 pub struct VaultErrors {
 	pub errors: Vec<String>,
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn error_from_status() {
+		let sealed = Error::from_status(
+			StatusCode::SERVICE_UNAVAILABLE,
+			"/v1/secret/foo",
+			vec!["Vault is sealed".to_owned()],
+		);
+		assert!(matches!(sealed, Error::Sealed { ref path, .. } if path == "/v1/secret/foo"));
+		assert_eq!(sealed.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+		assert!(matches!(
+			Error::from_status(StatusCode::SERVICE_UNAVAILABLE, "/v1/sys/health", vec![]),
+			Error::VaultError { .. }
+		));
+		assert!(matches!(
+			Error::from_status(StatusCode::NOT_FOUND, "/v1/secret/foo", vec![]),
+			Error::NotFound { .. }
+		));
+	}
+}
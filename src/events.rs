@@ -0,0 +1,393 @@
+use crate::error::{Error, Result};
+#[cfg(feature = "isahc")]
+use crate::{
+	client::VaultClient,
+	websocket::{Connect, CurlStream, WebSocket},
+};
+
+#[cfg(feature = "isahc")]
+use futures_lite::Stream;
+#[cfg(feature = "isahc")]
+use http::Uri;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+#[cfg(feature = "isahc")]
+use std::{
+	collections::VecDeque,
+	future::Future,
+	pin::Pin,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	task::{Context, Poll, Waker},
+	thread,
+};
+
+/// A vault event notification (a cloud event) as sent by `sys/events/subscribe/<type>?json=true`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Event {
+	pub id: String,
+	pub source: String,
+	#[serde(rename = "type", default)]
+	pub cloud_event_type: String,
+	#[serde(default)]
+	pub time: String,
+	pub data: EventData,
+}
+
+/// Payload of an event notification
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EventData {
+	/// type of the event (ex: `kv-v2/data-write`)
+	pub event_type: String,
+	pub event: EventInfo,
+	#[serde(default)]
+	pub plugin_info: Option<PluginInfo>,
+}
+
+/// Event details
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EventInfo {
+	pub id: String,
+	/// event specific metadata (ex: `path` and `current_version` for kv events)
+	#[serde(default)]
+	pub metadata: HashMap<String, Value>,
+}
+
+/// Plugin that emitted an event
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PluginInfo {
+	#[serde(default)]
+	pub mount_class: String,
+	#[serde(default)]
+	pub mount_accessor: String,
+	#[serde(default)]
+	pub mount_path: String,
+	#[serde(default)]
+	pub plugin: String,
+}
+
+impl Event {
+	/// parse a text frame received on the events websocket
+	pub fn from_json(frame: &str) -> Result<Self> {
+		serde_json::from_str(frame).map_err(|e| Error::ParseError { source: e })
+	}
+
+	/// path of the secret the event refers to, if any
+	pub fn path(&self) -> Option<&str> {
+		self.data
+			.event
+			.metadata
+			.get("path")
+			.and_then(|v| v.as_str())
+	}
+}
+
+/// Blocking iterator over the events of a subscription. It ends when the server closes the
+/// websocket, which is closed when the iterator is dropped
+#[cfg(feature = "isahc")]
+pub struct Events(WebSocket<CurlStream>);
+
+#[cfg(feature = "isahc")]
+impl Iterator for Events {
+	type Item = Result<Event>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self.0.read_message() {
+			Ok(Some(message)) => Some(Event::from_json(&String::from_utf8_lossy(&message))),
+			Ok(None) => None,
+			Err(e) => Some(Err(Error::transport(e))),
+		}
+	}
+}
+
+#[cfg(feature = "isahc")]
+impl Drop for Events {
+	fn drop(&mut self) {
+		self.0.close();
+	}
+}
+
+/// Events read by the thread of an EventStream
+#[cfg(feature = "isahc")]
+#[derive(Default)]
+struct Queue {
+	/// the websocket is open
+	opened: bool,
+	/// the thread has ended
+	done: bool,
+	events: VecDeque<Result<Event>>,
+	waker: Option<Waker>,
+}
+
+#[cfg(feature = "isahc")]
+impl Queue {
+	/// add an event, or mark the end of the events with None
+	fn push(&mut self, event: Option<Result<Event>>) {
+		match event {
+			Some(event) => self.events.push_back(event),
+			None => self.done = true,
+		}
+		self.wake();
+	}
+
+	fn wake(&mut self) {
+		if let Some(waker) = self.waker.take() {
+			waker.wake();
+		}
+	}
+}
+
+/// Stream of the events of a subscription, the websocket being read by a background thread. It
+/// ends when the server closes the websocket, which is closed when the stream is dropped
+#[cfg(feature = "isahc")]
+pub struct EventStream {
+	queue: Arc<Mutex<Queue>>,
+	stop: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "isahc")]
+impl Stream for EventStream {
+	type Item = Result<Event>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+		match queue.events.pop_front() {
+			Some(event) => Poll::Ready(Some(event)),
+			None if queue.done => Poll::Ready(None),
+			None => {
+				queue.waker = Some(cx.waker().clone());
+				Poll::Pending
+			}
+		}
+	}
+}
+
+#[cfg(feature = "isahc")]
+impl Drop for EventStream {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Future waiting for the thread of an EventStream to open the websocket
+#[cfg(feature = "isahc")]
+struct Opened<'a>(&'a Mutex<Queue>);
+
+#[cfg(feature = "isahc")]
+impl Future for Opened<'_> {
+	type Output = Result<()>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let mut queue = self.0.lock().unwrap_or_else(|e| e.into_inner());
+		if queue.opened {
+			Poll::Ready(Ok(()))
+		} else if let Some(Err(e)) = queue.events.pop_front() {
+			Poll::Ready(Err(e))
+		} else {
+			queue.waker = Some(cx.waker().clone());
+			Poll::Pending
+		}
+	}
+}
+
+#[cfg(feature = "isahc")]
+impl VaultClient {
+	/// Subscribe with the token of role to the events of event_type (`*` for all, `kv*`...) on
+	/// the `sys/events/subscribe` websocket of the active node
+	pub fn subscribe_events(&self, role: &str, event_type: &str) -> Result<Events> {
+		let request = self.events_request(&self.token(role)?, event_type)?;
+		let stop = Arc::new(AtomicBool::new(false));
+		Ok(Events(request.open(&self.connect, stop)?))
+	}
+
+	/// Subscribe asynchronously with the token of role to the events of event_type. The
+	/// websocket is opened and read by a thread so that the executor is never blocked
+	pub async fn subscribe_events_async(
+		&self,
+		role: &str,
+		event_type: &str,
+	) -> Result<EventStream> {
+		let request = self.events_request(&self.token_async(role).await?, event_type)?;
+		let connect = self.connect.clone();
+		let queue = Arc::new(Mutex::new(Queue::default()));
+		let stop = Arc::new(AtomicBool::new(false));
+		let (thread_queue, thread_stop) = (Arc::clone(&queue), Arc::clone(&stop));
+		thread::spawn(move || {
+			let lock = || thread_queue.lock().unwrap_or_else(|e| e.into_inner());
+			let mut events = match request.open(&connect, thread_stop) {
+				Ok(websocket) => {
+					let mut queue = lock();
+					queue.opened = true;
+					queue.wake();
+					Events(websocket)
+				}
+				Err(e) => {
+					let mut queue = lock();
+					queue.push(Some(Err(e)));
+					queue.push(None);
+					return;
+				}
+			};
+			loop {
+				let event = events.next();
+				let done = event.is_none();
+				lock().push(event);
+				if done {
+					return;
+				}
+			}
+		});
+		Opened(&queue).await?;
+		Ok(EventStream { queue, stop })
+	}
+
+	/// Return the upgrade request subscribing with token to event_type
+	fn events_request(&self, token: &str, event_type: &str) -> Result<EventsRequest> {
+		let url = format!(
+			"{}/sys/events/subscribe/{}?json=true",
+			self.endpoints.active(),
+			event_type
+		);
+		let mut headers = self.connect.headers.clone();
+		headers.push(("X-Vault-Token".to_owned(), token.to_owned()));
+		if let Some(ref namespace) = self.namespace {
+			headers.push(("X-Vault-Namespace".to_owned(), namespace.clone()));
+		}
+		Ok(EventsRequest { url, headers })
+	}
+}
+
+/// Upgrade request of an events subscription
+#[cfg(feature = "isahc")]
+struct EventsRequest {
+	url: String,
+	headers: Vec<(String, String)>,
+}
+
+#[cfg(feature = "isahc")]
+impl EventsRequest {
+	/// Connect to the host of the url and upgrade the connection to a websocket
+	fn open(&self, connect: &Connect, stop: Arc<AtomicBool>) -> Result<WebSocket<CurlStream>> {
+		let uri = self
+			.url
+			.parse::<Uri>()
+			.map_err(|e| Error::HttpError { source: e.into() })?;
+		let host = uri
+			.authority()
+			.map(|host| host.as_str())
+			.unwrap_or_default();
+		let path = uri
+			.path_and_query()
+			.map(|path| path.as_str())
+			.unwrap_or("/");
+		let stream = CurlStream::connect(&self.url, connect, stop)?;
+		WebSocket::handshake(stream, host, path, &self.headers)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	#[cfg(feature = "isahc")]
+	use crate::{builder::VaultClientBuilder, client::TOKEN_ROLE, websocket::accept_key};
+	#[cfg(feature = "isahc")]
+	use futures_lite::{future, StreamExt};
+	#[cfg(feature = "isahc")]
+	use std::{
+		io::{Read, Write},
+		net::TcpListener,
+	};
+
+	#[cfg(feature = "isahc")]
+	const FRAME: &str = r#"{"id":"1","source":"vault","data":{"event_type":"kv-v2/data-write","event":{"id":"1","metadata":{"path":"secret/app"}}}}"#;
+
+	/// Start a websocket server sending one event to each subscriber and return its address
+	#[cfg(feature = "isahc")]
+	fn serve_one_event(subscribers: usize) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		thread::spawn(move || {
+			for stream in listener.incoming().take(subscribers) {
+				let mut stream = stream.unwrap();
+				let mut request = Vec::new();
+				let mut byte = [0];
+				while !request.ends_with(b"\r\n\r\n") {
+					stream.read_exact(&mut byte).unwrap();
+					request.push(byte[0]);
+				}
+				let request = String::from_utf8(request).unwrap();
+				assert!(request.starts_with("GET /v1/sys/events/subscribe/kv*?json=true HTTP/1.1"));
+				assert!(request.contains("\r\nX-Vault-Token: s.token\r\n"));
+				let key = request
+					.lines()
+					.find_map(|line| line.strip_prefix("Sec-WebSocket-Key: "))
+					.unwrap();
+				let mut response = format!(
+					"HTTP/1.1 101 Switching Protocols\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+					accept_key(key)
+				)
+				.into_bytes();
+				response.extend(&[0x81, FRAME.len() as u8]);
+				response.extend(FRAME.as_bytes());
+				response.extend(&[0x88, 2, 0x03, 0xe8]);
+				stream.write_all(&response).unwrap();
+			}
+		});
+		format!("http://{}/v1", addr)
+	}
+
+	#[test]
+	#[cfg(feature = "isahc")]
+	fn events_are_streamed() {
+		let client = VaultClientBuilder::new(&serve_one_event(2))
+			.token("s.token")
+			.proxy("")
+			.build()
+			.unwrap();
+		let events: Vec<Event> = client
+			.subscribe_events(TOKEN_ROLE, "kv*")
+			.unwrap()
+			.map(|event| event.unwrap())
+			.collect();
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].path(), Some("secret/app"));
+		let events: Vec<Result<Event>> = future::block_on(async {
+			let stream = client.subscribe_events_async(TOKEN_ROLE, "kv*").await?;
+			Ok::<_, Error>(stream.collect().await)
+		})
+		.unwrap();
+		assert_eq!(events.len(), 1);
+		assert_eq!(
+			events[0].as_ref().unwrap().data.event_type,
+			"kv-v2/data-write"
+		);
+	}
+
+	#[test]
+	fn kv_event_from_frame() {
+		let event = Event::from_json(
+			r#"{
+				"id": "a3be9fb1-b514-519f-5b25-b6f144a8c1ce",
+				"source": "https://vaultproject.io/",
+				"specversion": "1.0",
+				"type": "*",
+				"data": {
+					"event": {
+						"id": "a3be9fb1-b514-519f-5b25-b6f144a8c1ce",
+						"metadata": {"current_version": "1", "data_path": "secret/data/app", "path": "secret/app"}
+					},
+					"event_type": "kv-v2/data-write",
+					"plugin_info": {"mount_class": "secret", "mount_accessor": "kv_5dc4d18e", "mount_path": "secret/", "plugin": "kv"}
+				},
+				"datacontentype": "application/cloudevents",
+				"time": "2023-09-12T15:19:49.394915-07:00"
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(event.data.event_type, "kv-v2/data-write");
+		assert_eq!(event.path(), Some("secret/app"));
+	}
+}
@@ -0,0 +1,416 @@
+use crate::{
+	client::VaultClient,
+	env::EnvMapping,
+	error::{Error, Result},
+	secret::Secret,
+	spec::{Output, SecretSpec},
+};
+
+use std::{
+	collections::BTreeMap,
+	mem,
+	process::{Child, Command, ExitStatus},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// delay between two checks of the child process
+const POLL_DELAY: Duration = Duration::from_millis(100);
+/// delay before fetching again the secrets after a failure
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// delay given to the child to exit after a `TERM` signal before killing it
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What to do with the child process when the secrets change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rotation {
+	/// let the child run with the old values
+	Ignore,
+	/// stop the child (`TERM`, then `KILL` if it doesn't exit) and start it again with the new
+	/// values
+	Restart,
+	/// send a signal (ex: `HUP`) to the child with `kill`, for a child reading its secrets from
+	/// files (see `Output::File`) as its environment can't be changed
+	Signal(String),
+}
+
+/// A secret given to the child process
+struct Fetched {
+	secret: Secret,
+	/// duration of its lease as granted by vault
+	lease_duration: Duration,
+	/// when to renew its lease, or to fetch it again
+	deadline: Instant,
+}
+
+impl Fetched {
+	/// Fetch the secret of spec. Without lease it is fetched again every refresh interval
+	fn new(client: &VaultClient, spec: &SecretSpec, refresh: Duration) -> Result<Self> {
+		let secret = client.fetch_spec(spec)?;
+		let delay = secret.renew_delay().unwrap_or_else(|| {
+			let refresh = spec.refresh.map_or(refresh, Duration::from_secs);
+			client.renewal.jittered(refresh)
+		});
+		Ok(Self {
+			lease_duration: secret.metadata.lease_duration,
+			deadline: Instant::now() + delay,
+			secret,
+		})
+	}
+
+	/// Renew the lease of the secret. Returns false if it has no renewable lease or if the lease
+	/// can't be extended anymore (failure or maximum ttl reached), the secret having to be
+	/// fetched again
+	fn renew(&mut self, client: &VaultClient, role: &str) -> bool {
+		let lease_id = match self.secret.lease_id {
+			Some(ref lease_id) if self.secret.metadata.renewable => lease_id,
+			_ => return false,
+		};
+		match client.renew_lease(role, lease_id, None) {
+			// a shorter lease means that the max ttl has been reached
+			Ok(lease) if lease.lease_duration >= self.lease_duration => {
+				self.lease_duration = lease.lease_duration;
+				self.deadline = Instant::now() + lease.renew_delay;
+				true
+			}
+			Ok(_) => false,
+			Err(e) => {
+				log::warn!("unable to renew the lease {}: {}", lease_id, e);
+				false
+			}
+		}
+	}
+}
+
+/// Put the secrets in their outputs and return the variables of the child: the secrets with an
+/// `Env` output, or without output, are added to its environment and the others written to
+/// their file
+fn deliver(specs: &[SecretSpec], fetched: &[Fetched]) -> Result<BTreeMap<String, String>> {
+	let mut vars = BTreeMap::new();
+	for (spec, fetched) in specs.iter().zip(fetched.iter()) {
+		match spec.output {
+			Output::None => vars.extend(EnvMapping::new().apply(&fetched.secret)?),
+			Output::Env { ref prefix } => {
+				vars.extend(EnvMapping::new().prefix(prefix).apply(&fetched.secret)?)
+			}
+			Output::File { .. } => spec.output.write(&fetched.secret)?,
+		}
+	}
+	Ok(vars)
+}
+
+/// Start cmd with vars added to its environment
+fn spawn(cmd: &mut Command, vars: &BTreeMap<String, String>) -> Result<Child> {
+	cmd.envs(vars).spawn().map_err(|e| Error::ExecError {
+		command: format!("{:?}", cmd.get_program()),
+		source: e,
+	})
+}
+
+/// Send signal to the process pid
+fn signal(pid: u32, signal: &str) -> Result<()> {
+	let mut kill = Command::new("kill");
+	kill.args(["-s", signal, &pid.to_string()]);
+	match kill.status() {
+		Ok(status) if status.success() => Ok(()),
+		Ok(status) => Err(Error::ExecError {
+			command: "kill".to_owned(),
+			source: std::io::Error::other(status.to_string()),
+		}),
+		Err(e) => Err(Error::ExecError {
+			command: "kill".to_owned(),
+			source: e,
+		}),
+	}
+}
+
+/// Ask the child to stop with a `TERM` signal and kill it if it is still running after timeout
+fn stop(child: &mut Child, timeout: Duration) -> std::io::Result<ExitStatus> {
+	if signal(child.id(), "TERM").is_ok() {
+		let deadline = Instant::now() + timeout;
+		while Instant::now() < deadline {
+			if let Some(status) = child.try_wait()? {
+				return Ok(status);
+			}
+			thread::sleep(POLL_DELAY);
+		}
+	}
+	let _ = child.kill();
+	child.wait()
+}
+
+/// Fetch the secrets of specs, start cmd with their variables added to its environment (see
+/// `deliver`) and wait for it to exit. Unless rotation is `Ignore`, the leases of the secrets are
+/// renewed at their renew delay. A secret is fetched again when its lease can't be renewed
+/// anymore, or every refresh interval (or the `refresh` of its spec) when it has no lease, and
+/// the child is restarted or signaled when it changes. The superseded lease is then revoked,
+/// even if the value didn't change.
+/// Returns the exit status of the child
+pub fn run(
+	client: &VaultClient,
+	cmd: &mut Command,
+	specs: &[SecretSpec],
+	rotation: Rotation,
+	refresh: Duration,
+) -> Result<ExitStatus> {
+	let mut fetched = specs
+		.iter()
+		.map(|spec| Fetched::new(client, spec, refresh))
+		.collect::<Result<Vec<_>>>()?;
+	let mut child = spawn(cmd, &deliver(specs, &fetched)?)?;
+	let program = format!("{:?}", cmd.get_program());
+	let wait_error = |e| Error::ExecError {
+		command: program.clone(),
+		source: e,
+	};
+	if rotation == Rotation::Ignore || specs.is_empty() {
+		return child.wait().map_err(wait_error);
+	}
+	loop {
+		let deadline = fetched.iter().map(|fetched| fetched.deadline).min();
+		while deadline.is_some_and(|deadline| Instant::now() < deadline) {
+			if let Some(status) = child.try_wait().map_err(wait_error)? {
+				return Ok(status);
+			}
+			thread::sleep(POLL_DELAY);
+		}
+		let now = Instant::now();
+		let mut changed = false;
+		let mut superseded = Vec::new();
+		for (spec, fetched) in specs.iter().zip(fetched.iter_mut()) {
+			if fetched.deadline > now || fetched.renew(client, &spec.role) {
+				continue;
+			}
+			match Fetched::new(client, spec, refresh) {
+				Ok(mut previous) => {
+					mem::swap(fetched, &mut previous);
+					changed |= previous.secret.value != fetched.secret.value;
+					if let Some(lease_id) = previous.secret.lease_id.take() {
+						superseded.push((spec.role.as_str(), lease_id));
+					}
+				}
+				Err(e) => {
+					log::warn!("unable to fetch the secret {}: {}", spec.path, e);
+					fetched.deadline = now + client.renewal.jittered(RETRY_DELAY);
+				}
+			}
+		}
+		if changed {
+			let vars = deliver(specs, &fetched)?;
+			match rotation {
+				Rotation::Restart => {
+					log::info!("secrets changed, restarting {}", program);
+					stop(&mut child, STOP_TIMEOUT).map_err(wait_error)?;
+					child = spawn(cmd, &vars)?;
+				}
+				Rotation::Signal(ref name) => signal(child.id(), name)?,
+				Rotation::Ignore => {}
+			}
+		}
+		// the child has been restarted or signaled to use the new credentials, or still uses the
+		// same values
+		for (role, lease_id) in superseded {
+			if let Err(e) = client.revoke_lease(role, &lease_id) {
+				log::warn!("unable to revoke the lease {}: {}", lease_id, e);
+			}
+		}
+	}
+}
+
+#[cfg(all(test, unix))]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+	use serde_json::{json, Value};
+	use std::{env, fs};
+
+	/// Return the spec of the secret at path put in output
+	fn spec(path: &str, output: Value) -> SecretSpec {
+		serde_json::from_value(json!({"role": "app", "path": path, "output": output})).unwrap()
+	}
+
+	/// Return a shell command writing the password it gets to out, exiting if it is v2 and
+	/// otherwise waiting to be stopped with TERM
+	fn child(out: &str) -> Command {
+		let mut cmd = Command::new("sh");
+		cmd.env("OUT", out).args([
+			"-c",
+			r#"echo "$PASSWORD" >> "$OUT"; [ "$PASSWORD" = v2 ] && exit 0
+			trap 'echo stopped >> "$OUT"; exit 0' TERM; while :; do sleep 0.05; done"#,
+		]);
+		cmd
+	}
+
+	/// Return a path for the output of a child
+	fn out_path(name: &str) -> String {
+		let path = env::temp_dir().join(format!("vault-jwt-exec-{}-{}", name, std::process::id()));
+		let _ = fs::remove_file(&path);
+		path.to_str().unwrap().to_owned()
+	}
+
+	#[test]
+	fn child_gets_the_secrets() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/app",
+			200,
+			json!({"data": {"password": "hunter2", "username": "app"}}),
+		);
+		let client = transport.client();
+		let mut spec = spec("secret/app", json!({"type": "env", "prefix": "DB_"}));
+		spec.keys = Some(vec!["password".to_owned()]);
+		let status = run(
+			&client,
+			Command::new("sh").args([
+				"-c",
+				r#"test "$DB_PASSWORD" = hunter2 && test -z "$DB_USERNAME""#,
+			]),
+			&[spec],
+			Rotation::Ignore,
+			Duration::from_secs(60),
+		)
+		.unwrap();
+		assert!(status.success());
+	}
+
+	#[test]
+	fn child_is_restarted_when_secrets_change() {
+		let transport = Scripted::new();
+		let secret = |password: &str| json!({"data": {"password": password}});
+		transport.on("GET", "secret/app", 200, secret("v1")).on(
+			"GET",
+			"secret/app",
+			200,
+			secret("v2"),
+		);
+		let client = transport.client();
+		let out = out_path("restart");
+		let status = run(
+			&client,
+			&mut child(&out),
+			&[spec("secret/app", json!({"type": "none"}))],
+			Rotation::Restart,
+			Duration::from_millis(300),
+		)
+		.unwrap();
+		assert!(status.success());
+		assert_eq!(fs::read_to_string(&out).unwrap(), "v1\nstopped\nv2\n");
+		fs::remove_file(out).unwrap();
+	}
+
+	#[test]
+	fn child_is_signaled_when_secrets_change() {
+		let transport = Scripted::new();
+		let secret = |password: &str| json!({"data": {"password": password}});
+		transport.on("GET", "secret/app", 200, secret("v1")).on(
+			"GET",
+			"secret/app",
+			200,
+			secret("v2"),
+		);
+		let client = transport.client();
+		let status = run(
+			&client,
+			Command::new("sh").args(["-c", "trap 'exit 0' USR1; while :; do sleep 0.05; done"]),
+			&[spec("secret/app", json!({"type": "none"}))],
+			Rotation::Signal("USR1".to_owned()),
+			Duration::from_millis(500),
+		)
+		.unwrap();
+		assert!(status.success());
+	}
+
+	#[test]
+	fn leases_are_renewed_then_replaced() {
+		let transport = Scripted::new();
+		let creds = |n: u32| {
+			json!({
+				"lease_id": format!("database/creds/app/{}", n),
+				"lease_duration": 1,
+				"renewable": true,
+				"data": {"password": format!("v{}", n)}
+			})
+		};
+		transport
+			.on("GET", "database/creds/app", 200, creds(1))
+			.on("GET", "database/creds/app", 200, creds(2))
+			.on("PUT", "sys/leases/renew", 200, json!({"lease_duration": 1}))
+			// the maximum ttl of the lease is reached
+			.on("PUT", "sys/leases/renew", 200, json!({"lease_duration": 0}))
+			.on("PUT", "sys/leases/revoke", 204, Value::Null);
+		let client = transport.client();
+		let out = out_path("leases");
+		let status = run(
+			&client,
+			&mut child(&out),
+			&[spec("database/creds/app", json!({"type": "none"}))],
+			Rotation::Restart,
+			Duration::from_secs(60),
+		)
+		.unwrap();
+		assert!(status.success());
+		assert_eq!(fs::read_to_string(&out).unwrap(), "v1\nstopped\nv2\n");
+		assert_eq!(
+			transport.requests(),
+			vec![
+				"GET database/creds/app",
+				"PUT sys/leases/renew",
+				"PUT sys/leases/renew",
+				"GET database/creds/app",
+				"PUT sys/leases/revoke",
+			]
+		);
+		assert_eq!(
+			transport.body("PUT sys/leases/revoke"),
+			Some(json!({"lease_id": "database/creds/app/1"}))
+		);
+		fs::remove_file(out).unwrap();
+	}
+
+	#[test]
+	fn unchanged_secrets_have_their_lease_revoked() {
+		let transport = Scripted::new();
+		let creds = |n: u32, password: &str| {
+			json!({
+				"lease_id": format!("database/creds/app/{}", n),
+				"lease_duration": 1,
+				"renewable": false,
+				"data": {"password": password}
+			})
+		};
+		transport
+			.on("GET", "database/creds/app", 200, creds(1, "v1"))
+			.on("GET", "database/creds/app", 200, creds(2, "v1"))
+			.on("GET", "database/creds/app", 200, creds(3, "v2"))
+			.on("PUT", "sys/leases/revoke", 204, Value::Null);
+		let client = transport.client();
+		let out = out_path("unchanged");
+		let status = run(
+			&client,
+			&mut child(&out),
+			&[spec("database/creds/app", json!({"type": "none"}))],
+			Rotation::Restart,
+			Duration::from_secs(60),
+		)
+		.unwrap();
+		assert!(status.success());
+		// the child is only restarted when the value changes
+		assert_eq!(fs::read_to_string(&out).unwrap(), "v1\nstopped\nv2\n");
+		assert_eq!(
+			transport.requests(),
+			vec![
+				"GET database/creds/app",
+				"GET database/creds/app",
+				"PUT sys/leases/revoke",
+				"GET database/creds/app",
+				"PUT sys/leases/revoke",
+			]
+		);
+		assert_eq!(
+			transport.body("PUT sys/leases/revoke"),
+			Some(json!({"lease_id": "database/creds/app/2"}))
+		);
+		fs::remove_file(out).unwrap();
+	}
+}
@@ -0,0 +1,144 @@
+use std::{
+	collections::HashMap,
+	future::Future,
+	pin::Pin,
+	sync::{Arc, Condvar, Mutex},
+	task::{Context, Poll, Waker},
+};
+
+/// State of a Flight
+#[derive(Debug, Default)]
+struct State {
+	busy: bool,
+	wakers: Vec<Waker>,
+}
+
+/// Lock allowing a single login per role at a time. It can be acquired by blocking the thread
+/// or asynchronously without blocking the executor
+#[derive(Debug, Default)]
+struct Flight {
+	state: Mutex<State>,
+	cvar: Condvar,
+}
+
+impl Flight {
+	/// Wait for the flight to be free and take it
+	fn acquire(&self) {
+		let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let mut state = self
+			.cvar
+			.wait_while(state, |state| state.busy)
+			.unwrap_or_else(|e| e.into_inner());
+		state.busy = true;
+	}
+
+	/// Free the flight and wake up the waiters
+	fn release(&self) {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		state.busy = false;
+		for waker in state.wakers.drain(..) {
+			waker.wake();
+		}
+		self.cvar.notify_all();
+	}
+}
+
+/// Future taking a flight asynchronously
+struct Acquire(Arc<Flight>);
+
+impl Future for Acquire {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let mut state = self.0.state.lock().unwrap_or_else(|e| e.into_inner());
+		if state.busy {
+			state.wakers.push(cx.waker().clone());
+			Poll::Pending
+		} else {
+			state.busy = true;
+			Poll::Ready(())
+		}
+	}
+}
+
+/// Map a role to its flight. A flight is removed as soon as nobody holds or waits for it
+#[derive(Debug, Default)]
+pub(crate) struct Flights(Mutex<HashMap<String, Arc<Flight>>>);
+
+impl Flights {
+	/// Wait for the flight of key to be free and take it
+	pub(crate) fn acquire(&self, key: &str) -> FlightGuard<'_> {
+		let flight = self.get(key);
+		flight.acquire();
+		FlightGuard {
+			flights: self,
+			key: key.to_owned(),
+			flight,
+		}
+	}
+
+	/// Wait asynchronously for the flight of key to be free and take it
+	pub(crate) async fn acquire_async(&self, key: &str) -> FlightGuard<'_> {
+		let flight = self.get(key);
+		Acquire(Arc::clone(&flight)).await;
+		FlightGuard {
+			flights: self,
+			key: key.to_owned(),
+			flight,
+		}
+	}
+
+	/// Return the flight of key, creating it if necessary
+	fn get(&self, key: &str) -> Arc<Flight> {
+		let mut flights = self.0.lock().unwrap_or_else(|e| e.into_inner());
+		Arc::clone(flights.entry(key.to_owned()).or_default())
+	}
+
+	/// Return the number of flights held or waited for
+	#[cfg(test)]
+	pub(crate) fn len(&self) -> usize {
+		self.0.lock().unwrap().len()
+	}
+}
+
+/// Free the flight, wake up the waiters and remove the flight from the map if nobody else uses it
+/// when dropped
+pub(crate) struct FlightGuard<'a> {
+	flights: &'a Flights,
+	key: String,
+	flight: Arc<Flight>,
+}
+
+impl Drop for FlightGuard<'_> {
+	fn drop(&mut self) {
+		self.flight.release();
+		// the flights are only cloned with the map locked, so no new user can appear meanwhile
+		let mut flights = self.flights.0.lock().unwrap_or_else(|e| e.into_inner());
+		if Arc::strong_count(&self.flight) == 2 {
+			flights.remove(&self.key);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::{sync::mpsc, thread, time::Duration};
+
+	#[test]
+	fn waiters_run_one_at_a_time() {
+		let flights = Arc::new(Flights::default());
+		let guard = flights.acquire("role");
+		let (tx, rx) = mpsc::channel();
+		let thread_flights = Arc::clone(&flights);
+		let handle = thread::spawn(move || {
+			let _guard = thread_flights.acquire("role");
+			tx.send(()).unwrap();
+		});
+		assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+		drop(guard);
+		assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+		handle.join().unwrap();
+		assert_eq!(flights.len(), 0);
+	}
+}
@@ -0,0 +1,120 @@
+use crate::{
+	auth::Auth,
+	client::VaultClient,
+	error::{Error, Result},
+	method::{login_path, AuthMethod, JwtLogin, Login},
+	transport::{fetch, fetch_async},
+};
+
+use http::Request;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Identity endpoint of the default service account on the metadata server
+const IDENTITY_URL: &str =
+	"http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/identity";
+
+/// GCP auth method using a jwt signed by google (instance identity or service account token)
+pub struct GcpAuth {
+	login_path: String,
+	jwt: String,
+}
+
+/// The jwt is not shown
+impl fmt::Debug for GcpAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GcpAuth")
+			.field("login_path", &self.login_path)
+			.finish()
+	}
+}
+
+/// Scrub the jwt from memory
+impl Drop for GcpAuth {
+	fn drop(&mut self) {
+		self.jwt.zeroize();
+	}
+}
+
+impl GcpAuth {
+	/// create a gcp auth method given its mount point and a signed jwt
+	pub fn new(mount: &str, jwt: &str) -> Self {
+		Self {
+			login_path: login_path(mount),
+			jwt: jwt.to_owned(),
+		}
+	}
+}
+
+impl AuthMethod for GcpAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: JwtLogin {
+				role,
+				jwt: &self.jwt,
+			}
+			.to_value()?,
+		})
+	}
+}
+
+/// Build the request of an identity token for the vault role to the metadata server
+fn identity_request(role: &str) -> Result<Request<String>> {
+	Request::get(format!(
+		"{}?audience=vault/{}&format=full",
+		IDENTITY_URL, role
+	))
+	.header("Metadata-Flavor", "Google")
+	.body(String::new())
+	.map_err(|e| Error::HttpError { source: e })
+}
+
+/// Fetch from the metadata server an identity token of the instance (or of the workload
+/// identity on GKE) with the audience expected by vault for role
+pub fn fetch_identity_token(role: &str) -> Result<String> {
+	let (status, body) = fetch(identity_request(role)?)?;
+	if status.is_success() {
+		Ok(body.trim().to_owned())
+	} else {
+		Err(Error::from_status(status, IDENTITY_URL, vec![body]))
+	}
+}
+
+/// Fetch asynchronously from the metadata server an identity token for role
+pub async fn fetch_identity_token_async(role: &str) -> Result<String> {
+	let (status, body) = fetch_async(identity_request(role)?).await?;
+	if status.is_success() {
+		Ok(body.trim().to_owned())
+	} else {
+		Err(Error::from_status(status, IDENTITY_URL, vec![body]))
+	}
+}
+
+impl VaultClient {
+	/// Log in to the vault client for role with the gcp auth method mounted on `auth/gcp` using a
+	/// signed jwt (see `gcp::fetch_identity_token`) and return Auth
+	pub fn login_gcp(&self, role: &str, jwt: &str) -> Result<Auth> {
+		self.login_with(&GcpAuth::new("gcp", jwt), role)
+	}
+
+	/// Log in asynchronously to the vault client for role with the gcp auth method and return Auth
+	pub async fn login_gcp_async(&self, role: &str, jwt: &str) -> Result<Auth> {
+		self.login_with_async(&GcpAuth::new("gcp", jwt), role).await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn identity_token_audience() {
+		let request = identity_request("app").unwrap();
+		assert_eq!(
+			request.uri().query(),
+			Some("audience=vault/app&format=full")
+		);
+		assert_eq!(request.headers()["Metadata-Flavor"], "Google");
+	}
+}
@@ -0,0 +1,213 @@
+use crate::{
+	base64,
+	client::VaultClient,
+	error::{Error, Result},
+	lease::Lease,
+	redact::Redacted,
+	secret::TypedSecret,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fmt, time::Duration};
+use zeroize::ZeroizeOnDrop;
+
+/// OAuth2 access token of a roleset of the gcp secrets engine. Tokens are not leased and
+/// can't be renewed: generate a new one when it expires
+#[derive(ZeroizeOnDrop)]
+pub struct GcpToken {
+	pub token: String,
+	/// expiration as a unix timestamp
+	pub expires_at_seconds: u64,
+	#[zeroize(skip)]
+	pub token_ttl: Duration,
+	#[zeroize(skip)]
+	lease: Lease,
+}
+
+impl fmt::Debug for GcpToken {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GcpToken")
+			.field("token", &Redacted(&self.token))
+			.field("expires_at_seconds", &self.expires_at_seconds)
+			.field("token_ttl", &self.token_ttl)
+			.field("lease", &self.lease)
+			.finish()
+	}
+}
+
+/// token data as returned by vault
+#[derive(Deserialize)]
+struct TokenData {
+	token: String,
+	expires_at_seconds: u64,
+	token_ttl: u64,
+}
+
+impl GcpToken {
+	/// extract an access token from a response of `<mount>/roleset/<roleset>/token`
+	fn from_value(mut value: Value) -> Result<Self> {
+		let data: TokenData = serde_json::from_value(value["data"].take())
+			.map_err(|e| Error::ParseError { source: e })?;
+		let token_ttl = Duration::from_secs(data.token_ttl);
+		Ok(Self {
+			token: data.token,
+			expires_at_seconds: data.expires_at_seconds,
+			token_ttl,
+			lease: Lease::new(token_ttl),
+		})
+	}
+
+	/// check if the token is not expired
+	pub fn is_valid(&self) -> bool {
+		self.lease.is_valid()
+	}
+
+	/// check if a new token should be generated
+	pub fn to_renew(&self) -> bool {
+		self.lease.to_renew()
+	}
+
+	pub fn renew_delay(&self) -> Duration {
+		self.lease.renew_delay
+	}
+}
+
+/// Service account key of a roleset of the gcp secrets engine
+#[derive(Deserialize, ZeroizeOnDrop)]
+pub struct GcpKey {
+	/// base64 encoded service account credentials
+	pub private_key_data: String,
+	pub key_algorithm: String,
+	pub key_type: String,
+}
+
+impl fmt::Debug for GcpKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GcpKey")
+			.field("private_key_data", &Redacted(&self.private_key_data))
+			.field("key_algorithm", &self.key_algorithm)
+			.field("key_type", &self.key_type)
+			.finish()
+	}
+}
+
+impl GcpKey {
+	/// decode the service account credentials (a json file for the `TYPE_GOOGLE_CREDENTIALS_FILE`
+	/// key type)
+	pub fn credentials(&self) -> Result<Vec<u8>> {
+		base64::decode(&self.private_key_data).ok_or(Error::InvalidBase64)
+	}
+}
+
+/// Return the path of a roleset endpoint
+fn roleset_path(mount: &str, roleset: &str, endpoint: &str) -> String {
+	format!(
+		"{}/roleset/{}/{}",
+		mount.trim_matches('/'),
+		roleset,
+		endpoint
+	)
+}
+
+impl VaultClient {
+	/// Generate an access token for roleset of the gcp engine mounted at mount
+	pub fn gcp_token(&self, role: &str, mount: &str, roleset: &str) -> Result<GcpToken> {
+		let request = self.vault_request(
+			"GET",
+			&roleset_path(mount, roleset, "token"),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		GcpToken::from_value(self.send(request)?)
+	}
+
+	/// Generate asynchronously an access token for roleset of the gcp engine
+	pub async fn gcp_token_async(
+		&self,
+		role: &str,
+		mount: &str,
+		roleset: &str,
+	) -> Result<GcpToken> {
+		let request = self.vault_request(
+			"GET",
+			&roleset_path(mount, roleset, "token"),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		GcpToken::from_value(self.send_async(request).await?)
+	}
+
+	/// Generate a service account key for roleset of the gcp engine mounted at mount
+	pub fn gcp_key(&self, role: &str, mount: &str, roleset: &str) -> Result<TypedSecret<GcpKey>> {
+		let request = self.vault_request(
+			"GET",
+			&roleset_path(mount, roleset, "key"),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		self.leased(self.send(request)?)
+	}
+
+	/// Generate asynchronously a service account key for roleset of the gcp engine
+	pub async fn gcp_key_async(
+		&self,
+		role: &str,
+		mount: &str,
+		roleset: &str,
+	) -> Result<TypedSecret<GcpKey>> {
+		let request = self.vault_request(
+			"GET",
+			&roleset_path(mount, roleset, "key"),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		self.leased(self.send_async(request).await?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn token_from_response() {
+		let token = GcpToken::from_value(json!({
+			"data": {
+				"token": "ya29.c.ElodBmNPwHUNY5gcBpnXcE4ywG4w1k...",
+				"expires_at_seconds": 1537402548,
+				"token_ttl": 3599
+			}
+		}))
+		.unwrap();
+		assert_eq!(token.token_ttl, Duration::from_secs(3599));
+		assert!(token.is_valid());
+	}
+
+	#[test]
+	fn key_from_response() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"gcp/roleset/project/key",
+			200,
+			json!({
+				"lease_id": "gcp/roleset/project/key/2f6a614c",
+				"lease_duration": 2764800,
+				"renewable": true,
+				"data": {
+					"private_key_data": "eyJ0eXBlIjoic2VydmljZV9hY2NvdW50In0=",
+					"key_algorithm": "KEY_ALG_RSA_2048",
+					"key_type": "TYPE_GOOGLE_CREDENTIALS_FILE"
+				}
+			}),
+		);
+		let key = transport
+			.client()
+			.gcp_key(TOKEN_ROLE, "gcp", "project")
+			.unwrap();
+		assert_eq!(key.credentials().unwrap(), br#"{"type":"service_account"}"#);
+	}
+}
@@ -0,0 +1,556 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+	lease::Lease,
+	redact::Redacted,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, fmt, time::Duration};
+use zeroize::ZeroizeOnDrop;
+
+/// Properties of an entity to create or update. Unset properties are left unchanged
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct EntityConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub policies: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub metadata: Option<HashMap<String, String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub disabled: Option<bool>,
+}
+
+/// Properties of a group to create or update. Unset properties are left unchanged
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GroupConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	/// `internal` (default) or `external`
+	#[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+	pub group_type: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub policies: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub member_entity_ids: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub member_group_ids: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub metadata: Option<HashMap<String, String>>,
+}
+
+/// An identity entity
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entity {
+	pub id: String,
+	pub name: String,
+	#[serde(default)]
+	pub policies: Vec<String>,
+	#[serde(default)]
+	pub metadata: Option<HashMap<String, String>>,
+	#[serde(default)]
+	pub disabled: bool,
+	#[serde(default)]
+	pub aliases: Vec<EntityAlias>,
+	#[serde(default)]
+	pub group_ids: Vec<String>,
+}
+
+/// An alias tying an entity to the identity of an auth method
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntityAlias {
+	pub id: String,
+	pub name: String,
+	pub canonical_id: String,
+	pub mount_accessor: String,
+	#[serde(default)]
+	pub mount_type: String,
+}
+
+/// An identity group
+#[derive(Debug, Clone, Deserialize)]
+pub struct Group {
+	pub id: String,
+	pub name: String,
+	#[serde(rename = "type")]
+	pub group_type: String,
+	#[serde(default)]
+	pub policies: Vec<String>,
+	#[serde(default)]
+	pub member_entity_ids: Vec<String>,
+	#[serde(default)]
+	pub member_group_ids: Vec<String>,
+	#[serde(default)]
+	pub metadata: Option<HashMap<String, String>>,
+}
+
+/// An oidc identity token signed by vault
+#[derive(Clone, ZeroizeOnDrop)]
+pub struct IdentityToken {
+	pub token: String,
+	/// audience of the token
+	pub client_id: String,
+	#[zeroize(skip)]
+	pub ttl: Duration,
+	#[zeroize(skip)]
+	lease: Lease,
+}
+
+impl fmt::Debug for IdentityToken {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("IdentityToken")
+			.field("token", &Redacted(&self.token))
+			.field("client_id", &self.client_id)
+			.field("ttl", &self.ttl)
+			.field("lease", &self.lease)
+			.finish()
+	}
+}
+
+/// identity token data as returned by vault
+#[derive(Deserialize)]
+struct TokenData {
+	token: String,
+	client_id: String,
+	ttl: u64,
+}
+
+impl IdentityToken {
+	/// extract an identity token from a response of `identity/oidc/token/<name>`
+	fn from_value(value: Value) -> Result<Self> {
+		let data: TokenData = data_from_value(value)?;
+		let ttl = Duration::from_secs(data.ttl);
+		Ok(Self {
+			token: data.token,
+			client_id: data.client_id,
+			ttl,
+			lease: Lease::new(ttl),
+		})
+	}
+
+	/// check if the token is not expired
+	pub fn is_valid(&self) -> bool {
+		self.lease.is_valid()
+	}
+
+	/// check if a new token should be issued
+	pub fn to_renew(&self) -> bool {
+		self.lease.to_renew()
+	}
+}
+
+/// Extract the data of an identity response
+fn data_from_value<T: DeserializeOwned>(mut value: Value) -> Result<T> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Extract the id of a created object
+fn id_from_value(value: &Value) -> Result<String> {
+	value["data"]["id"]
+		.as_str()
+		.map(|s| s.to_owned())
+		.ok_or(Error::MissingField("id"))
+}
+
+/// Body of an entity creation with a mandatory name
+fn entity_body(name: &str, config: &EntityConfig) -> Value {
+	let mut body = json!(config);
+	body["name"] = Value::from(name);
+	body
+}
+
+/// Body of a group creation with a mandatory name
+fn group_body(name: &str, config: &GroupConfig) -> Value {
+	let mut body = json!(config);
+	body["name"] = Value::from(name);
+	body
+}
+
+/// Body of an identity token introspection
+fn introspect_body(token: &str, client_id: Option<&str>) -> Value {
+	let mut body = json!({ "token": token });
+	if let Some(client_id) = client_id {
+		body["client_id"] = Value::from(client_id);
+	}
+	body
+}
+
+/// Extract the state of an introspected token (the response is not wrapped in data)
+fn active_from_value(value: &Value) -> Result<bool> {
+	value["active"]
+		.as_bool()
+		.ok_or(Error::MissingField("active"))
+}
+
+impl VaultClient {
+	/// Return a cached identity token of the token of role for the oidc role name if it is not
+	/// about to expire
+	fn cached_identity_token(&self, role: &str, name: &str) -> Option<IdentityToken> {
+		let tokens = self
+			.identity_tokens
+			.lock()
+			.unwrap_or_else(|e| e.into_inner());
+		tokens
+			.get(&(role.to_owned(), name.to_owned()))
+			.filter(|token| token.is_valid() && !token.to_renew())
+			.cloned()
+	}
+
+	/// Cache an identity token and return it
+	fn cache_identity_token(&self, role: &str, name: &str, token: IdentityToken) -> IdentityToken {
+		self.identity_tokens
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.insert((role.to_owned(), name.to_owned()), token.clone());
+		token
+	}
+
+	/// Return an identity token of the entity of the token of role for the oidc role name. The
+	/// token is cached and issued again when 2/3 of its ttl have elapsed
+	pub fn identity_oidc_token(&self, role: &str, name: &str) -> Result<IdentityToken> {
+		if let Some(token) = self.cached_identity_token(role, name) {
+			return Ok(token);
+		}
+		let path = format!("identity/oidc/token/{}", name);
+		let token = IdentityToken::from_value(self.request(role, "GET", &path, None)?)?;
+		Ok(self.cache_identity_token(role, name, token))
+	}
+
+	/// Return asynchronously a cached or newly issued identity token for the oidc role name
+	pub async fn identity_oidc_token_async(&self, role: &str, name: &str) -> Result<IdentityToken> {
+		if let Some(token) = self.cached_identity_token(role, name) {
+			return Ok(token);
+		}
+		let path = format!("identity/oidc/token/{}", name);
+		let value = self.request_async(role, "GET", &path, None).await?;
+		let token = IdentityToken::from_value(value)?;
+		Ok(self.cache_identity_token(role, name, token))
+	}
+
+	/// Check that an identity token is active (well signed, not expired and with client_id as
+	/// audience when given)
+	pub fn identity_oidc_introspect(
+		&self,
+		role: &str,
+		token: &str,
+		client_id: Option<&str>,
+	) -> Result<bool> {
+		let value = self.request(
+			role,
+			"POST",
+			"identity/oidc/introspect",
+			Some(introspect_body(token, client_id)),
+		)?;
+		active_from_value(&value)
+	}
+
+	/// Check asynchronously that an identity token is active
+	pub async fn identity_oidc_introspect_async(
+		&self,
+		role: &str,
+		token: &str,
+		client_id: Option<&str>,
+	) -> Result<bool> {
+		let value = self
+			.request_async(
+				role,
+				"POST",
+				"identity/oidc/introspect",
+				Some(introspect_body(token, client_id)),
+			)
+			.await?;
+		active_from_value(&value)
+	}
+
+	/// Create the entity name and return its id
+	pub fn create_entity(&self, role: &str, name: &str, config: &EntityConfig) -> Result<String> {
+		let value = self.request(
+			role,
+			"POST",
+			"identity/entity",
+			Some(entity_body(name, config)),
+		)?;
+		id_from_value(&value)
+	}
+
+	/// Create asynchronously the entity name and return its id
+	pub async fn create_entity_async(
+		&self,
+		role: &str,
+		name: &str,
+		config: &EntityConfig,
+	) -> Result<String> {
+		let value = self
+			.request_async(
+				role,
+				"POST",
+				"identity/entity",
+				Some(entity_body(name, config)),
+			)
+			.await?;
+		id_from_value(&value)
+	}
+
+	/// Return the entity with id
+	pub fn read_entity(&self, role: &str, id: &str) -> Result<Entity> {
+		data_from_value(self.request(role, "GET", &format!("identity/entity/id/{}", id), None)?)
+	}
+
+	/// Return asynchronously the entity with id
+	pub async fn read_entity_async(&self, role: &str, id: &str) -> Result<Entity> {
+		let path = format!("identity/entity/id/{}", id);
+		data_from_value(self.request_async(role, "GET", &path, None).await?)
+	}
+
+	/// Return the entity named name
+	pub fn read_entity_by_name(&self, role: &str, name: &str) -> Result<Entity> {
+		let path = format!("identity/entity/name/{}", name);
+		data_from_value(self.request(role, "GET", &path, None)?)
+	}
+
+	/// Return asynchronously the entity named name
+	pub async fn read_entity_by_name_async(&self, role: &str, name: &str) -> Result<Entity> {
+		let path = format!("identity/entity/name/{}", name);
+		data_from_value(self.request_async(role, "GET", &path, None).await?)
+	}
+
+	/// Update the properties of the entity with id
+	pub fn update_entity(&self, role: &str, id: &str, config: &EntityConfig) -> Result<()> {
+		let path = format!("identity/entity/id/{}", id);
+		self.request(role, "POST", &path, Some(json!(config)))
+			.map(|_| ())
+	}
+
+	/// Update asynchronously the properties of the entity with id
+	pub async fn update_entity_async(
+		&self,
+		role: &str,
+		id: &str,
+		config: &EntityConfig,
+	) -> Result<()> {
+		let path = format!("identity/entity/id/{}", id);
+		self.request_async(role, "POST", &path, Some(json!(config)))
+			.await
+			.map(|_| ())
+	}
+
+	/// Delete the entity with id and its aliases
+	pub fn delete_entity(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/entity/id/{}", id);
+		self.request(role, "DELETE", &path, None).map(|_| ())
+	}
+
+	/// Delete asynchronously the entity with id and its aliases
+	pub async fn delete_entity_async(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/entity/id/{}", id);
+		self.request_async(role, "DELETE", &path, None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Create an alias name of the entity canonical_id for the auth method with mount_accessor
+	/// and return its id
+	pub fn create_entity_alias(
+		&self,
+		role: &str,
+		name: &str,
+		canonical_id: &str,
+		mount_accessor: &str,
+	) -> Result<String> {
+		let body = json!({
+			"name": name,
+			"canonical_id": canonical_id,
+			"mount_accessor": mount_accessor,
+		});
+		id_from_value(&self.request(role, "POST", "identity/entity-alias", Some(body))?)
+	}
+
+	/// Create asynchronously an alias name of the entity canonical_id and return its id
+	pub async fn create_entity_alias_async(
+		&self,
+		role: &str,
+		name: &str,
+		canonical_id: &str,
+		mount_accessor: &str,
+	) -> Result<String> {
+		let body = json!({
+			"name": name,
+			"canonical_id": canonical_id,
+			"mount_accessor": mount_accessor,
+		});
+		let value = self
+			.request_async(role, "POST", "identity/entity-alias", Some(body))
+			.await?;
+		id_from_value(&value)
+	}
+
+	/// Return the entity alias with id
+	pub fn read_entity_alias(&self, role: &str, id: &str) -> Result<EntityAlias> {
+		let path = format!("identity/entity-alias/id/{}", id);
+		data_from_value(self.request(role, "GET", &path, None)?)
+	}
+
+	/// Return asynchronously the entity alias with id
+	pub async fn read_entity_alias_async(&self, role: &str, id: &str) -> Result<EntityAlias> {
+		let path = format!("identity/entity-alias/id/{}", id);
+		data_from_value(self.request_async(role, "GET", &path, None).await?)
+	}
+
+	/// Delete the entity alias with id
+	pub fn delete_entity_alias(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/entity-alias/id/{}", id);
+		self.request(role, "DELETE", &path, None).map(|_| ())
+	}
+
+	/// Delete asynchronously the entity alias with id
+	pub async fn delete_entity_alias_async(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/entity-alias/id/{}", id);
+		self.request_async(role, "DELETE", &path, None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Create the group name and return its id
+	pub fn create_group(&self, role: &str, name: &str, config: &GroupConfig) -> Result<String> {
+		let value = self.request(
+			role,
+			"POST",
+			"identity/group",
+			Some(group_body(name, config)),
+		)?;
+		id_from_value(&value)
+	}
+
+	/// Create asynchronously the group name and return its id
+	pub async fn create_group_async(
+		&self,
+		role: &str,
+		name: &str,
+		config: &GroupConfig,
+	) -> Result<String> {
+		let value = self
+			.request_async(
+				role,
+				"POST",
+				"identity/group",
+				Some(group_body(name, config)),
+			)
+			.await?;
+		id_from_value(&value)
+	}
+
+	/// Return the group with id
+	pub fn read_group(&self, role: &str, id: &str) -> Result<Group> {
+		data_from_value(self.request(role, "GET", &format!("identity/group/id/{}", id), None)?)
+	}
+
+	/// Return asynchronously the group with id
+	pub async fn read_group_async(&self, role: &str, id: &str) -> Result<Group> {
+		let path = format!("identity/group/id/{}", id);
+		data_from_value(self.request_async(role, "GET", &path, None).await?)
+	}
+
+	/// Return the group named name
+	pub fn read_group_by_name(&self, role: &str, name: &str) -> Result<Group> {
+		let path = format!("identity/group/name/{}", name);
+		data_from_value(self.request(role, "GET", &path, None)?)
+	}
+
+	/// Return asynchronously the group named name
+	pub async fn read_group_by_name_async(&self, role: &str, name: &str) -> Result<Group> {
+		let path = format!("identity/group/name/{}", name);
+		data_from_value(self.request_async(role, "GET", &path, None).await?)
+	}
+
+	/// Update the properties of the group with id
+	pub fn update_group(&self, role: &str, id: &str, config: &GroupConfig) -> Result<()> {
+		let path = format!("identity/group/id/{}", id);
+		self.request(role, "POST", &path, Some(json!(config)))
+			.map(|_| ())
+	}
+
+	/// Update asynchronously the properties of the group with id
+	pub async fn update_group_async(
+		&self,
+		role: &str,
+		id: &str,
+		config: &GroupConfig,
+	) -> Result<()> {
+		let path = format!("identity/group/id/{}", id);
+		self.request_async(role, "POST", &path, Some(json!(config)))
+			.await
+			.map(|_| ())
+	}
+
+	/// Delete the group with id
+	pub fn delete_group(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/group/id/{}", id);
+		self.request(role, "DELETE", &path, None).map(|_| ())
+	}
+
+	/// Delete asynchronously the group with id
+	pub async fn delete_group_async(&self, role: &str, id: &str) -> Result<()> {
+		let path = format!("identity/group/id/{}", id);
+		self.request_async(role, "DELETE", &path, None)
+			.await
+			.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn group_body_renames_type() {
+		let config = GroupConfig {
+			group_type: Some("external".to_owned()),
+			policies: Some(vec!["ops".to_owned()]),
+			..Default::default()
+		};
+		assert_eq!(
+			group_body("ops", &config),
+			json!({"name": "ops", "type": "external", "policies": ["ops"]})
+		);
+	}
+
+	#[test]
+	fn identity_token_from_response() {
+		let token = IdentityToken::from_value(json!({
+			"data": {
+				"client_id": "P6CfCzyHsQY4pMcA6kWAOCItA7",
+				"token": "eyJhbGciOiJSUzI1NiIsImtpZCI6IjJkMGI4YjlkLWYwNGQtNzFlYy1iNjc0LWM3MzU4NDMyYmM1YiJ9",
+				"ttl": 3600
+			}
+		}))
+		.unwrap();
+		assert!(token.is_valid() && !token.to_renew());
+		assert!(!active_from_value(&json!({"active": false})).unwrap());
+	}
+
+	#[test]
+	fn entity_from_response() {
+		let entity: Entity = data_from_value(json!({
+			"data": {
+				"id": "8d6a45e5-572f-8f13-d226-cd0d1ec57297",
+				"name": "alice",
+				"policies": ["dev"],
+				"metadata": null,
+				"disabled": false,
+				"aliases": [{
+					"id": "b86e7d4c-a1d1-c6b8-4e8b-95dfab2b4e7b",
+					"name": "alice",
+					"canonical_id": "8d6a45e5-572f-8f13-d226-cd0d1ec57297",
+					"mount_accessor": "auth_kubernetes_e1d4b2c3",
+					"mount_type": "kubernetes"
+				}],
+				"group_ids": []
+			}
+		}))
+		.unwrap();
+		assert_eq!(entity.aliases[0].canonical_id, entity.id);
+		assert!(id_from_value(&Value::Null).is_err());
+	}
+}
@@ -0,0 +1,142 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use http::{request, response, Method, Request, Response, Uri};
+use std::fmt;
+
+/// Hook on the requests sent to vault (json and raw ones, each attempt, failover and redirect),
+/// to add headers, audit the requests or inject faults in tests. The interceptors are called
+/// in the order they were added to the builder
+pub trait Interceptor: fmt::Debug + Send + Sync {
+	/// Change the method, uri or headers of a request before it is sent. An error fails the
+	/// attempt without sending it, and is retried like the other errors if it's transient
+	fn on_request(&self, _request: &mut request::Parts) -> Result<()> {
+		Ok(())
+	}
+
+	/// Observe the response (status and headers) or the error of a request
+	fn on_response(
+		&self,
+		_method: &Method,
+		_uri: &Uri,
+		_response: std::result::Result<&response::Parts, &Error>,
+	) {
+	}
+}
+
+impl VaultClient {
+	/// Pass request through the interceptors before sending it
+	pub(crate) fn before_send<B>(&self, request: Request<B>) -> Result<Request<B>> {
+		if self.interceptors.is_empty() {
+			return Ok(request);
+		}
+		let (mut parts, body) = request.into_parts();
+		for interceptor in self.interceptors.iter() {
+			interceptor.on_request(&mut parts)?;
+		}
+		Ok(Request::from_parts(parts, body))
+	}
+
+	/// Show the response to the request method uri to the interceptors
+	pub(crate) fn after_send<B>(
+		&self,
+		method: &Method,
+		uri: &Uri,
+		res: Result<Response<B>>,
+	) -> Result<Response<B>> {
+		if self.interceptors.is_empty() {
+			return res;
+		}
+		match res {
+			Ok(res) => {
+				let (parts, body) = res.into_parts();
+				for interceptor in self.interceptors.iter() {
+					interceptor.on_response(method, uri, Ok(&parts));
+				}
+				Ok(Response::from_parts(parts, body))
+			}
+			Err(e) => {
+				for interceptor in self.interceptors.iter() {
+					interceptor.on_response(method, uri, Err(&e));
+				}
+				Err(e)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{retry::RetryPolicy, transport::mock::Scripted};
+	use http::{HeaderValue, StatusCode};
+	use serde_json::json;
+	use std::sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc, Mutex,
+	};
+
+	/// interceptor adding a header, failing the first request and recording the responses
+	#[derive(Debug, Default)]
+	struct Audit {
+		requests: AtomicUsize,
+		responses: Mutex<Vec<String>>,
+	}
+
+	impl Interceptor for Audit {
+		fn on_request(&self, request: &mut request::Parts) -> Result<()> {
+			if self.requests.fetch_add(1, Ordering::SeqCst) == 0 {
+				return Err(Error::from_status(
+					StatusCode::SERVICE_UNAVAILABLE,
+					request.uri.path(),
+					vec!["injected".to_owned()],
+				));
+			}
+			request
+				.headers
+				.insert("X-Test", HeaderValue::from_static("intercepted"));
+			Ok(())
+		}
+
+		fn on_response(
+			&self,
+			method: &Method,
+			uri: &Uri,
+			response: std::result::Result<&response::Parts, &Error>,
+		) {
+			self.responses.lock().unwrap().push(format!(
+				"{} {} {:?}",
+				method,
+				uri.path(),
+				response.map(|parts| parts.status)
+			));
+		}
+	}
+
+	#[test]
+	fn requests_are_intercepted() {
+		let audit = Arc::new(Audit::default());
+		let transport = Scripted::new();
+		transport.on("GET", "sys/init", 200, json!({"initialized": true}));
+		let client = transport
+			.builder()
+			.retry(RetryPolicy::new(2, std::time::Duration::from_millis(1)))
+			.interceptor(audit.clone())
+			.build()
+			.unwrap();
+		let request = client
+			.vault_request("GET", "sys/init", None, String::new())
+			.unwrap();
+		assert_eq!(client.send_idempotent(request).unwrap(), json!({"initialized": true}));
+		assert_eq!(
+			transport.header("GET sys/init", "X-Test").as_deref(),
+			Some("intercepted")
+		);
+		assert_eq!(
+			*audit.responses.lock().unwrap(),
+			vec!["GET /v1/sys/init Ok(200)"]
+		);
+	}
+}
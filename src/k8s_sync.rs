@@ -0,0 +1,348 @@
+use crate::{
+	base64,
+	error::{Error, Result},
+	secret::{raw_value, Secret},
+	sha256::{hex, sha256},
+	transport::HttpTransport,
+};
+#[cfg(feature = "isahc")]
+use crate::{builder::SERVICE_ACCOUNT_TOKEN_PATH, transport::IsahcTransport};
+
+use http::{Request, StatusCode};
+#[cfg(feature = "isahc")]
+use isahc::{
+	config::{CaCertificate, Configurable},
+	HttpClient,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+#[cfg(feature = "isahc")]
+use std::env;
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Arc};
+
+/// Certificate authority of the api server mounted in kubernetes pods
+pub const SERVICE_ACCOUNT_CA_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount/ca.crt";
+/// Namespace of the pod mounted in kubernetes pods
+pub const SERVICE_ACCOUNT_NAMESPACE_PATH: &str =
+	"/var/run/secrets/kubernetes.io/serviceaccount/namespace";
+/// Annotation holding the hash of the data, labels, owner and type of a synchronized secret
+pub const HASH_ANNOTATION: &str = "vault-jwt/data-hash";
+
+/// Owner of a synchronized secret, deleted with it by the kubernetes garbage collector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnerReference {
+	pub api_version: String,
+	pub kind: String,
+	pub name: String,
+	pub uid: String,
+	#[serde(default)]
+	pub controller: bool,
+}
+
+/// Kubernetes secret to write a vault secret to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecretTarget {
+	pub namespace: String,
+	pub name: String,
+	/// `Opaque` if empty
+	pub type_: String,
+	pub labels: BTreeMap<String, String>,
+	pub owner: Option<OwnerReference>,
+}
+
+/// Outcome of a synchronization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncResult {
+	Created,
+	Updated,
+	/// the data of the kubernetes secret is already up to date
+	Unchanged,
+}
+
+/// Minimal client of the kubernetes api for the secrets, authenticated with a bearer token. The
+/// `kube` feature doesn't depend on the kube crate: this client only covers what `sync_secret`
+/// needs
+#[derive(Debug)]
+pub struct KubeClient {
+	url: String,
+	transport: Arc<dyn HttpTransport>,
+	token_path: PathBuf,
+}
+
+/// Return the base64 encoded data of a kubernetes secret holding the fields of secret
+fn secret_data(secret: &Secret) -> Result<Map<String, Value>> {
+	let map = secret.value.as_object().ok_or(Error::SecretNotObject)?;
+	Ok(map
+		.iter()
+		.map(|(key, value)| {
+			(
+				key.clone(),
+				Value::from(base64::encode(raw_value(value).as_bytes())),
+			)
+		})
+		.collect())
+}
+
+impl SecretTarget {
+	/// write to the secret name in namespace
+	pub fn new(namespace: &str, name: &str) -> Self {
+		Self {
+			namespace: namespace.to_owned(),
+			name: name.to_owned(),
+			..Default::default()
+		}
+	}
+
+	/// Return the type of the secret
+	fn secret_type(&self) -> &str {
+		if self.type_.is_empty() {
+			"Opaque"
+		} else {
+			&self.type_
+		}
+	}
+
+	/// Return a hash of what is written to the kubernetes secret: data, labels, owner and type
+	/// (the keys of the maps are sorted)
+	fn hash(&self, data: &Map<String, Value>) -> String {
+		let content = json!({
+			"data": data,
+			"labels": self.labels,
+			"owner": self.owner,
+			"type": self.secret_type(),
+		});
+		hex(&sha256(content.to_string().as_bytes()))
+	}
+
+	/// Return the kubernetes object holding data, also used as a strategic merge patch so that
+	/// the labels, annotations and owners set by others are kept
+	fn object(&self, data: Map<String, Value>) -> Value {
+		let mut metadata = json!({
+			"name": self.name,
+			"namespace": self.namespace,
+			"labels": self.labels,
+			"annotations": { HASH_ANNOTATION: self.hash(&data) },
+		});
+		if let Some(ref owner) = self.owner {
+			metadata["ownerReferences"] = json!([owner]);
+		}
+		json!({
+			"apiVersion": "v1",
+			"kind": "Secret",
+			"metadata": metadata,
+			"type": self.secret_type(),
+			"data": data,
+		})
+	}
+}
+
+impl KubeClient {
+	/// Create a client of the api server at url, authenticated with the token read at each
+	/// request from token_path and trusting the certificate authority at ca_path
+	#[cfg(feature = "isahc")]
+	pub fn new(url: &str, token_path: &str, ca_path: Option<&str>) -> Result<Self> {
+		let mut builder = HttpClient::builder();
+		if let Some(ca_path) = ca_path {
+			builder = builder.ssl_ca_certificate(CaCertificate::file(ca_path));
+		}
+		let transport = Arc::new(IsahcTransport::new(builder.build()?));
+		Ok(Self::with_transport(url, token_path, transport))
+	}
+
+	/// Create a client of the api server at url sending its requests with transport,
+	/// authenticated with the token read at each request from token_path
+	pub fn with_transport(url: &str, token_path: &str, transport: Arc<dyn HttpTransport>) -> Self {
+		Self {
+			url: url.trim_end_matches('/').to_owned(),
+			transport,
+			token_path: PathBuf::from(token_path),
+		}
+	}
+
+	/// Create a client of the api server of the cluster running the pod with its service account
+	#[cfg(feature = "isahc")]
+	pub fn in_cluster() -> Result<Self> {
+		let host = env::var("KUBERNETES_SERVICE_HOST").map_err(|_| Error::NotInCluster)?;
+		let port = env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_owned());
+		let host = if host.contains(':') {
+			format!("[{}]", host)
+		} else {
+			host
+		};
+		Self::new(
+			&format!("https://{}:{}", host, port),
+			SERVICE_ACCOUNT_TOKEN_PATH,
+			Some(SERVICE_ACCOUNT_CA_PATH),
+		)
+	}
+
+	/// Return the namespace of the pod
+	pub fn pod_namespace() -> Result<String> {
+		Ok(fs::read_to_string(SERVICE_ACCOUNT_NAMESPACE_PATH)?
+			.trim()
+			.to_owned())
+	}
+
+	/// Send a request to path and return the json response or None if nothing exists at path
+	fn send(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Option<Value>> {
+		let token = fs::read_to_string(&self.token_path)?;
+		let mut request = Request::builder()
+			.method(method)
+			.uri(format!("{}{}", self.url, path))
+			.header("Authorization", format!("Bearer {}", token.trim()));
+		request = if method == "PATCH" {
+			request.header("Content-Type", "application/strategic-merge-patch+json")
+		} else {
+			request.header("Content-Type", "application/json")
+		};
+		let request = request.body(body.map(Value::to_string).unwrap_or_default())?;
+		let res = self.transport.send(request)?;
+		let status = res.status();
+		let text = String::from_utf8_lossy(res.body()).into_owned();
+		if status == StatusCode::NOT_FOUND {
+			Ok(None)
+		} else if status.is_success() {
+			serde_json::from_str(&text)
+				.map(Some)
+				.map_err(|e| Error::ParseError { source: e })
+		} else {
+			Err(Error::from_status(status, path, vec![text]))
+		}
+	}
+
+	/// Write the fields of secret to the kubernetes secret target, creating it if needed. The
+	/// secret is only patched if its data, labels, owner or type changed, according to its hash
+	/// annotation. The labels, annotations and owners set by others are kept, but the fields
+	/// removed from the vault secret are removed from the data
+	pub fn sync_secret(&self, target: &SecretTarget, secret: &Secret) -> Result<SyncResult> {
+		let data = secret_data(secret)?;
+		let path = format!("/api/v1/namespaces/{}/secrets", target.namespace);
+		let item_path = format!("{}/{}", path, target.name);
+		match self.send("GET", &item_path, None)? {
+			None => {
+				self.send("POST", &path, Some(&target.object(data)))?;
+				Ok(SyncResult::Created)
+			}
+			Some(current) => {
+				let annotations = &current["metadata"]["annotations"];
+				if annotations[HASH_ANNOTATION].as_str() == Some(&target.hash(&data)) {
+					return Ok(SyncResult::Unchanged);
+				}
+				let mut patch = target.object(data);
+				// a merge patch only adds or replaces keys: remove the fields that disappeared
+				if let (Some(current), Some(data)) =
+					(current["data"].as_object(), patch["data"].as_object_mut())
+				{
+					for key in current.keys() {
+						data.entry(key.clone()).or_insert(Value::Null);
+					}
+				}
+				self.send("PATCH", &item_path, Some(&patch))?;
+				Ok(SyncResult::Updated)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn kubernetes_secret_object() {
+		let secret = Secret::new(json!({"username": "app", "port": 5432}), None);
+		let mut target = SecretTarget::new("default", "app-db");
+		target.owner = Some(OwnerReference {
+			api_version: "apps/v1".to_owned(),
+			kind: "Deployment".to_owned(),
+			name: "app".to_owned(),
+			uid: "d9607e19-f88f-11e6-a518-42010a800195".to_owned(),
+			controller: true,
+		});
+		let data = secret_data(&secret).unwrap();
+		let hash = target.hash(&data);
+		let object = target.object(data.clone());
+		assert_eq!(
+			object["data"],
+			json!({"port": "NTQzMg==", "username": "YXBw"})
+		);
+		assert_eq!(object["type"], "Opaque");
+		assert_eq!(object["metadata"]["annotations"][HASH_ANNOTATION], hash);
+		assert_eq!(
+			object["metadata"]["ownerReferences"][0]["apiVersion"],
+			"apps/v1"
+		);
+		let changed = secret_data(&Secret::new(json!({"username": "other"}), None)).unwrap();
+		assert_ne!(target.hash(&changed), hash);
+		target
+			.labels
+			.insert("app.kubernetes.io/name".to_owned(), "app".to_owned());
+		assert_ne!(target.hash(&data), hash);
+		target.owner = None;
+		target.labels.clear();
+		assert_ne!(target.hash(&data), hash);
+	}
+
+	#[test]
+	fn secrets_are_synced_through_the_transport() {
+		let token_path =
+			std::env::temp_dir().join(format!("vault-jwt-k8s-{:016x}", fastrand::u64(..)));
+		fs::write(&token_path, "k8s-token\n").unwrap();
+		let transport = crate::transport::mock::Scripted::new();
+		let client = KubeClient::with_transport(
+			"https://kubernetes/",
+			token_path.to_str().unwrap(),
+			transport.clone(),
+		);
+		let target = SecretTarget::new("default", "app-db");
+		let secret = Secret::new(json!({"username": "app"}), None);
+		let item = "/api/v1/namespaces/default/secrets/app-db";
+		transport.on("POST", "/api/v1/namespaces/default/secrets", 201, json!({}));
+		let res = client.sync_secret(&target, &secret);
+		assert_eq!(res.unwrap(), SyncResult::Created);
+		let object = target.object(secret_data(&secret).unwrap());
+		assert_eq!(
+			transport.body("POST /api/v1/namespaces/default/secrets"),
+			Some(object.clone())
+		);
+		assert_eq!(
+			transport.header(&format!("GET {}", item), "Authorization"),
+			Some("Bearer k8s-token".to_owned())
+		);
+		transport.on("GET", item, 200, object);
+		let res = client.sync_secret(&target, &secret);
+		assert_eq!(res.unwrap(), SyncResult::Unchanged);
+		fs::remove_file(token_path).unwrap();
+	}
+
+	#[test]
+	fn removed_fields_are_removed_from_the_secret() {
+		let token_path =
+			std::env::temp_dir().join(format!("vault-jwt-k8s-{:016x}", fastrand::u64(..)));
+		fs::write(&token_path, "k8s-token\n").unwrap();
+		let transport = crate::transport::mock::Scripted::new();
+		let client = KubeClient::with_transport(
+			"https://kubernetes",
+			token_path.to_str().unwrap(),
+			transport.clone(),
+		);
+		let target = SecretTarget::new("default", "app-db");
+		let old = Secret::new(json!({"username": "app", "password": "pass"}), None);
+		let item = "/api/v1/namespaces/default/secrets/app-db";
+		transport
+			.on("GET", item, 200, target.object(secret_data(&old).unwrap()))
+			.on("PATCH", item, 200, json!({}));
+		let secret = Secret::new(json!({"username": "app"}), None);
+		let res = client.sync_secret(&target, &secret);
+		assert_eq!(res.unwrap(), SyncResult::Updated);
+		let patch = transport.body(&format!("PATCH {}", item)).unwrap();
+		assert_eq!(patch["data"], json!({"password": null, "username": "YXBw"}));
+		// the hash is the one of the new data, without the removed fields
+		assert_eq!(
+			patch["metadata"]["annotations"][HASH_ANNOTATION],
+			target.hash(&secret_data(&secret).unwrap())
+		);
+		fs::remove_file(token_path).unwrap();
+	}
+}
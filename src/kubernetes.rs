@@ -0,0 +1,149 @@
+use crate::{client::VaultClient, error::Result};
+
+use serde::Serialize;
+use serde_json::json;
+
+/// Configuration of the kubernetes auth method (`auth/<mount>/config`)
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct KubernetesConfig {
+	/// url of the kubernetes api server (ex: `https://kubernetes.default.svc`)
+	pub kubernetes_host: String,
+	/// pem encoded ca of the api server
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub kubernetes_ca_cert: Option<String>,
+	/// service account token used to call the TokenReview api. Without it the jwt used to
+	/// log in is used (or the local token when vault runs in the cluster)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub token_reviewer_jwt: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pem_keys: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub issuer: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub disable_iss_validation: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub disable_local_ca_jwt: Option<bool>,
+}
+
+/// A role of the kubernetes auth method (`auth/<mount>/role/<name>`)
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct KubernetesRole {
+	/// service account names allowed to log in (`*` for all)
+	pub bound_service_account_names: Vec<String>,
+	/// namespaces of the service accounts allowed to log in (`*` for all)
+	pub bound_service_account_namespaces: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub audience: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub token_policies: Option<Vec<String>>,
+	/// ttl of the tokens issued (ex: `1h`)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub token_ttl: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub token_max_ttl: Option<String>,
+}
+
+/// Return the path of a kubernetes auth method endpoint
+fn kubernetes_path(mount: &str, endpoint: &str) -> String {
+	format!("auth/{}/{}", mount.trim_matches('/'), endpoint)
+}
+
+impl VaultClient {
+	/// Configure the kubernetes auth method mounted at mount
+	pub fn configure_kubernetes_auth(
+		&self,
+		role: &str,
+		mount: &str,
+		config: &KubernetesConfig,
+	) -> Result<()> {
+		let path = kubernetes_path(mount, "config");
+		self.request(role, "POST", &path, Some(json!(config)))
+			.map(|_| ())
+	}
+
+	/// Configure asynchronously the kubernetes auth method mounted at mount
+	pub async fn configure_kubernetes_auth_async(
+		&self,
+		role: &str,
+		mount: &str,
+		config: &KubernetesConfig,
+	) -> Result<()> {
+		let path = kubernetes_path(mount, "config");
+		self.request_async(role, "POST", &path, Some(json!(config)))
+			.await
+			.map(|_| ())
+	}
+
+	/// Create or replace the role name of the kubernetes auth method mounted at mount
+	pub fn write_kubernetes_role(
+		&self,
+		role: &str,
+		mount: &str,
+		name: &str,
+		kube_role: &KubernetesRole,
+	) -> Result<()> {
+		let path = kubernetes_path(mount, &format!("role/{}", name));
+		self.request(role, "POST", &path, Some(json!(kube_role)))
+			.map(|_| ())
+	}
+
+	/// Create or replace asynchronously the role name of the kubernetes auth method
+	pub async fn write_kubernetes_role_async(
+		&self,
+		role: &str,
+		mount: &str,
+		name: &str,
+		kube_role: &KubernetesRole,
+	) -> Result<()> {
+		let path = kubernetes_path(mount, &format!("role/{}", name));
+		self.request_async(role, "POST", &path, Some(json!(kube_role)))
+			.await
+			.map(|_| ())
+	}
+
+	/// Delete the role name of the kubernetes auth method mounted at mount
+	pub fn delete_kubernetes_role(&self, role: &str, mount: &str, name: &str) -> Result<()> {
+		let path = kubernetes_path(mount, &format!("role/{}", name));
+		self.request(role, "DELETE", &path, None).map(|_| ())
+	}
+
+	/// Delete asynchronously the role name of the kubernetes auth method
+	pub async fn delete_kubernetes_role_async(
+		&self,
+		role: &str,
+		mount: &str,
+		name: &str,
+	) -> Result<()> {
+		let path = kubernetes_path(mount, &format!("role/{}", name));
+		self.request_async(role, "DELETE", &path, None)
+			.await
+			.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn role_body_skips_unset_fields() {
+		let role = KubernetesRole {
+			bound_service_account_names: vec!["app".to_owned()],
+			bound_service_account_namespaces: vec!["default".to_owned()],
+			token_ttl: Some("1h".to_owned()),
+			..Default::default()
+		};
+		assert_eq!(
+			json!(role),
+			json!({
+				"bound_service_account_names": ["app"],
+				"bound_service_account_namespaces": ["default"],
+				"token_ttl": "1h"
+			})
+		);
+		assert_eq!(
+			kubernetes_path("/kubernetes/", "config"),
+			"auth/kubernetes/config"
+		);
+	}
+}
@@ -0,0 +1,158 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde_json::{Map, Value};
+
+/// Return the path of a kv1 secret in a mount
+fn kv1_path(mount: &str, path: &str) -> String {
+	format!("{}/{}", mount.trim_matches('/'), path.trim_start_matches('/'))
+}
+
+/// Extract the data of a kv1 secret from a vault response
+fn data_from_value(mut value: Value) -> Result<Map<String, Value>> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Get the data of a kv1 secret at path in mount
+	pub fn kv1_get(&self, role: &str, mount: &str, path: &str) -> Result<Map<String, Value>> {
+		let request = self.vault_request(
+			"GET",
+			&kv1_path(mount, path),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		data_from_value(self.send(request)?)
+	}
+
+	/// Get asynchronously the data of a kv1 secret at path in mount
+	pub async fn kv1_get_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+	) -> Result<Map<String, Value>> {
+		let request = self.vault_request(
+			"GET",
+			&kv1_path(mount, path),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		data_from_value(self.send_async(request).await?)
+	}
+
+	/// Write (and replace) the data of a kv1 secret at path in mount
+	pub fn kv1_put(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+	) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			&kv1_path(mount, path),
+			Some(&self.token(role)?),
+			Value::from(data.clone()).to_string(),
+		)?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Write asynchronously the data of a kv1 secret at path in mount
+	pub async fn kv1_put_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+	) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			&kv1_path(mount, path),
+			Some(&self.token_async(role).await?),
+			Value::from(data.clone()).to_string(),
+		)?;
+		self.send_async(request).await.map(|_| ())
+	}
+
+	/// Delete a kv1 secret at path in mount
+	pub fn kv1_delete(&self, role: &str, mount: &str, path: &str) -> Result<()> {
+		let request = self.vault_request(
+			"DELETE",
+			&kv1_path(mount, path),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Delete asynchronously a kv1 secret at path in mount
+	pub async fn kv1_delete_async(&self, role: &str, mount: &str, path: &str) -> Result<()> {
+		let request = self.vault_request(
+			"DELETE",
+			&kv1_path(mount, path),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		self.send_async(request).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn kv1_data_from_response() {
+		let data = data_from_value(json!({
+			"lease_duration": 2764800,
+			"data": {"foo": "bar"}
+		}))
+		.unwrap();
+		assert_eq!(data["foo"], "bar");
+		assert_eq!(kv1_path("/secret/", "/foo"), "secret/foo");
+	}
+
+	#[test]
+	fn kv1_requests_are_sent_with_the_token() {
+		let transport = Scripted::new();
+		transport
+			.on("GET", "secret/app", 200, json!({"data": {"foo": "bar"}}))
+			.on("POST", "secret/app", 204, Value::Null)
+			.on("DELETE", "secret/app", 204, Value::Null);
+		let client = transport.client();
+		let mut data = Map::new();
+		data.insert("foo".to_owned(), Value::from("baz"));
+		client.kv1_put(TOKEN_ROLE, "secret", "app", &data).unwrap();
+		assert_eq!(
+			client.kv1_get(TOKEN_ROLE, "secret", "/app").unwrap()["foo"],
+			"bar"
+		);
+		client.kv1_delete(TOKEN_ROLE, "/secret/", "app").unwrap();
+		futures_lite::future::block_on(client.kv1_delete_async(TOKEN_ROLE, "secret", "app"))
+			.unwrap();
+		assert_eq!(
+			transport.requests(),
+			[
+				"POST secret/app",
+				"GET secret/app",
+				"DELETE secret/app",
+				"DELETE secret/app"
+			]
+		);
+		assert_eq!(
+			transport.body("POST secret/app"),
+			Some(json!({"foo": "baz"}))
+		);
+		assert_eq!(
+			transport
+				.header("DELETE secret/app", "x-vault-token")
+				.as_deref(),
+			Some("s.token")
+		);
+	}
+}
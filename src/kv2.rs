@@ -0,0 +1,432 @@
+use crate::{
+	client::{not_found_as_none, VaultClient},
+	error::{Error, Result},
+};
+
+use http::{header::CONTENT_TYPE, HeaderValue};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Metadata of a version of a kv2 secret
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Kv2Metadata {
+	pub version: u64,
+	pub created_time: String,
+	/// empty if the version is not deleted
+	pub deletion_time: String,
+	pub destroyed: bool,
+	#[serde(default)]
+	pub custom_metadata: Option<Map<String, Value>>,
+}
+
+/// A version of a kv2 secret: only the user data and its metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kv2Secret {
+	/// user data (empty if the version is deleted or destroyed)
+	pub data: Map<String, Value>,
+	pub metadata: Kv2Metadata,
+}
+
+/// kv2 response data as returned by vault
+#[derive(Deserialize)]
+struct Kv2Data {
+	data: Option<Map<String, Value>>,
+	metadata: Kv2Metadata,
+}
+
+impl Kv2Secret {
+	/// extract a kv2 secret from a response of `<mount>/data/<path>`
+	fn from_value(mut value: Value) -> Result<Self> {
+		let kv2: Kv2Data = serde_json::from_value(value["data"].take())
+			.map_err(|e| Error::ParseError { source: e })?;
+		Ok(Self {
+			data: kv2.data.unwrap_or_default(),
+			metadata: kv2.metadata,
+		})
+	}
+}
+
+/// Extract the metadata of the written version from a response of `<mount>/data/<path>`
+fn metadata_from_value(mut value: Value) -> Result<Kv2Metadata> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Body of a kv2 write with an optional check-and-set version
+fn put_body(data: &Map<String, Value>, cas: Option<u64>) -> String {
+	match cas {
+		Some(cas) => json!({"data": data, "options": {"cas": cas}}),
+		None => json!({ "data": data }),
+	}
+	.to_string()
+}
+
+/// Return the path of a kv2 endpoint (data, metadata, delete...) for a secret of a mount
+pub(crate) fn kv2_path(mount: &str, endpoint: &str, path: &str) -> String {
+	format!(
+		"{}/{}/{}",
+		mount.trim_matches('/'),
+		endpoint,
+		path.trim_start_matches('/')
+	)
+}
+
+impl VaultClient {
+	/// Get the latest version of a kv2 secret at path in mount
+	pub fn kv2_get(&self, role: &str, mount: &str, path: &str) -> Result<Kv2Secret> {
+		let request = self.vault_request(
+			"GET",
+			&kv2_path(mount, "data", path),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		Kv2Secret::from_value(self.send(request)?)
+	}
+
+	/// Get asynchronously the latest version of a kv2 secret at path in mount
+	pub async fn kv2_get_async(&self, role: &str, mount: &str, path: &str) -> Result<Kv2Secret> {
+		let request = self.vault_request(
+			"GET",
+			&kv2_path(mount, "data", path),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		Kv2Secret::from_value(self.send_async(request).await?)
+	}
+
+	/// Get the latest version of a kv2 secret at path in mount or None if it doesn't exist
+	pub fn kv2_try_get(&self, role: &str, mount: &str, path: &str) -> Result<Option<Kv2Secret>> {
+		not_found_as_none(self.kv2_get(role, mount, path))
+	}
+
+	/// Get asynchronously the latest version of a kv2 secret or None if it doesn't exist
+	pub async fn kv2_try_get_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+	) -> Result<Option<Kv2Secret>> {
+		not_found_as_none(self.kv2_get_async(role, mount, path).await)
+	}
+
+	/// Get a given version of a kv2 secret at path in mount
+	pub fn kv2_get_version(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		version: u64,
+	) -> Result<Kv2Secret> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}?version={}", kv2_path(mount, "data", path), version),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		Kv2Secret::from_value(self.send(request)?)
+	}
+
+	/// Get asynchronously a given version of a kv2 secret at path in mount
+	pub async fn kv2_get_version_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		version: u64,
+	) -> Result<Kv2Secret> {
+		let request = self.vault_request(
+			"GET",
+			&format!("{}?version={}", kv2_path(mount, "data", path), version),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		Kv2Secret::from_value(self.send_async(request).await?)
+	}
+
+	/// List the keys of the kv2 secrets under path in mount (folders end with a `/`)
+	pub fn kv2_list(&self, role: &str, mount: &str, path: &str) -> Result<Vec<String>> {
+		self.list_secrets(role, &kv2_path(mount, "metadata", path))
+	}
+
+	/// List asynchronously the keys of the kv2 secrets under path in mount
+	pub async fn kv2_list_async(&self, role: &str, mount: &str, path: &str) -> Result<Vec<String>> {
+		self.list_secrets_async(role, &kv2_path(mount, "metadata", path))
+			.await
+	}
+
+	/// Write a new version of a kv2 secret and return its metadata. If cas is set, the write only
+	/// succeeds if the current version of the secret matches (0 means the secret must not exist)
+	pub fn kv2_put(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+		cas: Option<u64>,
+	) -> Result<Kv2Metadata> {
+		let request = self.vault_request(
+			"POST",
+			&kv2_path(mount, "data", path),
+			Some(&self.token(role)?),
+			put_body(data, cas),
+		)?;
+		metadata_from_value(self.send(request)?)
+	}
+
+	/// Write asynchronously a new version of a kv2 secret and return its metadata
+	pub async fn kv2_put_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+		cas: Option<u64>,
+	) -> Result<Kv2Metadata> {
+		let request = self.vault_request(
+			"POST",
+			&kv2_path(mount, "data", path),
+			Some(&self.token_async(role).await?),
+			put_body(data, cas),
+		)?;
+		metadata_from_value(self.send_async(request).await?)
+	}
+
+	/// Merge data into the latest version of a kv2 secret (json merge patch) and return the
+	/// metadata of the new version
+	pub fn kv2_patch(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+		cas: Option<u64>,
+	) -> Result<Kv2Metadata> {
+		let mut request = self.vault_request(
+			"PATCH",
+			&kv2_path(mount, "data", path),
+			Some(&self.token(role)?),
+			put_body(data, cas),
+		)?;
+		request.headers_mut().insert(
+			CONTENT_TYPE,
+			HeaderValue::from_static("application/merge-patch+json"),
+		);
+		metadata_from_value(self.send(request)?)
+	}
+
+	/// Merge asynchronously data into the latest version of a kv2 secret
+	pub async fn kv2_patch_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		data: &Map<String, Value>,
+		cas: Option<u64>,
+	) -> Result<Kv2Metadata> {
+		let mut request = self.vault_request(
+			"PATCH",
+			&kv2_path(mount, "data", path),
+			Some(&self.token_async(role).await?),
+			put_body(data, cas),
+		)?;
+		request.headers_mut().insert(
+			CONTENT_TYPE,
+			HeaderValue::from_static("application/merge-patch+json"),
+		);
+		metadata_from_value(self.send_async(request).await?)
+	}
+
+	/// Soft delete versions of a kv2 secret. An empty list of versions deletes the latest one
+	pub fn kv2_delete(&self, role: &str, mount: &str, path: &str, versions: &[u64]) -> Result<()> {
+		let request = if versions.is_empty() {
+			self.vault_request(
+				"DELETE",
+				&kv2_path(mount, "data", path),
+				Some(&self.token(role)?),
+				String::new(),
+			)?
+		} else {
+			self.vault_request(
+				"POST",
+				&kv2_path(mount, "delete", path),
+				Some(&self.token(role)?),
+				json!({ "versions": versions }).to_string(),
+			)?
+		};
+		self.send(request).map(|_| ())
+	}
+
+	/// Soft delete asynchronously versions of a kv2 secret
+	pub async fn kv2_delete_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		versions: &[u64],
+	) -> Result<()> {
+		let request = if versions.is_empty() {
+			self.vault_request(
+				"DELETE",
+				&kv2_path(mount, "data", path),
+				Some(&self.token_async(role).await?),
+				String::new(),
+			)?
+		} else {
+			self.vault_request(
+				"POST",
+				&kv2_path(mount, "delete", path),
+				Some(&self.token_async(role).await?),
+				json!({ "versions": versions }).to_string(),
+			)?
+		};
+		self.send_async(request).await.map(|_| ())
+	}
+
+	/// Restore soft deleted versions of a kv2 secret
+	pub fn kv2_undelete(&self, role: &str, mount: &str, path: &str, versions: &[u64]) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			&kv2_path(mount, "undelete", path),
+			Some(&self.token(role)?),
+			json!({ "versions": versions }).to_string(),
+		)?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Restore asynchronously soft deleted versions of a kv2 secret
+	pub async fn kv2_undelete_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		versions: &[u64],
+	) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			&kv2_path(mount, "undelete", path),
+			Some(&self.token_async(role).await?),
+			json!({ "versions": versions }).to_string(),
+		)?;
+		self.send_async(request).await.map(|_| ())
+	}
+
+	/// Permanently remove versions of a kv2 secret
+	pub fn kv2_destroy(&self, role: &str, mount: &str, path: &str, versions: &[u64]) -> Result<()> {
+		let request = self.vault_request(
+			"PUT",
+			&kv2_path(mount, "destroy", path),
+			Some(&self.token(role)?),
+			json!({ "versions": versions }).to_string(),
+		)?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Permanently remove asynchronously versions of a kv2 secret
+	pub async fn kv2_destroy_async(
+		&self,
+		role: &str,
+		mount: &str,
+		path: &str,
+		versions: &[u64],
+	) -> Result<()> {
+		let request = self.vault_request(
+			"PUT",
+			&kv2_path(mount, "destroy", path),
+			Some(&self.token_async(role).await?),
+			json!({ "versions": versions }).to_string(),
+		)?;
+		self.send_async(request).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+
+	#[test]
+	fn put_body_with_cas() {
+		let mut data = Map::new();
+		data.insert("key".to_owned(), Value::from("val"));
+		assert_eq!(
+			put_body(&data, Some(0)),
+			r#"{"data":{"key":"val"},"options":{"cas":0}}"#
+		);
+	}
+
+	#[test]
+	fn kv2_data_path() {
+		assert_eq!(kv2_path("secret/", "data", "/app/db"), "secret/data/app/db");
+	}
+
+	#[test]
+	fn kv2_secret_from_response() {
+		let secret = Kv2Secret::from_value(json!({
+			"data": {
+				"data": {"password": "pass"},
+				"metadata": {
+					"created_time": "2018-03-22T02:24:06.945319214Z",
+					"custom_metadata": null,
+					"deletion_time": "",
+					"destroyed": false,
+					"version": 2
+				}
+			}
+		}))
+		.unwrap();
+		assert_eq!(secret.data["password"], "pass");
+		assert_eq!(secret.metadata.version, 2);
+	}
+
+	#[test]
+	fn deleted_kv2_secret_has_no_data() {
+		let secret = Kv2Secret::from_value(json!({
+			"data": {
+				"data": null,
+				"metadata": {
+					"created_time": "2018-03-22T02:24:06.945319214Z",
+					"deletion_time": "2018-03-23T02:24:06.945319214Z",
+					"destroyed": false,
+					"version": 1
+				}
+			}
+		}))
+		.unwrap();
+		assert!(secret.data.is_empty());
+	}
+
+	#[test]
+	fn kv2_requests_target_the_endpoints() {
+		let metadata = json!({"data": {
+			"created_time": "2018-03-22T02:24:06.945319214Z",
+			"deletion_time": "",
+			"destroyed": false,
+			"version": 3
+		}});
+		let transport = Scripted::new();
+		transport
+			.on("PATCH", "secret/data/app", 200, metadata)
+			.on("POST", "secret/delete/app", 204, Value::Null)
+			.on("GET", "secret/data/gone", 404, json!({"errors": []}));
+		let client = transport.client();
+		let mut data = Map::new();
+		data.insert("key".to_owned(), Value::from("val"));
+		let patched = client.kv2_patch(TOKEN_ROLE, "secret", "app", &data, None);
+		assert_eq!(patched.unwrap().version, 3);
+		assert_eq!(
+			transport
+				.header("PATCH secret/data/app", "content-type")
+				.as_deref(),
+			Some("application/merge-patch+json")
+		);
+		client
+			.kv2_delete(TOKEN_ROLE, "secret", "app", &[1, 2])
+			.unwrap();
+		assert_eq!(
+			transport.body("POST secret/delete/app"),
+			Some(json!({"versions": [1, 2]}))
+		);
+		let gone =
+			futures_lite::future::block_on(client.kv2_try_get_async(TOKEN_ROLE, "secret", "gone"));
+		assert!(gone.unwrap().is_none());
+	}
+}
@@ -1,13 +1,91 @@
+use crate::{
+	client::VaultClient,
+	error::Result,
+};
+
+use serde_json::{json, Value};
 use std::time::{SystemTime, Duration};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lease {
 	/// start of the Lease
 	pub time: SystemTime,
 	/// duration of the lease
 	pub lease_duration: Duration,
 	/// renew delay
-	pub renew_delay: Duration
+	pub renew_delay: Duration,
+	/// time before the end of the lease from which it is considered expired
+	pub grace: Duration
+}
+
+/// Return the delay after which a lease of duration dur should be renewed
+pub fn renew_delay(dur: Duration) -> Duration {
+	dur * 2 / 3
+}
+
+/// When leases are renewed. Each lease is renewed after a fraction of its duration, minus a
+/// random part of up to jitter times that delay so that clients started together don't renew
+/// at the same time. The same jitter is applied to secret refresh and retry delays. The grace
+/// period absorbs clock skew with the server by considering leases expired a bit before their end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenewalPolicy {
+	pub fraction: f64,
+	pub jitter: f64,
+	pub grace: Duration
+}
+
+/// Renew at 2/3 of the lease duration without jitter nor grace period
+impl Default for RenewalPolicy {
+	fn default() -> Self {
+		Self {
+			fraction: 2.0 / 3.0,
+			jitter: 0.0,
+			grace: Duration::from_secs(0)
+		}
+	}
+}
+
+impl RenewalPolicy {
+	/// renew after fraction (between 0 and 1) of the lease duration
+	pub fn new(fraction: f64) -> Self {
+		Self {
+			fraction: fraction.clamp(0.0, 1.0),
+			..Default::default()
+		}
+	}
+
+	/// renew up to jitter (between 0 and 1) times the renew delay earlier
+	pub fn jitter(mut self, jitter: f64) -> Self {
+		self.jitter = jitter.clamp(0.0, 1.0);
+		self
+	}
+
+	/// consider leases expired grace before their end
+	pub fn grace(mut self, grace: Duration) -> Self {
+		self.grace = grace;
+		self
+	}
+
+	/// Return delay shortened by a random part of up to jitter times delay
+	pub fn jittered(&self, delay: Duration) -> Duration {
+		delay - delay.mul_f64(self.jitter * fastrand::f64())
+	}
+
+	/// Return the delay after which a lease of duration dur should be renewed
+	pub fn renew_delay(&self, dur: Duration) -> Duration {
+		self.jittered(dur.mul_f64(self.fraction))
+			.min(dur.checked_sub(self.grace).unwrap_or_default())
+	}
+
+	/// Return a lease of duration dur obtained now
+	pub fn lease(&self, dur: Duration) -> Lease {
+		Lease {
+			time: SystemTime::now(),
+			lease_duration: dur,
+			renew_delay: self.renew_delay(dur),
+			grace: self.grace
+		}
+	}
 }
 
 impl Lease {
@@ -15,14 +93,16 @@ impl Lease {
 		Self {
 			time: SystemTime::now(),
 			lease_duration: dur,
-			renew_delay: dur * 2 / 3
+			renew_delay: renew_delay(dur),
+			grace: Duration::from_secs(0)
 		}
 	}
 
 	/// returns true if the lease is still valid
 	pub fn is_valid(&self) -> bool {
 		// TODO: replace with .is_zero() when stable
-		self.lease_duration != Duration::from_secs(0) && SystemTime::now() < self.time + self.lease_duration
+		self.lease_duration != Duration::from_secs(0)
+			&& SystemTime::now() + self.grace < self.time + self.lease_duration
 	}
 
 	/// returns true if the lease is about to expire
@@ -30,4 +110,106 @@ impl Lease {
 		// TODO: replace with .is_zero() when stable
 		self.lease_duration != Duration::from_secs(0) && SystemTime::now() > self.time + self.renew_delay
 	}
+
+	/// returns the time left before the lease needs a renewal
+	pub fn renew_in(&self) -> Duration {
+		(self.time + self.renew_delay)
+			.duration_since(SystemTime::now())
+			.unwrap_or_else(|_| Duration::from_secs(0))
+	}
+}
+
+/// Body of a lease renewal with an optional increment
+fn renew_body(lease_id: &str, increment: Option<Duration>) -> String {
+	match increment {
+		Some(increment) => json!({"lease_id": lease_id, "increment": increment.as_secs()}),
+		None => json!({ "lease_id": lease_id }),
+	}
+	.to_string()
+}
+
+/// Extract the new lease from a renewal response
+fn lease_from_value(value: &Value, policy: &RenewalPolicy) -> Lease {
+	policy.lease(Duration::from_secs(value["lease_duration"].as_u64().unwrap_or(0)))
+}
+
+impl VaultClient {
+	/// Renew the lease lease_id, optionally requesting an increment, and return the new lease
+	pub fn renew_lease(&self, role: &str, lease_id: &str, increment: Option<Duration>) -> Result<Lease> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/leases/renew",
+			Some(&self.token(role)?),
+			renew_body(lease_id, increment),
+		)?;
+		Ok(lease_from_value(&self.send(request)?, &self.renewal))
+	}
+
+	/// Renew asynchronously the lease lease_id and return the new lease
+	pub async fn renew_lease_async(
+		&self,
+		role: &str,
+		lease_id: &str,
+		increment: Option<Duration>,
+	) -> Result<Lease> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/leases/renew",
+			Some(&self.token_async(role).await?),
+			renew_body(lease_id, increment),
+		)?;
+		Ok(lease_from_value(&self.send_async(request).await?, &self.renewal))
+	}
+
+	/// Revoke the lease lease_id
+	pub fn revoke_lease(&self, role: &str, lease_id: &str) -> Result<()> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/leases/revoke",
+			Some(&self.token(role)?),
+			json!({ "lease_id": lease_id }).to_string(),
+		)?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Revoke asynchronously the lease lease_id
+	pub async fn revoke_lease_async(&self, role: &str, lease_id: &str) -> Result<()> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/leases/revoke",
+			Some(&self.token_async(role).await?),
+			json!({ "lease_id": lease_id }).to_string(),
+		)?;
+		self.send_async(request).await.map(|_| ())
+	}
+}
+
+#[test]
+fn renew_in_is_renew_delay_for_fresh_lease() {
+	let lease = Lease::new(Duration::from_secs(300));
+	assert!(lease.renew_in() <= Duration::from_secs(200));
+	assert!(lease.renew_in() > Duration::from_secs(190));
+}
+
+#[test]
+fn renewal_policy_with_jitter_and_grace() {
+	let policy = RenewalPolicy::new(0.5).jitter(0.2).grace(Duration::from_secs(30));
+	for _ in 0..10 {
+		let delay = policy.renew_delay(Duration::from_secs(100));
+		assert!(delay <= Duration::from_secs(50) && delay >= Duration::from_secs(40));
+	}
+	// the renewal happens before the grace period
+	assert_eq!(
+		RenewalPolicy::new(1.0).grace(Duration::from_secs(30)).renew_delay(Duration::from_secs(100)),
+		Duration::from_secs(70)
+	);
+	assert!(!policy.lease(Duration::from_secs(20)).is_valid());
+}
+
+#[test]
+fn renew_body_with_increment() {
+	assert_eq!(
+		renew_body("aws/creds/deploy/abcd", Some(Duration::from_secs(3600))),
+		r#"{"increment":3600,"lease_id":"aws/creds/deploy/abcd"}"#
+	);
 }
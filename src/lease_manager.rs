@@ -0,0 +1,317 @@
+use crate::{
+	client::VaultClient,
+	error::Result,
+	lease::{Lease, RenewalPolicy},
+	secret::TypedSecret,
+};
+
+use std::{
+	collections::HashMap,
+	sync::{
+		mpsc::{self, Receiver, Sender},
+		Arc, Condvar, Mutex, MutexGuard,
+	},
+	thread,
+	time::Duration,
+};
+
+/// Events sent to the subscribers of a LeaseManager
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaseEvent {
+	/// the lease has been renewed for lease_duration
+	Renewed {
+		lease_id: String,
+		lease_duration: Duration,
+	},
+	/// the lease can't be renewed anymore and the secret must be fetched again
+	Expired { lease_id: String, reason: String },
+}
+
+/// State shared between a LeaseManager and its renewal thread
+#[derive(Debug, Default)]
+struct State {
+	leases: HashMap<String, Lease>,
+	subscribers: Vec<Sender<LeaseEvent>>,
+	stopped: bool,
+}
+
+impl State {
+	/// send an event to all subscribers and forget the ones that are gone
+	fn notify(&mut self, event: LeaseEvent) {
+		self.subscribers.retain(|s| s.send(event.clone()).is_ok());
+	}
+
+	/// return the leases to renew now and the time left before the next renewal
+	fn due(&self) -> (Vec<String>, Option<Duration>) {
+		let mut due = Vec::new();
+		let mut next: Option<Duration> = None;
+		for (lease_id, lease) in self.leases.iter() {
+			let renew_in = lease.renew_in();
+			// TODO: replace with .is_zero() when stable
+			if renew_in == Duration::from_secs(0) {
+				due.push(lease_id.to_owned());
+			} else {
+				next = Some(next.map_or(renew_in, |next| next.min(renew_in)));
+			}
+		}
+		(due, next)
+	}
+
+	/// update a lease after a renewal attempt
+	fn renewed(&mut self, lease_id: String, res: Result<Lease>) {
+		// the lease may have been removed during the renewal
+		let previous = match self.leases.get(&lease_id) {
+			Some(lease) => lease.lease_duration,
+			None => return,
+		};
+		match res {
+			// TODO: replace with .is_zero() when stable
+			Ok(lease)
+				if lease.lease_duration >= previous
+					&& lease.lease_duration != Duration::from_secs(0) =>
+			{
+				let lease_duration = lease.lease_duration;
+				self.leases.insert(lease_id.clone(), lease);
+				self.notify(LeaseEvent::Renewed {
+					lease_id,
+					lease_duration,
+				});
+			}
+			// a shorter lease means that the max ttl has been reached
+			Ok(_) => {
+				self.leases.remove(&lease_id);
+				self.notify(LeaseEvent::Expired {
+					lease_id,
+					reason: "lease reached its maximum ttl".to_owned(),
+				});
+			}
+			Err(e) => {
+				self.leases.remove(&lease_id);
+				self.notify(LeaseEvent::Expired {
+					lease_id,
+					reason: e.to_string(),
+				});
+			}
+		}
+	}
+}
+
+/// Owns a set of leases and renews them in a background thread when they reach their renew
+/// delay. Subscribers are notified of renewals and of leases that can't be renewed anymore.
+/// The thread stops when the manager is dropped
+#[derive(Debug)]
+pub struct LeaseManager {
+	state: Arc<(Mutex<State>, Condvar)>,
+	policy: RenewalPolicy,
+}
+
+impl LeaseManager {
+	/// Create a lease manager renewing leases with the token of role
+	pub fn new(client: &Arc<VaultClient>, role: &str) -> Self {
+		let state = Arc::new((Mutex::new(State::default()), Condvar::new()));
+		let policy = client.renewal;
+		let client = Arc::clone(client);
+		let role = role.to_owned();
+		let thread_state = Arc::clone(&state);
+		thread::spawn(move || run(&client, &role, &thread_state));
+		Self { state, policy }
+	}
+
+	fn lock(&self) -> MutexGuard<'_, State> {
+		self.state.0.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Register a lease obtained now for lease_duration
+	pub fn add(&self, lease_id: &str, lease_duration: Duration) {
+		self.lock()
+			.leases
+			.insert(lease_id.to_owned(), self.policy.lease(lease_duration));
+		// wake up the thread as the next deadline may have changed
+		self.state.1.notify_one();
+	}
+
+	/// Register the lease of dynamic credentials (ex: from `database_creds`) when it is
+	/// renewable and return the credentials
+	pub fn manage<T>(&self, creds: TypedSecret<T>) -> TypedSecret<T> {
+		if let Some(lease_id) = creds.lease_id.as_deref().filter(|_| creds.metadata.renewable) {
+			self.add(lease_id, creds.metadata.lease_duration);
+		}
+		creds
+	}
+
+	/// Stop managing a lease. Returns true if the lease was managed
+	pub fn remove(&self, lease_id: &str) -> bool {
+		self.lock().leases.remove(lease_id).is_some()
+	}
+
+	/// Check if a lease is managed
+	pub fn contains(&self, lease_id: &str) -> bool {
+		self.lock().leases.contains_key(lease_id)
+	}
+
+	/// Return a channel receiving the lease events
+	pub fn subscribe(&self) -> Receiver<LeaseEvent> {
+		let (tx, rx) = mpsc::channel();
+		self.lock().subscribers.push(tx);
+		rx
+	}
+}
+
+impl Drop for LeaseManager {
+	fn drop(&mut self) {
+		self.lock().stopped = true;
+		self.state.1.notify_one();
+	}
+}
+
+/// Renewal loop of a LeaseManager
+fn run(client: &VaultClient, role: &str, state: &(Mutex<State>, Condvar)) {
+	let (lock, cvar) = state;
+	let mut guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+	loop {
+		if guard.stopped {
+			return;
+		}
+		let (due, next) = guard.due();
+		if due.is_empty() {
+			// wait for the next deadline or for a change of the leases
+			guard = match next {
+				Some(delay) => {
+					cvar.wait_timeout(guard, delay)
+						.unwrap_or_else(|e| e.into_inner())
+						.0
+				}
+				None => cvar.wait(guard).unwrap_or_else(|e| e.into_inner()),
+			};
+			continue;
+		}
+		// don't hold the state during the renewals
+		drop(guard);
+		let results: Vec<(String, Result<Lease>)> = due
+			.into_iter()
+			.map(|lease_id| {
+				let res = client.renew_lease(role, &lease_id, None);
+				client.notify_lease(role, &lease_id, &res);
+				(lease_id, res)
+			})
+			.collect();
+		guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+		for (lease_id, res) in results {
+			guard.renewed(lease_id, res);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		client::TOKEN_ROLE, error::Error, listener::RenewalListener, secret::Secret,
+		transport::mock::Scripted,
+	};
+	use serde_json::{json, Value};
+
+	#[derive(Debug, Default)]
+	struct Refreshes(Mutex<Vec<String>>);
+
+	impl RenewalListener for Refreshes {
+		fn secret_refreshed(&self, role: &str, path: &str, ttl: Option<Duration>) {
+			self.0
+				.lock()
+				.unwrap()
+				.push(format!("{} {} {:?}", role, path, ttl));
+		}
+	}
+
+	#[test]
+	fn renewals_reach_listeners() {
+		let transport = Scripted::new();
+		transport.on(
+			"PUT",
+			"sys/leases/renew",
+			200,
+			json!({"lease_id": "database/creds/readonly/2f6a614c", "lease_duration": 3600}),
+		);
+		let lease_id = "database/creds/readonly/2f6a614c";
+		let refreshes = Arc::new(Refreshes::default());
+		let builder = transport.builder().listener(refreshes.clone());
+		let client = Arc::new(builder.build().unwrap());
+		let manager = LeaseManager::new(&client, TOKEN_ROLE);
+		let events = manager.subscribe();
+		manager.add(lease_id, Duration::from_secs(0));
+		assert!(matches!(
+			events.recv_timeout(Duration::from_secs(5)),
+			Ok(LeaseEvent::Renewed { .. })
+		));
+		assert_eq!(
+			*refreshes.0.lock().unwrap(),
+			vec![format!("{} {} Some(3600s)", TOKEN_ROLE, lease_id)]
+		);
+	}
+
+	#[test]
+	fn failed_renewal_expires_lease() {
+		let mut state = State::default();
+		let (tx, rx) = mpsc::channel();
+		state.subscribers.push(tx);
+		state
+			.leases
+			.insert("id".to_owned(), Lease::new(Duration::from_secs(60)));
+		state.renewed("id".to_owned(), Err(Error::NotLogged));
+		assert!(state.leases.is_empty());
+		assert!(matches!(rx.try_recv(), Ok(LeaseEvent::Expired { lease_id, .. }) if lease_id == "id"));
+	}
+
+	#[test]
+	fn renewable_creds_are_managed() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"database/creds/readonly",
+			200,
+			json!({
+				"lease_id": "database/creds/readonly/2f6a614c",
+				"lease_duration": 3600,
+				"renewable": true,
+				"data": {"username": "user", "password": "pass"}
+			}),
+		);
+		let client = Arc::new(transport.client());
+		let manager = LeaseManager::new(&client, TOKEN_ROLE);
+		let creds = manager.manage(
+			client
+				.database_creds(TOKEN_ROLE, "database", "readonly")
+				.unwrap(),
+		);
+		assert_eq!(creds.username, "user");
+		assert!(manager.contains("database/creds/readonly/2f6a614c"));
+		let mut secret = Secret::new(json!({}), Some(Duration::from_secs(60)));
+		secret.lease_id = Some("aws/sts/deploy/7cb8df71".to_owned());
+		manager.manage(secret.deserialize::<Value>().unwrap());
+		assert!(!manager.contains("aws/sts/deploy/7cb8df71"));
+	}
+
+	#[test]
+	fn capped_renewal_expires_lease() {
+		let mut state = State::default();
+		state
+			.leases
+			.insert("id".to_owned(), Lease::new(Duration::from_secs(60)));
+		state.renewed("id".to_owned(), Ok(Lease::new(Duration::from_secs(30))));
+		assert!(state.leases.is_empty());
+	}
+
+	#[test]
+	fn due_leases() {
+		let mut state = State::default();
+		state
+			.leases
+			.insert("now".to_owned(), Lease::new(Duration::from_secs(0)));
+		state
+			.leases
+			.insert("later".to_owned(), Lease::new(Duration::from_secs(60)));
+		let (due, next) = state.due();
+		assert_eq!(due, vec!["now".to_owned()]);
+		assert!(next.unwrap() <= Duration::from_secs(40));
+	}
+}
@@ -1,8 +1,79 @@
+pub mod acl;
+pub mod agent;
+pub mod api;
+pub mod audit;
+#[allow(clippy::bind_instead_of_map, clippy::bool_assert_comparison)]
 pub mod auth;
+pub mod aws;
+pub mod aws_secret;
+pub mod azure;
+pub mod azure_secret;
+mod base64;
+pub mod batch;
+pub mod blocking;
+pub mod builder;
+mod cache;
+pub mod cert_watcher;
+#[allow(clippy::bind_instead_of_map, clippy::or_then_unwrap, clippy::redundant_closure)]
 pub mod client;
+pub mod creds;
+pub mod cubbyhole;
+pub mod database;
+mod delay;
+#[cfg(feature = "testing")]
+pub mod dev_vault;
+mod endpoint;
+pub mod env;
 pub mod error;
+pub mod events;
+pub mod exec;
+mod flight;
+pub mod gcp;
+pub mod gcp_secret;
+pub mod identity;
+pub mod intercept;
+#[cfg(feature = "kube")]
+pub mod k8s_sync;
+pub mod kubernetes;
+pub mod kv1;
+pub mod kv2;
 pub mod lease;
+pub mod lease_manager;
+pub mod listener;
+pub mod method;
+pub mod metrics;
+pub mod monitor;
+pub mod mounts;
+pub mod pki;
+pub mod policy;
+pub mod pool;
+pub mod provider;
+pub mod raft;
+pub mod raw;
+mod redact;
+pub mod rekey;
+mod renew;
+pub mod retry;
+pub mod runtime;
+mod scrub;
+#[allow(clippy::bind_instead_of_map, clippy::bool_assert_comparison, clippy::needless_return)]
 pub mod secret;
+mod sha256;
+pub mod sink;
+pub mod spec;
+pub mod ssh;
+pub mod sys;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod token;
+pub mod totp;
+mod trace;
+pub mod transit;
+pub mod transport;
+pub mod watch;
+#[cfg(feature = "isahc")]
+mod websocket;
+pub mod wrapping;
 #[cfg(feature = "nom")]
 mod parser;
 #[cfg(not(feature = "nom"))]
@@ -0,0 +1,107 @@
+use crate::{
+	auth::Auth,
+	client::VaultClient,
+	error::{Error, Result},
+	lease::Lease,
+	secret::Secret,
+};
+
+use std::{fmt, time::Duration};
+
+/// Receiver of the renewals of the client, to reload what depends on a token or a secret
+/// (database pools, tls contexts...) exactly when it changes. All the methods do nothing by
+/// default and are called from the thread (or task) doing the renewal, so they must not block
+pub trait RenewalListener: fmt::Debug + Send + Sync {
+	/// the token of role was renewed with a new ttl (None for tokens without lease)
+	fn token_renewed(&self, _role: &str, _ttl: Option<Duration>) {}
+
+	/// the renewal of the token of role failed
+	fn token_renewal_failed(&self, _role: &str, _error: &Error) {}
+
+	/// the secret at path was fetched again by a watcher with the token of role, or the lease
+	/// of a LeaseManager was renewed (path is then the lease id)
+	fn secret_refreshed(&self, _role: &str, _path: &str, _ttl: Option<Duration>) {}
+
+	/// a watcher failed to fetch again the secret at path, or a LeaseManager failed to renew the
+	/// lease with this id
+	fn secret_refresh_failed(&self, _role: &str, _path: &str, _error: &Error) {}
+}
+
+impl VaultClient {
+	/// Notify the listeners of the result of a token renewal of role
+	pub(crate) fn notify_renewal(&self, role: &str, res: &Result<Auth>) {
+		for listener in self.listeners.iter() {
+			match res {
+				Ok(auth) => listener.token_renewed(role, auth.duration()),
+				Err(e) => listener.token_renewal_failed(role, e),
+			}
+		}
+	}
+
+	/// Notify the listeners of the result of a secret refresh of role at path
+	pub(crate) fn notify_refresh(&self, role: &str, path: &str, res: &Result<Secret>) {
+		for listener in self.listeners.iter() {
+			match res {
+				Ok(secret) => listener.secret_refreshed(role, path, secret.duration()),
+				Err(e) => listener.secret_refresh_failed(role, path, e),
+			}
+		}
+	}
+
+	/// Notify the listeners of the result of the renewal of lease_id with the token of role
+	pub(crate) fn notify_lease(&self, role: &str, lease_id: &str, res: &Result<Lease>) {
+		for listener in self.listeners.iter() {
+			match res {
+				Ok(lease) => listener.secret_refreshed(role, lease_id, Some(lease.lease_duration)),
+				Err(e) => listener.secret_refresh_failed(role, lease_id, e),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{builder::VaultClientBuilder, transport::mock::Scripted};
+	use std::sync::{Arc, Mutex};
+
+	#[derive(Debug, Default)]
+	struct Events(Mutex<Vec<String>>);
+
+	impl RenewalListener for Events {
+		fn token_renewed(&self, role: &str, ttl: Option<Duration>) {
+			self.0
+				.lock()
+				.unwrap()
+				.push(format!("renewed {} {:?}", role, ttl));
+		}
+
+		fn secret_refresh_failed(&self, role: &str, path: &str, _error: &Error) {
+			self.0
+				.lock()
+				.unwrap()
+				.push(format!("failed {} {}", role, path));
+		}
+	}
+
+	#[test]
+	fn listeners_are_notified() {
+		let events = Arc::new(Events::default());
+		let client = VaultClientBuilder::new("http://localhost:8200/v1")
+			.token("s.token")
+			.listener(events.clone())
+			.transport(Scripted::new())
+			.build()
+			.unwrap();
+		client.notify_renewal(
+			"app",
+			&Ok(Auth::new("s.token", Some(Duration::from_secs(60)))),
+		);
+		client.notify_renewal("app", &Err(Error::NotLogged));
+		client.notify_refresh("app", "secret/app", &Err(Error::NotLogged));
+		assert_eq!(
+			*events.0.lock().unwrap(),
+			vec!["renewed app Some(60s)", "failed app secret/app"]
+		);
+	}
+}
@@ -0,0 +1,551 @@
+use crate::{
+	api::BoxFuture,
+	error::{Error, Result},
+	redact::{Redacted, RedactedValue},
+};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::{
+	collections::HashMap,
+	fmt, fs,
+	path::{Path, PathBuf},
+};
+use zeroize::Zeroize;
+
+/// Return the login path of an auth method given its mount point
+pub fn login_path(mount: &str) -> String {
+	format!("/auth/{}/login", mount.trim_matches('/').trim_start_matches("auth/"))
+}
+
+/// Login body of the auth methods using a jwt (kubernetes, jwt, gcp...)
+#[derive(Serialize)]
+pub(crate) struct JwtLogin<'a> {
+	pub(crate) role: &'a str,
+	pub(crate) jwt: &'a str,
+}
+
+impl<'a> JwtLogin<'a> {
+	pub(crate) fn to_value(&self) -> Result<Value> {
+		serde_json::to_value(self).map_err(|e| Error::ParseError { source: e })
+	}
+}
+
+/// What is needed to obtain a token for a role
+pub enum Login {
+	/// post a json body to a login path (relative to the vault url)
+	Request { path: String, body: Value },
+	/// use a token directly without contacting vault
+	Token(String),
+}
+
+/// The body and the token are redacted
+impl fmt::Debug for Login {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Login::Request { path, body } => f
+				.debug_struct("Request")
+				.field("path", path)
+				.field("body", &RedactedValue(body))
+				.finish(),
+			Login::Token(token) => f.debug_tuple("Token").field(&Redacted(token)).finish(),
+		}
+	}
+}
+
+/// An auth method knows how to log in to vault for a given role
+pub trait AuthMethod: fmt::Debug + Send + Sync {
+	/// return the login to perform for the role
+	fn login(&self, role: &str) -> Result<Login>;
+
+	/// return asynchronously the login to perform for the role. It calls `login` by default, so
+	/// methods that send requests to build the login (ex: to obtain credentials) must override
+	/// it not to block the executor
+	fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Login>> {
+		Box::pin(async move { self.login(role) })
+	}
+}
+
+/// Where the kubernetes auth method gets its jwt from
+enum JwtSource {
+	String(String),
+	/// file read again at each login as projected service account tokens are rotated
+	Path(PathBuf),
+}
+
+impl fmt::Debug for JwtSource {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			JwtSource::String(jwt) => f.debug_tuple("String").field(&Redacted(jwt)).finish(),
+			JwtSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+		}
+	}
+}
+
+/// Scrub the jwt from memory
+impl Drop for JwtSource {
+	fn drop(&mut self) {
+		if let JwtSource::String(jwt) = self {
+			jwt.zeroize();
+		}
+	}
+}
+
+impl JwtSource {
+	fn read(&self) -> Result<String> {
+		match self {
+			JwtSource::String(jwt) => Ok(jwt.clone()),
+			JwtSource::Path(path) => Ok(fs::read_to_string(path)?.trim().to_owned()),
+		}
+	}
+}
+
+/// Kubernetes auth method using a service account jwt. Roles can use their own jwt (a projected
+/// token with a specific audience for instance) instead of the default one
+#[derive(Debug)]
+pub struct KubernetesAuth {
+	login_path: String,
+	jwt: JwtSource,
+	roles: HashMap<String, JwtSource>,
+}
+
+impl KubernetesAuth {
+	/// create a kubernetes auth method given its mount point and a jwt token
+	pub fn new(mount: &str, jwt: &str) -> Self {
+		Self::with_login_path(&login_path(mount), jwt)
+	}
+
+	/// create a kubernetes auth method given a full login path and a jwt token
+	pub fn with_login_path(login_path: &str, jwt: &str) -> Self {
+		Self {
+			login_path: login_path.to_owned(),
+			jwt: JwtSource::String(jwt.to_owned()),
+			roles: HashMap::new(),
+		}
+	}
+
+	/// create a kubernetes auth method given a full login path and the path of a file containing
+	/// the jwt. The file is read at each login so that rotated tokens are picked up
+	pub fn with_token_path(login_path: &str, token_path: impl Into<PathBuf>) -> Self {
+		Self {
+			login_path: login_path.to_owned(),
+			jwt: JwtSource::Path(token_path.into()),
+			roles: HashMap::new(),
+		}
+	}
+
+	/// log in role with the jwt read from the file at token_path (at each login) instead of the
+	/// default jwt
+	pub fn role_token_path(mut self, role: &str, token_path: impl Into<PathBuf>) -> Self {
+		self.roles
+			.insert(role.to_owned(), JwtSource::Path(token_path.into()));
+		self
+	}
+
+	/// log in role with the given jwt instead of the default one
+	pub fn role_token(mut self, role: &str, jwt: &str) -> Self {
+		self.roles
+			.insert(role.to_owned(), JwtSource::String(jwt.to_owned()));
+		self
+	}
+}
+
+impl AuthMethod for KubernetesAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		let mut jwt = self.roles.get(role).unwrap_or(&self.jwt).read()?;
+		let body = JwtLogin { role, jwt: &jwt }.to_value();
+		jwt.zeroize();
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: body?,
+		})
+	}
+}
+
+/// Generic JWT/OIDC auth method using a jwt issued by an external identity provider
+/// (workload identity tokens for instance)
+pub struct JwtAuth {
+	login_path: String,
+	jwt: String,
+}
+
+/// The secrets are not shown
+impl fmt::Debug for JwtAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("JwtAuth")
+			.field("login_path", &self.login_path)
+			.finish()
+	}
+}
+
+/// Scrub the jwt from memory
+impl Drop for JwtAuth {
+	fn drop(&mut self) {
+		self.jwt.zeroize();
+	}
+}
+
+impl JwtAuth {
+	/// create a jwt auth method given its mount point and a jwt token
+	pub fn new(mount: &str, jwt: &str) -> Self {
+		Self {
+			login_path: login_path(mount),
+			jwt: jwt.to_owned(),
+		}
+	}
+}
+
+impl AuthMethod for JwtAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: JwtLogin {
+				role,
+				jwt: &self.jwt,
+			}
+			.to_value()?,
+		})
+	}
+}
+
+/// AppRole auth method. The role given at login is only used as a cache key
+pub struct AppRoleAuth {
+	login_path: String,
+	role_id: String,
+	secret_id: String,
+}
+
+/// The secrets are not shown
+impl fmt::Debug for AppRoleAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AppRoleAuth")
+			.field("login_path", &self.login_path)
+			.field("role_id", &self.role_id)
+			.finish()
+	}
+}
+
+/// Scrub the secret id from memory
+impl Drop for AppRoleAuth {
+	fn drop(&mut self) {
+		self.secret_id.zeroize();
+	}
+}
+
+impl AppRoleAuth {
+	/// create an approle auth method mounted on `auth/approle/`
+	pub fn new(role_id: &str, secret_id: &str) -> Self {
+		Self::with_mount("approle", role_id, secret_id)
+	}
+
+	/// create an approle auth method given its mount point
+	pub fn with_mount(mount: &str, role_id: &str, secret_id: &str) -> Self {
+		Self {
+			login_path: login_path(mount),
+			role_id: role_id.to_owned(),
+			secret_id: secret_id.to_owned(),
+		}
+	}
+}
+
+impl AuthMethod for AppRoleAuth {
+	fn login(&self, _role: &str) -> Result<Login> {
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: json!({"role_id": &self.role_id, "secret_id": &self.secret_id}),
+		})
+	}
+}
+
+/// TLS certificate auth method. The client certificate must be configured on the connection
+/// with `VaultClientBuilder::client_certificate`. The role given at login is the name of the
+/// certificate role to authenticate against (empty to let vault try all of them)
+#[derive(Debug)]
+pub struct CertAuth {
+	login_path: String,
+}
+
+impl CertAuth {
+	/// create a cert auth method mounted on `auth/cert/`
+	pub fn new() -> Self {
+		Self::with_mount("cert")
+	}
+
+	/// create a cert auth method given its mount point
+	pub fn with_mount(mount: &str) -> Self {
+		Self {
+			login_path: login_path(mount),
+		}
+	}
+}
+
+impl Default for CertAuth {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl AuthMethod for CertAuth {
+	fn login(&self, role: &str) -> Result<Login> {
+		let body = if role.is_empty() {
+			json!({})
+		} else {
+			json!({ "name": role })
+		};
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body,
+		})
+	}
+}
+
+/// Username and password auth method (`userpass` or `ldap` backends). The role given at login
+/// is only used as a cache key
+pub struct PasswordAuth {
+	login_path: String,
+	password: String,
+}
+
+/// The secrets are not shown
+impl fmt::Debug for PasswordAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PasswordAuth")
+			.field("login_path", &self.login_path)
+			.finish()
+	}
+}
+
+/// Scrub the password from memory
+impl Drop for PasswordAuth {
+	fn drop(&mut self) {
+		self.password.zeroize();
+	}
+}
+
+impl PasswordAuth {
+	/// create a password auth method given its mount point, a username and a password
+	pub fn new(mount: &str, username: &str, password: &str) -> Self {
+		Self {
+			login_path: format!("{}/{}", login_path(mount), username),
+			password: password.to_owned(),
+		}
+	}
+}
+
+impl AuthMethod for PasswordAuth {
+	fn login(&self, _role: &str) -> Result<Login> {
+		Ok(Login::Request {
+			path: self.login_path.clone(),
+			body: json!({ "password": &self.password }),
+		})
+	}
+}
+
+/// Use a static token for every role
+pub struct TokenAuth {
+	token: String,
+}
+
+/// The secrets are not shown
+impl fmt::Debug for TokenAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TokenAuth").finish()
+	}
+}
+
+/// Scrub the token from memory
+impl Drop for TokenAuth {
+	fn drop(&mut self) {
+		self.token.zeroize();
+	}
+}
+
+impl TokenAuth {
+	pub fn new(token: &str) -> Self {
+		Self {
+			token: token.to_owned(),
+		}
+	}
+}
+
+impl AuthMethod for TokenAuth {
+	fn login(&self, _role: &str) -> Result<Login> {
+		Ok(Login::Token(self.token.clone()))
+	}
+}
+
+/// Use for every role the token written by the file sink of a Vault Agent auto-auth. The file is
+/// read at each login, so that a new login picks up the token renewed or re-issued by the agent
+#[derive(Debug, Clone)]
+pub struct AgentSinkAuth {
+	path: PathBuf,
+}
+
+impl AgentSinkAuth {
+	pub fn new(path: &str) -> Self {
+		Self {
+			path: PathBuf::from(path),
+		}
+	}
+}
+
+/// Read the token written by the file sink of a Vault Agent at path
+pub(crate) fn read_token(path: &Path) -> Result<String> {
+	let mut content = fs::read_to_string(path)?;
+	let token = content.trim().to_owned();
+	content.zeroize();
+	if token.is_empty() {
+		// the agent has not authenticated yet
+		return Err(Error::NotLogged);
+	}
+	Ok(token)
+}
+
+impl AuthMethod for AgentSinkAuth {
+	fn login(&self, _role: &str) -> Result<Login> {
+		read_token(&self.path).map(Login::Token)
+	}
+}
+
+/// Add extra parameters to the login body of an auth method (ex: `nonce` or `mfa` parameters).
+/// The parameters override the ones of the auth method with the same name
+pub struct WithParams<M> {
+	method: M,
+	params: Map<String, Value>,
+}
+
+/// Only the names of the parameters are shown
+impl<M: fmt::Debug> fmt::Debug for WithParams<M> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("WithParams")
+			.field("method", &self.method)
+			.field("params", &self.params.keys().collect::<Vec<_>>())
+			.finish()
+	}
+}
+
+impl<M: AuthMethod> WithParams<M> {
+	pub fn new(method: M, params: Map<String, Value>) -> Self {
+		Self { method, params }
+	}
+}
+
+impl<M: AuthMethod> AuthMethod for WithParams<M> {
+	fn login(&self, role: &str) -> Result<Login> {
+		match self.method.login(role)? {
+			Login::Request {
+				path,
+				body: Value::Object(mut body),
+			} => {
+				body.extend(self.params.clone());
+				Ok(Login::Request {
+					path,
+					body: Value::Object(body),
+				})
+			}
+			login => Ok(login),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn login_path_from_mount() {
+		assert_eq!(login_path("kubernetes"), "/auth/kubernetes/login");
+		assert_eq!(login_path("auth/k8s-prod/"), "/auth/k8s-prod/login");
+		assert_eq!(login_path("/k8s-prod"), "/auth/k8s-prod/login");
+	}
+
+	#[test]
+	fn kubernetes_token_file_is_read_at_each_login() {
+		let path = std::env::temp_dir().join(format!("vault-jwt-token-{}", std::process::id()));
+		let method = KubernetesAuth::with_token_path("/auth/kubernetes/login", &path);
+		for jwt in ["first", "second"].iter() {
+			fs::write(&path, format!("{}\n", jwt)).unwrap();
+			assert!(
+				matches!(method.login("app").unwrap(), Login::Request { body, .. } if body["jwt"] == *jwt)
+			);
+		}
+		fs::remove_file(&path).unwrap();
+		assert!(method.login("app").is_err());
+	}
+
+	#[test]
+	fn kubernetes_role_token() {
+		let method = KubernetesAuth::new("kubernetes", "default").role_token("app", "audience");
+		assert!(
+			matches!(method.login("app").unwrap(), Login::Request { body, .. } if body["jwt"] == "audience")
+		);
+		assert!(
+			matches!(method.login("other").unwrap(), Login::Request { body, .. } if body["jwt"] == "default")
+		);
+	}
+
+	#[test]
+	fn jwt_auth_body() {
+		let method = JwtAuth::new("jwt", "eyJ");
+		assert!(
+			matches!(method.login("app").unwrap(), Login::Request { path, body } if path == "/auth/jwt/login" && body == json!({"role": "app", "jwt": "eyJ"}))
+		);
+	}
+
+	#[test]
+	fn cert_auth_body() {
+		let method = CertAuth::new();
+		assert!(
+			matches!(method.login("web").unwrap(), Login::Request { path, body } if path == "/auth/cert/login" && body == json!({"name": "web"}))
+		);
+		assert!(
+			matches!(method.login("").unwrap(), Login::Request { body, .. } if body == json!({}))
+		);
+	}
+
+	#[test]
+	fn password_auth_path() {
+		let method = PasswordAuth::new("ldap", "jdoe", "pass");
+		assert!(
+			matches!(method.login("jdoe").unwrap(), Login::Request { path, body } if path == "/auth/ldap/login/jdoe" && body == json!({"password": "pass"}))
+		);
+	}
+
+	#[test]
+	fn jwt_login_escapes_role() {
+		let method = JwtAuth::new("jwt", "eyJ");
+		let body = match method.login("app\"role").unwrap() {
+			Login::Request { body, .. } => body.to_string(),
+			Login::Token(_) => unreachable!(),
+		};
+		assert_eq!(body, r#"{"jwt":"eyJ","role":"app\"role"}"#);
+	}
+
+	#[test]
+	fn extra_login_params() {
+		let mut params = Map::new();
+		params.insert("nonce".to_owned(), Value::from("5defbf9e"));
+		let method = WithParams::new(KubernetesAuth::new("kubernetes", "eyJ"), params);
+		assert!(
+			matches!(method.login("app").unwrap(), Login::Request { body, .. } if body == json!({"role": "app", "jwt": "eyJ", "nonce": "5defbf9e"}))
+		);
+	}
+
+	#[test]
+	fn agent_sink_token() {
+		let path = std::env::temp_dir().join(format!("vault-jwt-sink-{}", std::process::id()));
+		let method = AgentSinkAuth::new(path.to_str().unwrap());
+		fs::write(&path, "").unwrap();
+		assert!(matches!(method.login("any"), Err(Error::NotLogged)));
+		fs::write(&path, "hvs.agent\n").unwrap();
+		assert!(matches!(method.login("any").unwrap(), Login::Token(t) if t == "hvs.agent"));
+		fs::remove_file(&path).unwrap();
+		assert!(matches!(method.login("any"), Err(Error::TokenError { .. })));
+	}
+
+	#[test]
+	fn token_auth_needs_no_request() {
+		let method = TokenAuth::new("s.token");
+		assert!(matches!(method.login("any").unwrap(), Login::Token(t) if t == "s.token"));
+	}
+}
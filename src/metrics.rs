@@ -0,0 +1,261 @@
+use crate::{client::VaultClient, error::Result};
+
+use http::{Method, StatusCode};
+use std::{fmt, time::Instant};
+
+/// Counter of the logins, labelled by role and result (`ok` or `error`)
+pub const LOGINS: &str = "vault_logins_total";
+/// Counter of the token renewals, labelled by role and result
+pub const RENEWALS: &str = "vault_token_renewals_total";
+/// Counter of the secret fetches with `get_secret`, labelled by role, cache (`hit` or `miss`) and
+/// result
+pub const SECRET_FETCHES: &str = "vault_secret_fetches_total";
+/// Counter of the failed requests, labelled by status (`error` when vault was unreachable)
+pub const ERRORS: &str = "vault_errors_total";
+/// Histogram of the requests duration in seconds, labelled by method and status
+pub const REQUEST_DURATION: &str = "vault_request_duration_seconds";
+
+/// Receiver of the measures of the client operations, to bridge them to a metrics registry (a
+/// prometheus registry...). The `metrics` feature provides `MetricsFacade` for the `metrics` facade
+pub trait MetricsRecorder: fmt::Debug + Send + Sync {
+	/// increment by one the counter name with labels
+	fn increment(&self, name: &'static str, labels: &[(&'static str, &str)]);
+
+	/// record value in the histogram name with labels
+	fn record(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+}
+
+/// Recorder emitting the measures through the `metrics` facade, to the recorder installed by the
+/// application (a prometheus exporter...). It is the default recorder with the `metrics` feature
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl MetricsFacade {
+	/// labels of the facade (its label values must be owned)
+	fn labels(labels: &[(&'static str, &str)]) -> Vec<metrics::Label> {
+		labels
+			.iter()
+			.map(|(key, value)| metrics::Label::new(*key, value.to_string()))
+			.collect()
+	}
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsFacade {
+	fn increment(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+		metrics::counter!(name, Self::labels(labels)).increment(1);
+	}
+
+	fn record(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+		metrics::histogram!(name, Self::labels(labels)).record(value);
+	}
+}
+
+/// label of a result
+fn result_label<T>(res: &Result<T>) -> &'static str {
+	if res.is_ok() {
+		"ok"
+	} else {
+		"error"
+	}
+}
+
+impl VaultClient {
+	/// Count an operation on role by result
+	pub(crate) fn count_result<T>(&self, name: &'static str, role: &str, res: &Result<T>) {
+		if let Some(ref metrics) = self.metrics {
+			metrics.increment(name, &[("role", role), ("result", result_label(res))]);
+		}
+	}
+
+	/// Count a secret fetch of role served or not from the cache
+	pub(crate) fn count_fetch<T>(&self, role: &str, cached: bool, res: &Result<T>) {
+		if let Some(ref metrics) = self.metrics {
+			let cache = if cached { "hit" } else { "miss" };
+			metrics.increment(
+				SECRET_FETCHES,
+				&[
+					("role", role),
+					("cache", cache),
+					("result", result_label(res)),
+				],
+			);
+		}
+	}
+
+	/// Record the duration of a request started at start, and count it as an error if it failed
+	/// (status is None when vault was unreachable)
+	pub(crate) fn observe(&self, method: &Method, start: Instant, status: Option<StatusCode>) {
+		if let Some(ref metrics) = self.metrics {
+			let status_label = status.as_ref().map_or("error", StatusCode::as_str);
+			metrics.record(
+				REQUEST_DURATION,
+				&[("method", method.as_str()), ("status", status_label)],
+				start.elapsed().as_secs_f64(),
+			);
+			if !status.is_some_and(|status| status.is_success() || status.is_redirection()) {
+				metrics.increment(ERRORS, &[("status", status_label)]);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		builder::VaultClientBuilder, client::TOKEN_ROLE, error::Error, transport::mock::Scripted,
+	};
+	use serde_json::json;
+	use std::sync::{Arc, Mutex};
+
+	#[derive(Debug, Default)]
+	struct Recorder(Mutex<Vec<String>>);
+
+	impl MetricsRecorder for Recorder {
+		fn increment(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+			self.0
+				.lock()
+				.unwrap()
+				.push(format!("{} {:?}", name, labels));
+		}
+
+		fn record(&self, name: &'static str, labels: &[(&'static str, &str)], _value: f64) {
+			self.0
+				.lock()
+				.unwrap()
+				.push(format!("{} {:?}", name, labels));
+		}
+	}
+
+	#[test]
+	fn client_operations_are_recorded() {
+		let recorder = Arc::new(Recorder::default());
+		let client = VaultClientBuilder::new("http://localhost:8200/v1")
+			.token("s.token")
+			.metrics(recorder.clone())
+			.transport(Scripted::new())
+			.build()
+			.unwrap();
+		client.count_result(LOGINS, "app", &Err::<(), _>(Error::NotLogged));
+		client.count_fetch("app", true, &Ok::<_, Error>(()));
+		client.observe(&Method::GET, Instant::now(), Some(StatusCode::OK));
+		client.observe(&Method::PUT, Instant::now(), Some(StatusCode::FORBIDDEN));
+		assert_eq!(
+			*recorder.0.lock().unwrap(),
+			vec![
+				r#"vault_logins_total [("role", "app"), ("result", "error")]"#,
+				r#"vault_secret_fetches_total [("role", "app"), ("cache", "hit"), ("result", "ok")]"#,
+				r#"vault_request_duration_seconds [("method", "GET"), ("status", "200")]"#,
+				r#"vault_request_duration_seconds [("method", "PUT"), ("status", "403")]"#,
+				r#"vault_errors_total [("status", "403")]"#,
+			]
+		);
+	}
+
+	#[test]
+	fn uncached_fetches_are_counted() {
+		let recorder = Arc::new(Recorder::default());
+		let transport = Scripted::new();
+		transport.on("GET", "secret/app", 200, json!({"data": {"foo": "bar"}}));
+		let client = transport
+			.builder()
+			.metrics(recorder.clone())
+			.build()
+			.unwrap();
+		client
+			.get_secret_with_headers(TOKEN_ROLE, "GET", "secret/app", None, &[])
+			.unwrap();
+		futures_lite::future::block_on(client.get_secret_in_namespace_async(
+			TOKEN_ROLE,
+			"team",
+			"GET",
+			"secret/app",
+			None,
+		))
+		.unwrap();
+		let fetches = recorder
+			.0
+			.lock()
+			.unwrap()
+			.iter()
+			.filter(|metric| metric.starts_with(SECRET_FETCHES))
+			.count();
+		assert_eq!(fetches, 2);
+	}
+
+	/// Facade recorder keeping the keys of the counters and histograms registered
+	#[cfg(feature = "metrics")]
+	#[derive(Debug, Default)]
+	struct Keys(Mutex<Vec<String>>);
+
+	#[cfg(feature = "metrics")]
+	impl metrics::Recorder for Keys {
+		fn describe_counter(
+			&self,
+			_: metrics::KeyName,
+			_: Option<metrics::Unit>,
+			_: metrics::SharedString,
+		) {
+		}
+
+		fn describe_gauge(
+			&self,
+			_: metrics::KeyName,
+			_: Option<metrics::Unit>,
+			_: metrics::SharedString,
+		) {
+		}
+
+		fn describe_histogram(
+			&self,
+			_: metrics::KeyName,
+			_: Option<metrics::Unit>,
+			_: metrics::SharedString,
+		) {
+		}
+
+		fn register_counter(
+			&self,
+			key: &metrics::Key,
+			_: &metrics::Metadata<'_>,
+		) -> metrics::Counter {
+			self.0.lock().unwrap().push(key.to_string());
+			metrics::Counter::noop()
+		}
+
+		fn register_gauge(&self, _: &metrics::Key, _: &metrics::Metadata<'_>) -> metrics::Gauge {
+			metrics::Gauge::noop()
+		}
+
+		fn register_histogram(
+			&self,
+			key: &metrics::Key,
+			_: &metrics::Metadata<'_>,
+		) -> metrics::Histogram {
+			self.0.lock().unwrap().push(key.to_string());
+			metrics::Histogram::noop()
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "metrics")]
+	fn client_operations_reach_the_facade() {
+		let keys = Keys::default();
+		let client = Scripted::new().client();
+		metrics::with_local_recorder(&keys, || {
+			client.count_result(RENEWALS, "app", &Ok::<_, Error>(()));
+			client.observe(&Method::GET, Instant::now(), None);
+		});
+		assert_eq!(
+			*keys.0.lock().unwrap(),
+			vec![
+				"Key(vault_token_renewals_total, [role = app, result = ok])",
+				"Key(vault_request_duration_seconds, [method = GET, status = error])",
+				"Key(vault_errors_total, [status = error])",
+			]
+		);
+	}
+}
@@ -0,0 +1,85 @@
+use crate::{
+	client::VaultClient,
+	error::Result,
+	transport::{AsyncBody, Body},
+};
+
+use futures_lite::{
+	io::{AsyncBufReadExt, BufReader as AsyncBufReader, Lines as AsyncLines},
+	Stream,
+};
+use std::{
+	io::{BufRead, BufReader, Lines},
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+/// Return the path of the monitor endpoint for log_level (`trace`, `debug`, `info`, `warn` or
+/// `error`)
+fn monitor_path(log_level: &str) -> String {
+	format!("sys/monitor?log_level={}&log_format=standard", log_level)
+}
+
+/// Blocking iterator over the log lines of a vault server. It ends when the server closes the
+/// connection
+pub struct LogLines(Lines<BufReader<Body>>);
+
+impl Iterator for LogLines {
+	type Item = Result<String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.0.next().map(|line| line.map_err(Into::into))
+	}
+}
+
+/// Stream of the log lines of a vault server. It ends when the server closes the connection
+pub struct LogStream(AsyncLines<AsyncBufReader<AsyncBody>>);
+
+impl Stream for LogStream {
+	type Item = Result<String>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		Pin::new(&mut self.0)
+			.poll_next(cx)
+			.map(|line| line.map(|line| line.map_err(Into::into)))
+	}
+}
+
+impl VaultClient {
+	/// Follow the logs of the vault server at log_level (`trace`, `debug`, `info`, `warn` or
+	/// `error`) with the token of role
+	pub fn monitor(&self, role: &str, log_level: &str) -> Result<LogLines> {
+		let res = self.request_stream(role, "GET", &monitor_path(log_level), Body::empty())?;
+		Ok(LogLines(BufReader::new(res.into_body()).lines()))
+	}
+
+	/// Follow asynchronously the logs of the vault server at log_level with the token of role
+	pub async fn monitor_async(&self, role: &str, log_level: &str) -> Result<LogStream> {
+		let res = self
+			.request_stream_async(role, "GET", &monitor_path(log_level), AsyncBody::empty())
+			.await?;
+		Ok(LogStream(AsyncBufReader::new(res.into_body()).lines()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use futures_lite::{future, StreamExt};
+
+	#[test]
+	fn log_lines_from_body() {
+		let body = "2021-10-14T10:43:01.954Z [INFO]  core: vault is unsealed\n2021-10-14T10:43:02.001Z [DEBUG] core: starting\n";
+		let lines: Vec<String> = LogLines(BufReader::new(Body::from(body)).lines())
+			.map(|line| line.unwrap())
+			.collect();
+		assert_eq!(lines.len(), 2);
+		let mut stream = LogStream(AsyncBufReader::new(AsyncBody::from(body)).lines());
+		let first = future::block_on(stream.next()).unwrap().unwrap();
+		assert!(first.ends_with("vault is unsealed"));
+		assert_eq!(
+			monitor_path("debug"),
+			"sys/monitor?log_level=debug&log_format=standard"
+		);
+	}
+}
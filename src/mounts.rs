@@ -0,0 +1,298 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Configuration of a secrets engine or an auth method when it is enabled or tuned. Unset values are left to
+/// their default (or unchanged when tuning)
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MountConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// default lease ttl (ex: `768h`)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub default_lease_ttl: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_lease_ttl: Option<String>,
+	/// `hidden` or `unauth`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub listing_visibility: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub audit_non_hmac_request_keys: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub audit_non_hmac_response_keys: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub passthrough_request_headers: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub allowed_response_headers: Option<Vec<String>>,
+	/// engine specific options (ex: `version` of the kv engine)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub options: Option<HashMap<String, String>>,
+}
+
+/// A secrets engine or an auth method as returned by `sys/mounts` or `sys/auth`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MountInfo {
+	#[serde(rename = "type")]
+	pub mount_type: String,
+	#[serde(default)]
+	pub description: String,
+	#[serde(default)]
+	pub accessor: String,
+	#[serde(default)]
+	pub local: bool,
+	#[serde(default)]
+	pub seal_wrap: bool,
+	#[serde(default)]
+	pub config: MountTuning,
+	#[serde(default)]
+	pub options: Option<HashMap<String, String>>,
+}
+
+/// Lease settings of a secrets engine in seconds (zero means the system default)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MountTuning {
+	#[serde(default)]
+	pub default_lease_ttl: u64,
+	#[serde(default)]
+	pub max_lease_ttl: u64,
+	#[serde(default)]
+	pub force_no_cache: bool,
+}
+
+impl MountInfo {
+	/// version of the engine from its options (ex: `2` for kv v2)
+	pub fn version(&self) -> Option<&str> {
+		self.options
+			.as_ref()
+			.and_then(|options| options.get("version"))
+			.map(|s| s.as_str())
+	}
+}
+
+/// Body of an engine activation. Description and options are at the top level, the other
+/// settings in config
+fn enable_body(engine_type: &str, config: &MountConfig) -> Value {
+	let mut config = json!(config);
+	let mut body = json!({ "type": engine_type });
+	for key in ["description", "options"].iter() {
+		if let Some(value) = config.as_object_mut().and_then(|c| c.remove(*key)) {
+			body[*key] = value;
+		}
+	}
+	body["config"] = config;
+	body
+}
+
+/// Extract the mounts from a response of `sys/mounts`
+fn mounts_from_value(mut value: Value) -> Result<HashMap<String, MountInfo>> {
+	match value["data"].take() {
+		Value::Null => Err(Error::MountsNotFound),
+		data => serde_json::from_value(data).map_err(|e| Error::ParseError { source: e }),
+	}
+}
+
+/// Return the path of a mount endpoint
+fn mount_path(path: &str) -> String {
+	format!("sys/mounts/{}", path.trim_matches('/'))
+}
+
+/// Return the path of an auth method endpoint
+fn auth_path(path: &str) -> String {
+	format!("sys/auth/{}", path.trim_matches('/'))
+}
+
+impl VaultClient {
+	/// Return the secrets engines by mount point (with a trailing slash)
+	pub fn list_mounts(&self, role: &str) -> Result<HashMap<String, MountInfo>> {
+		mounts_from_value(self.request(role, "GET", "sys/mounts", None)?)
+	}
+
+	/// Return asynchronously the secrets engines by mount point
+	pub async fn list_mounts_async(&self, role: &str) -> Result<HashMap<String, MountInfo>> {
+		mounts_from_value(self.request_async(role, "GET", "sys/mounts", None).await?)
+	}
+
+	/// Enable a secrets engine of engine_type (ex: `kv`, `pki`) at path
+	pub fn enable_secret_engine(
+		&self,
+		role: &str,
+		path: &str,
+		engine_type: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let body = enable_body(engine_type, config);
+		self.request(role, "POST", &mount_path(path), Some(body))
+			.map(|_| ())
+	}
+
+	/// Enable asynchronously a secrets engine of engine_type at path
+	pub async fn enable_secret_engine_async(
+		&self,
+		role: &str,
+		path: &str,
+		engine_type: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let body = enable_body(engine_type, config);
+		self.request_async(role, "POST", &mount_path(path), Some(body))
+			.await
+			.map(|_| ())
+	}
+
+	/// Disable the secrets engine at path, revoking all its secrets
+	pub fn disable_secret_engine(&self, role: &str, path: &str) -> Result<()> {
+		self.request(role, "DELETE", &mount_path(path), None)
+			.map(|_| ())
+	}
+
+	/// Disable asynchronously the secrets engine at path
+	pub async fn disable_secret_engine_async(&self, role: &str, path: &str) -> Result<()> {
+		self.request_async(role, "DELETE", &mount_path(path), None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Change the configuration of the secrets engine at path
+	pub fn tune_mount(&self, role: &str, path: &str, config: &MountConfig) -> Result<()> {
+		let path = format!("{}/tune", mount_path(path));
+		self.request(role, "POST", &path, Some(json!(config)))
+			.map(|_| ())
+	}
+
+	/// Change asynchronously the configuration of the secrets engine at path
+	pub async fn tune_mount_async(
+		&self,
+		role: &str,
+		path: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let path = format!("{}/tune", mount_path(path));
+		self.request_async(role, "POST", &path, Some(json!(config)))
+			.await
+			.map(|_| ())
+	}
+
+	/// Return the auth methods by mount point (with a trailing slash)
+	pub fn list_auth_methods(&self, role: &str) -> Result<HashMap<String, MountInfo>> {
+		mounts_from_value(self.request(role, "GET", "sys/auth", None)?)
+	}
+
+	/// Return asynchronously the auth methods by mount point
+	pub async fn list_auth_methods_async(&self, role: &str) -> Result<HashMap<String, MountInfo>> {
+		mounts_from_value(self.request_async(role, "GET", "sys/auth", None).await?)
+	}
+
+	/// Enable an auth method of method_type (ex: `kubernetes`, `approle`) at path
+	pub fn enable_auth_method(
+		&self,
+		role: &str,
+		path: &str,
+		method_type: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let body = enable_body(method_type, config);
+		self.request(role, "POST", &auth_path(path), Some(body))
+			.map(|_| ())
+	}
+
+	/// Enable asynchronously an auth method of method_type at path
+	pub async fn enable_auth_method_async(
+		&self,
+		role: &str,
+		path: &str,
+		method_type: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let body = enable_body(method_type, config);
+		self.request_async(role, "POST", &auth_path(path), Some(body))
+			.await
+			.map(|_| ())
+	}
+
+	/// Disable the auth method at path, revoking all the tokens it issued
+	pub fn disable_auth_method(&self, role: &str, path: &str) -> Result<()> {
+		self.request(role, "DELETE", &auth_path(path), None)
+			.map(|_| ())
+	}
+
+	/// Disable asynchronously the auth method at path
+	pub async fn disable_auth_method_async(&self, role: &str, path: &str) -> Result<()> {
+		self.request_async(role, "DELETE", &auth_path(path), None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Change the configuration of the auth method at path
+	pub fn tune_auth_method(&self, role: &str, path: &str, config: &MountConfig) -> Result<()> {
+		let path = format!("{}/tune", auth_path(path));
+		self.request(role, "POST", &path, Some(json!(config)))
+			.map(|_| ())
+	}
+
+	/// Change asynchronously the configuration of the auth method at path
+	pub async fn tune_auth_method_async(
+		&self,
+		role: &str,
+		path: &str,
+		config: &MountConfig,
+	) -> Result<()> {
+		let path = format!("{}/tune", auth_path(path));
+		self.request_async(role, "POST", &path, Some(json!(config)))
+			.await
+			.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn enable_body_nests_config() {
+		let mut options = HashMap::new();
+		options.insert("version".to_owned(), "2".to_owned());
+		let config = MountConfig {
+			description: Some("app secrets".to_owned()),
+			max_lease_ttl: Some("768h".to_owned()),
+			options: Some(options),
+			..Default::default()
+		};
+		assert_eq!(
+			enable_body("kv", &config),
+			json!({
+				"type": "kv",
+				"description": "app secrets",
+				"options": {"version": "2"},
+				"config": {"max_lease_ttl": "768h"}
+			})
+		);
+	}
+
+	#[test]
+	fn mounts_from_response() {
+		let mounts = mounts_from_value(json!({
+			"data": {
+				"secret/": {
+					"accessor": "kv_2b3a7c4e",
+					"config": {"default_lease_ttl": 0, "force_no_cache": false, "max_lease_ttl": 0},
+					"description": "key/value secret storage",
+					"local": false,
+					"options": {"version": "2"},
+					"seal_wrap": false,
+					"type": "kv"
+				}
+			}
+		}))
+		.unwrap();
+		assert_eq!(mounts["secret/"].version(), Some("2"));
+		assert!(matches!(
+			mounts_from_value(json!({})),
+			Err(Error::MountsNotFound)
+		));
+	}
+}
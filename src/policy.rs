@@ -0,0 +1,92 @@
+use crate::{
+	client::{keys_from_value, VaultClient},
+	error::{Error, Result},
+};
+
+use serde_json::{json, Value};
+
+/// Return the path of an acl policy
+fn policy_path(name: &str) -> String {
+	format!("sys/policies/acl/{}", name)
+}
+
+/// Extract the rules of a policy from a response of `sys/policies/acl/<name>`
+fn rules_from_value(value: &Value) -> Result<String> {
+	value["data"]["policy"]
+		.as_str()
+		.map(|s| s.to_owned())
+		.ok_or(Error::MissingField("policy"))
+}
+
+impl VaultClient {
+	/// Return the names of the acl policies
+	pub fn list_policies(&self, role: &str) -> Result<Vec<String>> {
+		keys_from_value(self.request(role, "GET", "sys/policies/acl?list=true", None)?)
+	}
+
+	/// Return asynchronously the names of the acl policies
+	pub async fn list_policies_async(&self, role: &str) -> Result<Vec<String>> {
+		let value = self
+			.request_async(role, "GET", "sys/policies/acl?list=true", None)
+			.await?;
+		keys_from_value(value)
+	}
+
+	/// Return the rules (hcl) of the acl policy name
+	pub fn read_policy(&self, role: &str, name: &str) -> Result<String> {
+		rules_from_value(&self.request(role, "GET", &policy_path(name), None)?)
+	}
+
+	/// Return asynchronously the rules of the acl policy name
+	pub async fn read_policy_async(&self, role: &str, name: &str) -> Result<String> {
+		let value = self
+			.request_async(role, "GET", &policy_path(name), None)
+			.await?;
+		rules_from_value(&value)
+	}
+
+	/// Create or replace the acl policy name with rules (hcl)
+	pub fn write_policy(&self, role: &str, name: &str, rules: &str) -> Result<()> {
+		let body = json!({ "policy": rules });
+		self.request(role, "POST", &policy_path(name), Some(body))
+			.map(|_| ())
+	}
+
+	/// Create or replace asynchronously the acl policy name with rules
+	pub async fn write_policy_async(&self, role: &str, name: &str, rules: &str) -> Result<()> {
+		let body = json!({ "policy": rules });
+		self.request_async(role, "POST", &policy_path(name), Some(body))
+			.await
+			.map(|_| ())
+	}
+
+	/// Delete the acl policy name
+	pub fn delete_policy(&self, role: &str, name: &str) -> Result<()> {
+		self.request(role, "DELETE", &policy_path(name), None)
+			.map(|_| ())
+	}
+
+	/// Delete asynchronously the acl policy name
+	pub async fn delete_policy_async(&self, role: &str, name: &str) -> Result<()> {
+		self.request_async(role, "DELETE", &policy_path(name), None)
+			.await
+			.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn rules_from_response() {
+		let rules = rules_from_value(&json!({
+			"data": {
+				"name": "app",
+				"policy": "path \"secret/data/app/*\" {\n  capabilities = [\"read\"]\n}"
+			}
+		}))
+		.unwrap();
+		assert!(rules.starts_with("path \"secret/data/app/*\""));
+	}
+}
@@ -0,0 +1,162 @@
+use crate::{
+	builder::VaultClientBuilder,
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use std::{
+	collections::BTreeMap,
+	sync::{
+		mpsc::{self, Receiver},
+		Arc,
+	},
+	thread,
+};
+
+/// Configuration applied to the builder of every client of a pool
+type Defaults = Box<dyn Fn(VaultClientBuilder) -> VaultClientBuilder>;
+
+/// Clients of several vault clusters (prod and dr, one per region...) identified by a name
+#[derive(Debug, Default, Clone)]
+pub struct VaultPool {
+	clients: BTreeMap<String, Arc<VaultClient>>,
+}
+
+/// Builder of a pool of clients sharing the same defaults
+pub struct VaultPoolBuilder {
+	defaults: Defaults,
+	clusters: Vec<(String, String, Defaults)>,
+}
+
+impl Default for VaultPoolBuilder {
+	fn default() -> Self {
+		Self {
+			defaults: Box::new(|builder| builder),
+			clusters: Vec::new(),
+		}
+	}
+}
+
+impl VaultPoolBuilder {
+	/// Configure the builders of all the clients with defaults (auth method, retry, tls...)
+	pub fn defaults(
+		mut self,
+		defaults: impl Fn(VaultClientBuilder) -> VaultClientBuilder + 'static,
+	) -> Self {
+		self.defaults = Box::new(defaults);
+		self
+	}
+
+	/// Add a client named name of the vault server at url configured with the defaults
+	pub fn cluster(self, name: &str, url: &str) -> Self {
+		self.cluster_with(name, url, |builder| builder)
+	}
+
+	/// Add a client named name of the vault server at url, configured with the defaults and
+	/// then with configure
+	pub fn cluster_with(
+		mut self,
+		name: &str,
+		url: &str,
+		configure: impl Fn(VaultClientBuilder) -> VaultClientBuilder + 'static,
+	) -> Self {
+		self.clusters
+			.push((name.to_owned(), url.to_owned(), Box::new(configure)));
+		self
+	}
+
+	/// Build the clients of the pool, failing with the error of the first client that can't be
+	/// built
+	pub fn build(self) -> Result<VaultPool> {
+		let mut clients = BTreeMap::new();
+		for (name, url, configure) in self.clusters {
+			let builder = configure((self.defaults)(VaultClientBuilder::new(&url)));
+			clients.insert(name, Arc::new(builder.build()?));
+		}
+		Ok(VaultPool { clients })
+	}
+}
+
+impl VaultPool {
+	/// Create a builder of a pool
+	pub fn builder() -> VaultPoolBuilder {
+		VaultPoolBuilder::default()
+	}
+
+	/// Add client under name, returning the client replaced if any
+	pub fn insert(&mut self, name: &str, client: VaultClient) -> Option<Arc<VaultClient>> {
+		self.clients.insert(name.to_owned(), Arc::new(client))
+	}
+
+	/// Return the client named name
+	pub fn get(&self, name: &str) -> Option<&Arc<VaultClient>> {
+		self.clients.get(name)
+	}
+
+	/// Return the names of the clients
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.clients.keys().map(String::as_str)
+	}
+
+	/// Return the clients with their names
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<VaultClient>)> {
+		self.clients
+			.iter()
+			.map(|(name, client)| (name.as_str(), client))
+	}
+
+	/// Log in role on all the clients and return the errors by client name (empty if all the
+	/// logins succeeded)
+	pub fn login_all(&self, role: &str) -> Vec<(String, Error)> {
+		self.clients
+			.iter()
+			.filter_map(|(name, client)| client.login(role).err().map(|e| (name.clone(), e)))
+			.collect()
+	}
+
+	/// Start the renewal of the token of role on all the clients (see
+	/// `VaultClient::start_renewal`) and return the errors over a single channel, with the name
+	/// of the client where they occurred
+	pub fn start_renewal(&self, role: &str) -> Receiver<(String, Error)> {
+		let (tx, rx) = mpsc::channel();
+		for (name, client) in self.clients.iter() {
+			let errors = VaultClient::start_renewal(client, role);
+			let tx = tx.clone();
+			let name = name.clone();
+			thread::spawn(move || {
+				for error in errors {
+					if tx.send((name.clone(), error)).is_err() {
+						return;
+					}
+				}
+			});
+		}
+		rx
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+
+	#[test]
+	fn pool_with_defaults() {
+		let pool = VaultPool::builder()
+			.defaults(|builder| builder.token("s.token").transport(Scripted::new()))
+			.cluster("prod", "http://vault-prod:8200/v1")
+			.cluster_with("dr", "http://vault-dr:8200/v1", |builder| {
+				builder.namespace("dr")
+			})
+			.build()
+			.unwrap();
+		assert_eq!(pool.names().collect::<Vec<_>>(), vec!["dr", "prod"]);
+		assert_eq!(pool.get("dr").unwrap().namespace.as_deref(), Some("dr"));
+		assert!(pool.login_all("app").is_empty());
+		assert!(VaultPool::builder()
+			.defaults(|builder| builder.transport(Scripted::new()))
+			.cluster("prod", "http://vault-prod:8200/v1")
+			.build()
+			.is_err());
+	}
+}
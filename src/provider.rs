@@ -0,0 +1,426 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+	sink::FileSink,
+	spec::SecretSpec,
+};
+
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Component, Path, PathBuf},
+	sync::{
+		mpsc::{self, Receiver},
+		Arc, Condvar, Mutex,
+	},
+	thread,
+	time::{Duration, Instant, SystemTime},
+};
+
+/// delay before fetching again a secret after a failure
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// extensions of the spec files
+#[cfg(not(feature = "yaml"))]
+const SPEC_EXTENSIONS: &[&str] = &["json"];
+#[cfg(feature = "yaml")]
+const SPEC_EXTENSIONS: &[&str] = &["json", "yaml", "yml"];
+
+/// What the provider did with a specification
+#[derive(Debug)]
+pub enum ProviderEvent {
+	/// the secret of the spec file was written to file
+	Written { spec: PathBuf, file: PathBuf },
+	/// the spec file can't be read or its secret can't be fetched or written
+	Failed { spec: PathBuf, error: Error },
+	/// the spec file was removed, or changed its output or became invalid, and the file previously
+	/// written for it was removed too
+	Removed { spec: PathBuf, file: PathBuf },
+}
+
+/// A specification being served
+struct Served {
+	modified: Option<SystemTime>,
+	/// None if the spec file is invalid
+	sink: Option<(SecretSpec, FileSink)>,
+	due: Instant,
+}
+
+/// State of the provider loop
+struct Provider {
+	client: Arc<VaultClient>,
+	spec_dir: PathBuf,
+	target_dir: PathBuf,
+	scan: Duration,
+	served: HashMap<PathBuf, Served>,
+}
+
+/// Watches in a background thread a directory of secret specifications (`SecretSpec` with a file
+/// output relative to the target directory), and materializes the secrets they reference under
+/// a target directory: they are fetched again at their renew delay (or at the refresh interval of
+/// the spec) and their files are removed with the spec files. The thread stops when the watcher or
+/// the receiver of the events is dropped. The specs are json files (`*.json`), or yaml files
+/// (`*.yaml` or `*.yml`) with the `yaml` feature
+#[derive(Debug)]
+pub struct ProviderWatcher {
+	stop: Arc<(Mutex<bool>, Condvar)>,
+}
+
+/// Parse a spec file, as yaml if its extension isn't json
+fn parse_spec(path: &Path) -> Result<SecretSpec> {
+	let content = fs::read(path)?;
+	#[cfg(feature = "yaml")]
+	if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+		return Ok(serde_yaml::from_slice(&content)?);
+	}
+	serde_json::from_slice(&content).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Parse a spec file and return the sink of its file output under target_dir
+fn load_spec(path: &Path, target_dir: &Path) -> Result<(SecretSpec, FileSink)> {
+	let spec = parse_spec(path)?;
+	let mut sink = spec
+		.output
+		.sink()
+		.ok_or_else(|| Error::SpecError("the output of the spec is not a file".to_owned()))?;
+	// keep the files under the target directory
+	let relative = sink.path.clone();
+	if relative.as_os_str().is_empty()
+		|| !relative
+			.components()
+			.all(|component| matches!(component, Component::Normal(_)))
+	{
+		return Err(Error::SpecError(format!(
+			"file \"{}\" is not relative to the target directory",
+			relative.display()
+		)));
+	}
+	sink.path = target_dir.join(relative);
+	Ok((spec, sink))
+}
+
+/// Return the spec files of dir with their modification time
+fn spec_files(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+	fs::read_dir(dir)
+		.map(|entries| {
+			entries
+				.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+				.filter(|path| {
+					path.extension()
+						.and_then(|ext| ext.to_str())
+						.is_some_and(|ext| SPEC_EXTENSIONS.contains(&ext))
+				})
+				.map(|path| {
+					let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+					(path, modified)
+				})
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+impl Provider {
+	/// Reload the changed spec files, forget the removed ones and fetch the due secrets.
+	/// Returns the events and the time of the next fetch
+	fn tick(&mut self) -> (Vec<ProviderEvent>, Option<Instant>) {
+		let mut events = Vec::new();
+		let now = Instant::now();
+		let files = spec_files(&self.spec_dir);
+		let removed: Vec<PathBuf> = self
+			.served
+			.keys()
+			.filter(|spec| !files.iter().any(|(path, _)| path == *spec))
+			.cloned()
+			.collect();
+		for spec in removed {
+			if let Some((_, sink)) = self.served.remove(&spec).and_then(|served| served.sink) {
+				let _ = fs::remove_file(&sink.path);
+				events.push(ProviderEvent::Removed {
+					spec,
+					file: sink.path,
+				});
+			}
+		}
+		for (path, modified) in files {
+			if self.served.get(&path).map(|served| served.modified) == Some(modified) {
+				continue;
+			}
+			let sink = match load_spec(&path, &self.target_dir) {
+				Ok(sink) => Some(sink),
+				Err(error) => {
+					events.push(ProviderEvent::Failed {
+						spec: path.clone(),
+						error,
+					});
+					None
+				}
+			};
+			// don't leave behind the file of the previous version of the spec
+			if let Some((_, old)) = self
+				.served
+				.get(&path)
+				.and_then(|served| served.sink.as_ref())
+			{
+				if sink.as_ref().is_none_or(|(_, new)| new.path != old.path) {
+					let _ = fs::remove_file(&old.path);
+					events.push(ProviderEvent::Removed {
+						spec: path.clone(),
+						file: old.path.clone(),
+					});
+				}
+			}
+			self.served.insert(
+				path,
+				Served {
+					modified,
+					sink,
+					due: now,
+				},
+			);
+		}
+		let (client, scan) = (&self.client, self.scan);
+		for (path, served) in self.served.iter_mut() {
+			let (spec, sink) = match served.sink {
+				Some((ref spec, ref sink)) if served.due <= now => (spec, sink),
+				_ => continue,
+			};
+			let res = client
+				.fetch_spec(spec)
+				.and_then(|secret| sink.write(&secret).map(|_| secret));
+			let delay = match res {
+				Ok(ref secret) => secret.renew_delay().unwrap_or_else(|| {
+					client
+						.renewal
+						.jittered(spec.refresh.map_or(scan, Duration::from_secs))
+				}),
+				Err(_) => client.renewal.jittered(RETRY_DELAY),
+			};
+			served.due = now + delay;
+			events.push(match res {
+				Ok(_) => ProviderEvent::Written {
+					spec: path.clone(),
+					file: sink.path.clone(),
+				},
+				Err(error) => ProviderEvent::Failed {
+					spec: path.clone(),
+					error,
+				},
+			});
+		}
+		let next = self
+			.served
+			.values()
+			.filter(|served| served.sink.is_some())
+			.map(|served| served.due)
+			.min();
+		(events, next)
+	}
+}
+
+impl ProviderWatcher {
+	/// Start serving the spec files (`*.json`) of spec_dir, with the files written under
+	/// target_dir. The directory is scanned for changes every scan interval, which is also the
+	/// refresh interval of the specs without one
+	pub fn start(
+		client: &Arc<VaultClient>,
+		spec_dir: &str,
+		target_dir: &str,
+		scan: Duration,
+	) -> (Self, Receiver<ProviderEvent>) {
+		let (tx, rx) = mpsc::channel();
+		let stop = Arc::new((Mutex::new(false), Condvar::new()));
+		let mut provider = Provider {
+			client: Arc::clone(client),
+			spec_dir: PathBuf::from(spec_dir),
+			target_dir: PathBuf::from(target_dir),
+			scan,
+			served: HashMap::new(),
+		};
+		let thread_stop = Arc::clone(&stop);
+		thread::spawn(move || loop {
+			let (events, next) = provider.tick();
+			for event in events {
+				if tx.send(event).is_err() {
+					return;
+				}
+			}
+			// wait until the next scan or fetch, or for the watcher to be dropped
+			let delay = next.map_or(scan, |next| {
+				scan.min(next.saturating_duration_since(Instant::now()))
+			});
+			let (lock, cvar) = &*thread_stop;
+			let stopped = lock.lock().unwrap_or_else(|e| e.into_inner());
+			let (stopped, _) = cvar
+				.wait_timeout_while(stopped, delay, |stopped| !*stopped)
+				.unwrap_or_else(|e| e.into_inner());
+			if *stopped {
+				return;
+			}
+		});
+		(Self { stop }, rx)
+	}
+}
+
+impl Drop for ProviderWatcher {
+	fn drop(&mut self) {
+		let (lock, cvar) = &*self.stop;
+		*lock.lock().unwrap_or_else(|e| e.into_inner()) = true;
+		cvar.notify_one();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+	use serde_json::json;
+
+	#[test]
+	fn specs_are_materialized() {
+		let dir = std::env::temp_dir().join(format!("vault-jwt-provider-{}", std::process::id()));
+		let (specs, target) = (dir.join("specs"), dir.join("target"));
+		fs::create_dir_all(&specs).unwrap();
+		fs::create_dir_all(&target).unwrap();
+		fs::write(
+			specs.join("db.json"),
+			r#"{"role": "app", "path": "secret/db", "output": {"type": "file", "path": "password", "format": {"raw": "password"}}}"#,
+		)
+		.unwrap();
+		fs::write(
+			specs.join("bad.json"),
+			r#"{"role": "app", "path": "secret/db", "output": {"type": "file", "path": "../escape"}}"#,
+		)
+		.unwrap();
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/db",
+			200,
+			json!({"data": {"password": "hunter2"}}),
+		);
+		let mut provider = Provider {
+			client: Arc::new(transport.client()),
+			spec_dir: specs.clone(),
+			target_dir: target.clone(),
+			scan: Duration::from_secs(60),
+			served: HashMap::new(),
+		};
+		let (events, next) = provider.tick();
+		assert_eq!(events.len(), 2);
+		assert!(events.iter().any(|event| matches!(
+			event,
+			ProviderEvent::Failed {
+				error: Error::SpecError(_),
+				..
+			}
+		)));
+		assert!(next.is_some());
+		assert_eq!(
+			fs::read_to_string(target.join("password")).unwrap(),
+			"hunter2"
+		);
+		assert!(provider.tick().0.is_empty());
+
+		fs::remove_file(specs.join("db.json")).unwrap();
+		let (events, _) = provider.tick();
+		assert!(matches!(events[..], [ProviderEvent::Removed { .. }]));
+		assert!(!target.join("password").exists());
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[cfg(feature = "yaml")]
+	#[test]
+	fn yaml_specs_are_materialized() {
+		let dir = std::env::temp_dir().join(format!("vault-jwt-yaml-{}", std::process::id()));
+		let (specs, target) = (dir.join("specs"), dir.join("target"));
+		fs::create_dir_all(&specs).unwrap();
+		fs::create_dir_all(&target).unwrap();
+		fs::write(
+			specs.join("db.yml"),
+			"role: app\npath: secret/db\noutput:\n  type: file\n  path: password\n  format:\n    raw: password\n",
+		)
+		.unwrap();
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/db",
+			200,
+			json!({"data": {"password": "hunter2"}}),
+		);
+		let mut provider = Provider {
+			client: Arc::new(transport.client()),
+			spec_dir: specs,
+			target_dir: target.clone(),
+			scan: Duration::from_secs(60),
+			served: HashMap::new(),
+		};
+		let (events, _) = provider.tick();
+		assert!(matches!(events[..], [ProviderEvent::Written { .. }]));
+		assert_eq!(
+			fs::read_to_string(target.join("password")).unwrap(),
+			"hunter2"
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	/// Write a spec file with a modification time different from the previous one
+	fn write_spec(path: &Path, spec: &str, modified: u64) {
+		fs::write(path, spec).unwrap();
+		fs::File::options()
+			.write(true)
+			.open(path)
+			.and_then(|file| {
+				file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified))
+			})
+			.unwrap();
+	}
+
+	#[test]
+	fn stale_files_are_removed() {
+		let dir = std::env::temp_dir().join(format!("vault-jwt-stale-{}", std::process::id()));
+		let (specs, target) = (dir.join("specs"), dir.join("target"));
+		fs::create_dir_all(&specs).unwrap();
+		fs::create_dir_all(&target).unwrap();
+		let spec = specs.join("db.json");
+		let output = |path: &str| {
+			format!(
+				r#"{{"role": "app", "path": "secret/db", "output": {{"type": "file", "path": "{}"}}}}"#,
+				path
+			)
+		};
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/db",
+			200,
+			json!({"data": {"password": "hunter2"}}),
+		);
+		let mut provider = Provider {
+			client: Arc::new(transport.client()),
+			spec_dir: specs,
+			target_dir: target.clone(),
+			scan: Duration::from_secs(60),
+			served: HashMap::new(),
+		};
+		write_spec(&spec, &output("old"), 1);
+		provider.tick();
+		assert!(target.join("old").exists());
+
+		write_spec(&spec, &output("new"), 2);
+		let (events, _) = provider.tick();
+		assert!(matches!(
+			events[..],
+			[ProviderEvent::Removed { .. }, ProviderEvent::Written { .. }]
+		));
+		assert!(!target.join("old").exists());
+		assert!(target.join("new").exists());
+
+		write_spec(&spec, "{", 3);
+		let (events, _) = provider.tick();
+		assert!(matches!(
+			events[..],
+			[ProviderEvent::Failed { .. }, ProviderEvent::Removed { .. }]
+		));
+		assert!(!target.join("new").exists());
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -0,0 +1,204 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+	transport::{AsyncBody, Body},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// A server of the raft cluster
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RaftServer {
+	pub node_id: String,
+	pub address: String,
+	pub leader: bool,
+	pub voter: bool,
+	#[serde(default)]
+	pub protocol_version: String,
+}
+
+/// Configuration of the raft cluster as returned by `sys/storage/raft/configuration`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RaftConfiguration {
+	pub servers: Vec<RaftServer>,
+	#[serde(default)]
+	pub index: u64,
+}
+
+impl RaftConfiguration {
+	/// return the current leader
+	pub fn leader(&self) -> Option<&RaftServer> {
+		self.servers.iter().find(|server| server.leader)
+	}
+}
+
+/// Extract the raft configuration
+fn configuration_from_value(mut value: Value) -> Result<RaftConfiguration> {
+	serde_json::from_value(value["data"]["config"].take())
+		.map_err(|e| Error::ParseError { source: e })
+}
+
+/// Body of a join request
+fn join_body(leader_api_addr: &str, leader_ca_cert: Option<&str>) -> String {
+	let mut body = json!({ "leader_api_addr": leader_api_addr });
+	if let Some(ca_cert) = leader_ca_cert {
+		body["leader_ca_cert"] = Value::from(ca_cert);
+	}
+	body.to_string()
+}
+
+/// Extract the result of a join request
+fn joined_from_value(value: &Value) -> Result<bool> {
+	value["joined"]
+		.as_bool()
+		.ok_or(Error::MissingField("joined"))
+}
+
+/// Return the restore endpoint. A forced restore accepts a snapshot of another cluster
+fn restore_path(force: bool) -> &'static str {
+	if force {
+		"sys/storage/raft/snapshot-force"
+	} else {
+		"sys/storage/raft/snapshot"
+	}
+}
+
+impl VaultClient {
+	/// Return the configuration of the raft cluster with the token of role
+	pub fn raft_configuration(&self, role: &str) -> Result<RaftConfiguration> {
+		configuration_from_value(self.request(
+			role,
+			"GET",
+			"sys/storage/raft/configuration",
+			None,
+		)?)
+	}
+
+	/// Return asynchronously the configuration of the raft cluster with the token of role
+	pub async fn raft_configuration_async(&self, role: &str) -> Result<RaftConfiguration> {
+		let value = self
+			.request_async(role, "GET", "sys/storage/raft/configuration", None)
+			.await?;
+		configuration_from_value(value)
+	}
+
+	/// Join the (uninitialized) server of this client to the raft cluster of leader_api_addr.
+	/// No login is needed
+	pub fn raft_join(&self, leader_api_addr: &str, leader_ca_cert: Option<&str>) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			"sys/storage/raft/join",
+			None,
+			join_body(leader_api_addr, leader_ca_cert),
+		)?;
+		joined_from_value(&self.send(request)?)
+	}
+
+	/// Join asynchronously the server of this client to the raft cluster of leader_api_addr
+	pub async fn raft_join_async(
+		&self,
+		leader_api_addr: &str,
+		leader_ca_cert: Option<&str>,
+	) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			"sys/storage/raft/join",
+			None,
+			join_body(leader_api_addr, leader_ca_cert),
+		)?;
+		joined_from_value(&self.send_async(request).await?)
+	}
+
+	/// Remove the server with node id server_id from the raft cluster
+	pub fn raft_remove_peer(&self, role: &str, server_id: &str) -> Result<()> {
+		let body = json!({ "server_id": server_id });
+		self.request(role, "POST", "sys/storage/raft/remove-peer", Some(body))
+			.map(|_| ())
+	}
+
+	/// Remove asynchronously the server with node id server_id from the raft cluster
+	pub async fn raft_remove_peer_async(&self, role: &str, server_id: &str) -> Result<()> {
+		let body = json!({ "server_id": server_id });
+		self.request_async(role, "POST", "sys/storage/raft/remove-peer", Some(body))
+			.await
+			.map(|_| ())
+	}
+
+	/// Take a snapshot of the raft storage and return it as a stream to copy (ex: with
+	/// `std::io::copy`) to a file
+	pub fn raft_snapshot(&self, role: &str) -> Result<Body> {
+		let request = self.vault_request(
+			"GET",
+			"sys/storage/raft/snapshot",
+			Some(&self.token(role)?),
+			Body::empty(),
+		)?;
+		Ok(self.send_raw(request)?.into_body())
+	}
+
+	/// Take asynchronously a snapshot of the raft storage and return it as an async stream
+	pub async fn raft_snapshot_async(&self, role: &str) -> Result<AsyncBody> {
+		let request = self.vault_request(
+			"GET",
+			"sys/storage/raft/snapshot",
+			Some(&self.token_async(role).await?),
+			AsyncBody::empty(),
+		)?;
+		Ok(self.send_raw_async(request).await?.into_body())
+	}
+
+	/// Restore the raft storage from a snapshot (use `Body::from_reader` to stream a file).
+	/// With force, a snapshot of another cluster is accepted
+	pub fn raft_restore<B: Into<Body>>(&self, role: &str, snapshot: B, force: bool) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			restore_path(force),
+			Some(&self.token(role)?),
+			snapshot.into(),
+		)?;
+		self.send_raw(request).map(|_| ())
+	}
+
+	/// Restore asynchronously the raft storage from a snapshot
+	pub async fn raft_restore_async<B: Into<AsyncBody>>(
+		&self,
+		role: &str,
+		snapshot: B,
+		force: bool,
+	) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			restore_path(force),
+			Some(&self.token_async(role).await?),
+			snapshot.into(),
+		)?;
+		self.send_raw_async(request).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn configuration_from_response() {
+		let config = configuration_from_value(json!({
+			"data": {
+				"config": {
+					"index": 0,
+					"servers": [
+						{"address": "vault-0.vault-internal:8201", "leader": true, "node_id": "vault-0", "protocol_version": "3", "voter": true},
+						{"address": "vault-1.vault-internal:8201", "leader": false, "node_id": "vault-1", "protocol_version": "3", "voter": true}
+					]
+				}
+			}
+		}))
+		.unwrap();
+		assert_eq!(config.leader().unwrap().node_id, "vault-0");
+		assert_eq!(
+			join_body("https://vault-0.vault-internal:8200", None),
+			r#"{"leader_api_addr":"https://vault-0.vault-internal:8200"}"#
+		);
+	}
+}
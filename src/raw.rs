@@ -0,0 +1,211 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+	transport::{AsyncBody, Body},
+};
+
+use http::{header::CONTENT_TYPE, Response, StatusCode};
+use serde_json::Value;
+use std::io::Read;
+
+/// A buffered vault response whose body may not be json
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+	pub status: StatusCode,
+	pub content_type: Option<String>,
+	pub body: Vec<u8>,
+}
+
+/// Body of a raw response decoded according to its content type
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawBody {
+	Json(Value),
+	Text(String),
+	Bytes(Vec<u8>),
+	Empty,
+}
+
+impl RawResponse {
+	/// buffer the body of a response
+	fn from_response(res: &mut Response<Body>) -> Result<Self> {
+		let mut body = Vec::new();
+		res.body_mut()
+			.read_to_end(&mut body)
+			.map_err(Error::transport)?;
+		Ok(Self {
+			status: res.status(),
+			content_type: content_type(res),
+			body,
+		})
+	}
+
+	/// buffer asynchronously the body of a response
+	async fn from_response_async(res: &mut Response<AsyncBody>) -> Result<Self> {
+		let body = res
+			.body_mut()
+			.read_bytes()
+			.await
+			.map_err(Error::transport)?;
+		Ok(Self {
+			status: res.status(),
+			content_type: content_type(res),
+			body,
+		})
+	}
+
+	/// check if the body is json
+	pub fn is_json(&self) -> bool {
+		matches!(self.content_type, Some(ref ct) if ct.ends_with("json"))
+	}
+
+	/// return the body as an utf-8 string
+	pub fn text(&self) -> Result<&str> {
+		std::str::from_utf8(&self.body).map_err(|_| Error::InvalidUtf8)
+	}
+
+	/// parse the body as json whatever its content type
+	pub fn json(&self) -> Result<Value> {
+		serde_json::from_slice(&self.body).map_err(|e| Error::ParseError { source: e })
+	}
+
+	/// decode the body according to its content type: json, text (`text/*`, pem files and
+	/// certificate chains) or raw bytes (der certificates, snapshots...)
+	pub fn decode(self) -> Result<RawBody> {
+		if self.body.is_empty() {
+			return Ok(RawBody::Empty);
+		}
+		let content_type = self.content_type.as_deref().unwrap_or("");
+		if self.is_json() {
+			self.json().map(RawBody::Json)
+		} else if content_type.starts_with("text/")
+			|| content_type.ends_with("pem-file")
+			|| content_type.starts_with("application/pem-")
+		{
+			String::from_utf8(self.body)
+				.map(RawBody::Text)
+				.map_err(|_| Error::InvalidUtf8)
+		} else {
+			Ok(RawBody::Bytes(self.body))
+		}
+	}
+}
+
+/// Return the media type of a response without its parameters
+fn content_type<B>(res: &Response<B>) -> Option<String> {
+	res.headers()
+		.get(CONTENT_TYPE)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.split(';').next())
+		.map(|v| v.trim().to_ascii_lowercase())
+}
+
+impl VaultClient {
+	/// Send a request to any vault endpoint with the token of role (or without token if role is
+	/// empty) and return the response buffered as bytes, for endpoints that don't answer json
+	pub fn request_raw(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: Vec<u8>,
+	) -> Result<RawResponse> {
+		let mut res = self.request_stream(role, method, path, body)?;
+		RawResponse::from_response(&mut res)
+	}
+
+	/// Send asynchronously a request to any vault endpoint and return the response buffered
+	pub async fn request_raw_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: Vec<u8>,
+	) -> Result<RawResponse> {
+		let mut res = self.request_stream_async(role, method, path, body).await?;
+		RawResponse::from_response_async(&mut res).await
+	}
+
+	/// Send a request to any vault endpoint with the token of role (or without token if role is
+	/// empty) and return the response with its body unread to stream large or endless bodies
+	pub fn request_stream<B: Into<Body>>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: B,
+	) -> Result<Response<Body>> {
+		let token = if role.is_empty() {
+			None
+		} else {
+			Some(self.token(role)?)
+		};
+		let token = token.as_ref().map(|token| token.as_str());
+		let request = self.vault_request(method, path, token, body.into())?;
+		self.send_raw(request)
+	}
+
+	/// Send asynchronously a request to any vault endpoint and return the response with its
+	/// body unread
+	pub async fn request_stream_async<B: Into<AsyncBody>>(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		body: B,
+	) -> Result<Response<AsyncBody>> {
+		let token = if role.is_empty() {
+			None
+		} else {
+			Some(self.token_async(role).await?)
+		};
+		let token = token.as_ref().map(|token| token.as_str());
+		let request = self.vault_request(method, path, token, body.into())?;
+		self.send_raw_async(request).await
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn raw(content_type: &str, body: &[u8]) -> RawResponse {
+		RawResponse {
+			status: StatusCode::OK,
+			content_type: Some(content_type.to_owned()),
+			body: body.to_vec(),
+		}
+	}
+
+	#[test]
+	fn decode_by_content_type() {
+		assert_eq!(
+			raw("application/json", br#"{"data": {}}"#)
+				.decode()
+				.unwrap(),
+			RawBody::Json(serde_json::json!({"data": {}}))
+		);
+		assert_eq!(
+			raw("application/pkix-cert", b"\x30\x82")
+				.decode()
+				.unwrap(),
+			RawBody::Bytes(vec![0x30, 0x82])
+		);
+		assert_eq!(
+			raw("application/pem-certificate-chain", b"-----BEGIN CERTIFICATE-----")
+				.decode()
+				.unwrap(),
+			RawBody::Text("-----BEGIN CERTIFICATE-----".to_owned())
+		);
+		assert_eq!(
+			raw("text/plain", b"-----BEGIN CERTIFICATE-----")
+				.decode()
+				.unwrap(),
+			RawBody::Text("-----BEGIN CERTIFICATE-----".to_owned())
+		);
+		let res = Response::builder()
+			.header(CONTENT_TYPE, "Application/JSON; charset=utf-8")
+			.body(())
+			.unwrap();
+		assert_eq!(content_type(&res).as_deref(), Some("application/json"));
+	}
+}
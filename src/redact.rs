@@ -0,0 +1,114 @@
+use crate::{
+	error::Error,
+	sha256::{hex, sha256},
+};
+
+use serde_json::Value;
+use std::fmt;
+
+/// Display a token or a password with only its length and the beginning of its sha256 digest,
+/// enough to tell two tokens apart without leaking them
+pub(crate) struct Redacted<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Redacted<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.0.is_empty() {
+			write!(f, "<empty>")
+		} else {
+			let digest = hex(&sha256(self.0.as_bytes()));
+			write!(
+				f,
+				"<redacted {} bytes sha256:{}>",
+				self.0.len(),
+				&digest[..8]
+			)
+		}
+	}
+}
+
+impl fmt::Debug for Redacted<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// Display a list of keys or tokens, each one redacted
+pub(crate) struct RedactedList<'a>(pub(crate) &'a [String]);
+
+impl fmt::Debug for RedactedList<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_list()
+			.entries(self.0.iter().map(|item| Redacted(item)))
+			.finish()
+	}
+}
+
+/// Display a secret payload with its keys but only the length of its values
+pub(crate) struct RedactedValue<'a>(pub(crate) &'a Value);
+
+impl fmt::Debug for RedactedValue<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Value::Object(map) => f
+				.debug_map()
+				.entries(map.iter().map(|(k, v)| (k, RedactedLen(v))))
+				.finish(),
+			value => RedactedLen(value).fmt(f),
+		}
+	}
+}
+
+/// length of the json representation of a value
+struct RedactedLen<'a>(&'a Value);
+
+impl fmt::Debug for RedactedLen<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Value::Null => write!(f, "null"),
+			Value::String(s) => write!(f, "<redacted {} bytes>", s.len()),
+			value => write!(f, "<redacted {} bytes>", value.to_string().len()),
+		}
+	}
+}
+
+/// Replace a deserialization error of a secret payload whose message could quote a value (`invalid
+/// type: string "..."`) by a generic one
+pub(crate) fn parse_error(e: serde_json::Error) -> Error {
+	let message = match e.classify() {
+		serde_json::error::Category::Data => "secret doesn't match the expected type",
+		_ => "invalid secret",
+	};
+	Error::ParseError {
+		source: serde::de::Error::custom(message),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn values_are_redacted() {
+		assert_eq!(
+			Redacted("hvs.token").to_string(),
+			"<redacted 9 bytes sha256:1e4f1541>"
+		);
+		assert_eq!(
+			format!(
+				"{:?}",
+				RedactedValue(&json!({"username": "app", "password": "hunter2", "ttl": 3600}))
+			),
+			r#"{"password": <redacted 7 bytes>, "ttl": <redacted 4 bytes>, "username": <redacted 3 bytes>}"#
+		);
+	}
+
+	#[test]
+	fn parse_error_hides_value() {
+		let e = serde_json::from_value::<u64>(json!("hunter2")).unwrap_err();
+		match parse_error(e) {
+			Error::ParseError { source } => assert!(!format!("{:?}", source).contains("hunter2")),
+			_ => panic!("not a parse error"),
+		}
+	}
+}
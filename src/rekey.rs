@@ -0,0 +1,214 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeroize::ZeroizeOnDrop;
+
+/// Parameters of a rekey of the unseal keys
+#[derive(Debug, Clone, Serialize)]
+pub struct RekeyParams {
+	/// number of new key shares
+	pub secret_shares: u32,
+	/// number of new key shares required to unseal
+	pub secret_threshold: u32,
+	/// pgp public keys (base64) used to encrypt each new key share
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pgp_keys: Option<Vec<String>>,
+	/// store the encrypted key shares in the core to recover them with `sys/rekey/backup`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub backup: Option<bool>,
+	/// ask for the new key shares before they replace the old ones
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub require_verification: Option<bool>,
+}
+
+impl RekeyParams {
+	pub fn new(secret_shares: u32, secret_threshold: u32) -> Self {
+		Self {
+			secret_shares,
+			secret_threshold,
+			pgp_keys: None,
+			backup: None,
+			require_verification: None,
+		}
+	}
+}
+
+/// State of a rekey as returned by `sys/rekey/init` and `sys/rekey/update`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct RekeyStatus {
+	#[serde(default)]
+	pub nonce: String,
+	#[serde(default)]
+	pub started: bool,
+	/// new threshold
+	#[serde(default)]
+	pub t: u32,
+	/// new number of shares
+	#[serde(default)]
+	pub n: u32,
+	/// number of old key shares already given
+	#[serde(default)]
+	pub progress: u32,
+	/// number of old key shares required
+	#[serde(default)]
+	pub required: u32,
+	#[serde(default)]
+	pub complete: bool,
+	/// new key shares in hexadecimal once complete
+	#[serde(default)]
+	pub keys: Vec<String>,
+	#[serde(default)]
+	pub keys_base64: Vec<String>,
+	#[serde(default)]
+	pub backup: bool,
+	#[serde(default)]
+	pub verification_required: bool,
+	#[serde(default)]
+	pub verification_nonce: String,
+}
+
+/// State of the encryption key as returned by `sys/key-status`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct KeyStatus {
+	/// version of the encryption key
+	pub term: u64,
+	pub install_time: String,
+	#[serde(default)]
+	pub encryptions: u64,
+}
+
+/// Deserialize a vault response that is not wrapped in a data field
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+	serde_json::from_value(value).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Rotate the encryption key of the barrier with the token of role. Older keys are kept to
+	/// decrypt existing data
+	pub fn rotate(&self, role: &str) -> Result<()> {
+		self.request(role, "PUT", "sys/rotate", None).map(|_| ())
+	}
+
+	/// Rotate asynchronously the encryption key of the barrier with the token of role
+	pub async fn rotate_async(&self, role: &str) -> Result<()> {
+		self.request_async(role, "PUT", "sys/rotate", None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Return the state of the encryption key with the token of role
+	pub fn key_status(&self, role: &str) -> Result<KeyStatus> {
+		let mut value = self.request(role, "GET", "sys/key-status", None)?;
+		match value.get_mut("data").map(Value::take) {
+			Some(data) if !data.is_null() => from_value(data),
+			_ => from_value(value),
+		}
+	}
+
+	/// Return asynchronously the state of the encryption key with the token of role
+	pub async fn key_status_async(&self, role: &str) -> Result<KeyStatus> {
+		let mut value = self
+			.request_async(role, "GET", "sys/key-status", None)
+			.await?;
+		match value.get_mut("data").map(Value::take) {
+			Some(data) if !data.is_null() => from_value(data),
+			_ => from_value(value),
+		}
+	}
+
+	/// Return the state of the current rekey. No login is needed
+	pub fn rekey_status(&self) -> Result<RekeyStatus> {
+		let request = self.vault_request("GET", "sys/rekey/init", None, String::new())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the state of the current rekey
+	pub async fn rekey_status_async(&self) -> Result<RekeyStatus> {
+		let request = self.vault_request("GET", "sys/rekey/init", None, String::new())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Start a rekey of the unseal keys. The returned status holds the nonce to give with each
+	/// old key share
+	pub fn rekey_init(&self, params: &RekeyParams) -> Result<RekeyStatus> {
+		let request =
+			self.vault_request("PUT", "sys/rekey/init", None, json!(params).to_string())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Start asynchronously a rekey of the unseal keys
+	pub async fn rekey_init_async(&self, params: &RekeyParams) -> Result<RekeyStatus> {
+		let request =
+			self.vault_request("PUT", "sys/rekey/init", None, json!(params).to_string())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Give an old key share to the rekey identified by nonce. The status is complete with the
+	/// new key shares once enough shares were given
+	pub fn rekey_update(&self, key: &str, nonce: &str) -> Result<RekeyStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/rekey/update",
+			None,
+			json!({ "key": key, "nonce": nonce }).to_string(),
+		)?;
+		from_value(self.send(request)?)
+	}
+
+	/// Give asynchronously an old key share to the rekey identified by nonce
+	pub async fn rekey_update_async(&self, key: &str, nonce: &str) -> Result<RekeyStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/rekey/update",
+			None,
+			json!({ "key": key, "nonce": nonce }).to_string(),
+		)?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Cancel the current rekey
+	pub fn rekey_cancel(&self) -> Result<()> {
+		let request = self.vault_request("DELETE", "sys/rekey/init", None, String::new())?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Cancel asynchronously the current rekey
+	pub async fn rekey_cancel_async(&self) -> Result<()> {
+		let request = self.vault_request("DELETE", "sys/rekey/init", None, String::new())?;
+		self.send_async(request).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn rekey_status_from_responses() {
+		let status: RekeyStatus = from_value(json!({
+			"started": true,
+			"nonce": "2dbd10f1-8528-6246-09e7-82b25b8aba63",
+			"t": 3,
+			"n": 5,
+			"progress": 1,
+			"required": 3,
+			"pgp_fingerprints": null,
+			"backup": false,
+			"verification_required": false
+		}))
+		.unwrap();
+		assert!(status.started && !status.complete && status.keys.is_empty());
+		let status: RekeyStatus = from_value(json!({
+			"complete": true,
+			"keys": ["216a5eb1"],
+			"keys_base64": ["IWpesQ=="],
+			"nonce": "2dbd10f1-8528-6246-09e7-82b25b8aba63"
+		}))
+		.unwrap();
+		assert_eq!(status.keys_base64, vec!["IWpesQ=="]);
+	}
+}
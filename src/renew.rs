@@ -0,0 +1,95 @@
+use crate::{client::VaultClient, error::Error};
+
+use std::{
+	sync::{
+		mpsc::{self, Receiver},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+/// delay before retrying after a failed renewal
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+impl VaultClient {
+	/// Spawn a blocking task on the runtime of the client (a thread by default) that renews the
+	/// token of role each time its lease reaches its renew delay.
+	/// When a renewal fails, the error is sent over the returned channel and a new login is attempted.
+	/// The listeners of the client are notified of the renewals and of the re-logins.
+	/// The task stops when the token has no lease to renew or when an error can't be reported
+	/// because the receiver was dropped.
+	pub fn start_renewal(client: &Arc<VaultClient>, role: &str) -> Receiver<Error> {
+		let (tx, rx) = mpsc::channel();
+		let runtime = Arc::clone(&client.runtime);
+		let client = Arc::clone(client);
+		let role = role.to_owned();
+		runtime.spawn_blocking(Box::new(move || loop {
+			// time left before the renewal
+			let delay = match client.auth(&role).and_then(|mut auth| auth.lease.take()) {
+				// TODO: replace with .is_zero() when stable
+				Some(lease) if lease.lease_duration != Duration::from_secs(0) => lease.renew_in(),
+				_ => break,
+			};
+			thread::sleep(delay);
+			let res = match client.renew_token(&role) {
+				Ok(_) => Ok(()),
+				Err(e) => {
+					if tx.send(e).is_err() {
+						break;
+					}
+					let auth = client.login(&role);
+					client.notify_renewal(&role, &auth);
+					auth.map(|_| ())
+				}
+			};
+			if let Err(e) = res {
+				if tx.send(e).is_err() {
+					break;
+				}
+				// stagger the re-login of clients started together
+				thread::sleep(client.renewal.jittered(RETRY_DELAY));
+			}
+		}));
+		rx
+	}
+
+	/// Spawn on the runtime of the client an async task that renews the token of role like
+	/// `start_renewal`, without blocking a thread while waiting
+	pub fn start_renewal_async(client: &Arc<VaultClient>, role: &str) -> Receiver<Error> {
+		let (tx, rx) = mpsc::channel();
+		let runtime = Arc::clone(&client.runtime);
+		let client = Arc::clone(client);
+		let role = role.to_owned();
+		runtime.spawn(Box::pin(async move {
+			loop {
+				let delay = match client.auth(&role).and_then(|mut auth| auth.lease.take()) {
+					Some(lease) if lease.lease_duration != Duration::from_secs(0) => lease.renew_in(),
+					_ => break,
+				};
+				client.runtime.sleep(delay).await;
+				let res = match client.renew_token_async(&role).await {
+					Ok(_) => Ok(()),
+					Err(e) => {
+						if tx.send(e).is_err() {
+							break;
+						}
+						let auth = client.login_async(&role).await;
+						client.notify_renewal(&role, &auth);
+						auth.map(|_| ())
+					}
+				};
+				if let Err(e) = res {
+					if tx.send(e).is_err() {
+						break;
+					}
+					client
+						.runtime
+						.sleep(client.renewal.jittered(RETRY_DELAY))
+						.await;
+				}
+			}
+		}));
+		rx
+	}
+}
@@ -0,0 +1,100 @@
+use crate::error::Error;
+
+use http::StatusCode;
+use std::time::Duration;
+
+/// Retry policy with exponential backoff applied to transient failures: connection errors,
+/// timeouts, 5xx, 412 (eventual consistency), 429 (rate limit) and 473 (performance standby)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+	/// maximum number of attempts including the first one
+	pub max_attempts: u32,
+	/// delay before the first retry, doubled at each new retry
+	pub base_delay: Duration,
+	/// maximum delay between two attempts
+	pub max_delay: Duration,
+	/// fraction of the delay (between 0 and 1) randomly added to avoid synchronized retries
+	pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: 4,
+			base_delay: Duration::from_millis(250),
+			max_delay: Duration::from_secs(10),
+			jitter: 0.5,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Create a policy with max_attempts and base_delay, and default max delay and jitter
+	pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+		Self {
+			max_attempts,
+			base_delay,
+			..Default::default()
+		}
+	}
+
+	/// Return the delay to wait before the retry number retry (starting at 1)
+	pub fn delay(&self, retry: u32) -> Duration {
+		let delay = self
+			.base_delay
+			.checked_mul(1 << retry.saturating_sub(1).min(16))
+			.unwrap_or(self.max_delay)
+			.min(self.max_delay);
+		delay + delay.mul_f64(self.jitter.clamp(0.0, 1.0) * fastrand::f64())
+	}
+
+	/// Check if a new attempt should be done after the attempt number attempt failed with error
+	pub(crate) fn should_retry(&self, attempt: u32, error: &Error) -> bool {
+		attempt < self.max_attempts && is_transient(error)
+	}
+}
+
+/// Check if a status code denotes a transient failure
+pub(crate) fn is_transient_status(status: StatusCode) -> bool {
+	status.is_server_error() || matches!(status.as_u16(), 412 | 429 | 473)
+}
+
+/// Check if an error is a transient failure worth retrying
+pub(crate) fn is_transient(error: &Error) -> bool {
+	match error {
+		#[cfg(feature = "isahc")]
+		Error::ClientError { source } => source.is_network() || source.is_timeout(),
+		Error::TransportError { .. } => true,
+		error => error.status().is_some_and(is_transient_status),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn exponential_delay() {
+		let policy = RetryPolicy {
+			jitter: 0.0,
+			..RetryPolicy::new(5, Duration::from_millis(100))
+		};
+		assert_eq!(policy.delay(1), Duration::from_millis(100));
+		assert_eq!(policy.delay(3), Duration::from_millis(400));
+		assert_eq!(policy.delay(30), policy.max_delay);
+	}
+
+	#[test]
+	fn transient_errors() {
+		let policy = RetryPolicy::default();
+		let unavailable =
+			Error::from_status(StatusCode::SERVICE_UNAVAILABLE, "/v1/secret", Vec::new());
+		assert!(policy.should_retry(1, &unavailable));
+		assert!(!policy.should_retry(4, &unavailable));
+		assert!(!policy.should_retry(
+			1,
+			&Error::from_status(StatusCode::FORBIDDEN, "/v1/secret", Vec::new())
+		));
+		assert!(is_transient_status(StatusCode::from_u16(473).unwrap()));
+	}
+}
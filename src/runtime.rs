@@ -0,0 +1,184 @@
+use crate::{api::BoxFuture, delay::Delay};
+
+use std::{fmt, thread, time::Duration};
+
+/// Timer and task spawner used by the async apis and background tasks of the client, so that the
+/// crate doesn't depend on a particular executor. Besides [`Threads`], the crate provides
+/// [`AsyncStd`], [`Tokio`] and [`Smol`] with the features of the same name
+pub trait Runtime: fmt::Debug + Send + Sync {
+	/// run task in the background
+	fn spawn(&self, task: BoxFuture<'static, ()>);
+
+	/// return a future that completes after dur
+	fn sleep(&self, dur: Duration) -> BoxFuture<'static, ()>;
+
+	/// run in the background a task blocking its thread (a sync loop renewing a token for
+	/// instance). It runs on its own thread by default
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+		thread::spawn(task);
+	}
+}
+
+/// Runtime using threads: each task is driven by its own thread and timers are served by a single
+/// timer thread shared by the process. It works with any executor but uses a thread per task
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Threads;
+
+impl Runtime for Threads {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		thread::spawn(move || futures_lite::future::block_on(task));
+	}
+
+	fn sleep(&self, dur: Duration) -> BoxFuture<'static, ()> {
+		Box::pin(Delay::new(dur))
+	}
+}
+
+/// Runtime spawning the tasks and sleeping on the async-std executor
+#[cfg(feature = "async-std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AsyncStd;
+
+#[cfg(feature = "async-std")]
+impl Runtime for AsyncStd {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		async_std::task::spawn(task);
+	}
+
+	fn sleep(&self, dur: Duration) -> BoxFuture<'static, ()> {
+		Box::pin(async_std::task::sleep(dur))
+	}
+
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+		async_std::task::spawn_blocking(task);
+	}
+}
+
+/// Runtime spawning the tasks and sleeping on a tokio runtime. It keeps a handle of the runtime,
+/// so the tasks and timers can be created outside of it (from the timer thread of a retry for
+/// instance)
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct Tokio {
+	handle: tokio::runtime::Handle,
+}
+
+#[cfg(feature = "tokio")]
+impl Tokio {
+	/// Use the runtime of handle
+	pub fn new(handle: tokio::runtime::Handle) -> Self {
+		Self { handle }
+	}
+
+	/// Use the current runtime. Panics when not called from a tokio runtime
+	pub fn current() -> Self {
+		Self::new(tokio::runtime::Handle::current())
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl Runtime for Tokio {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		self.handle.spawn(task);
+	}
+
+	fn sleep(&self, dur: Duration) -> BoxFuture<'static, ()> {
+		// the timer is registered to the runtime when created
+		let _guard = self.handle.enter();
+		Box::pin(tokio::time::sleep(dur))
+	}
+
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+		self.handle.spawn_blocking(task);
+	}
+}
+
+/// Runtime spawning the tasks on the global executor of smol and sleeping with its timers
+#[cfg(feature = "smol")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Smol;
+
+#[cfg(feature = "smol")]
+impl Runtime for Smol {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		smol::spawn(task).detach();
+	}
+
+	fn sleep(&self, dur: Duration) -> BoxFuture<'static, ()> {
+		let timer = smol::Timer::after(dur);
+		Box::pin(async move {
+			timer.await;
+		})
+	}
+
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+		smol::unblock(task).detach();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::{sync::mpsc, time::Instant};
+
+	/// Check that runtime runs a task sleeping 50ms and a blocking task
+	fn check_spawn_and_sleep(runtime: impl Runtime + Clone + 'static) {
+		let (tx, rx) = mpsc::channel();
+		let start = Instant::now();
+		let task_runtime = runtime.clone();
+		let task_tx = tx.clone();
+		runtime.spawn(Box::pin(async move {
+			task_runtime.sleep(Duration::from_millis(50)).await;
+			task_tx.send(start.elapsed()).unwrap();
+		}));
+		runtime.spawn_blocking(Box::new(move || {
+			tx.send(Duration::from_secs(3600)).unwrap()
+		}));
+		let mut elapsed = [
+			rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+			rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+		];
+		elapsed.sort();
+		assert!(elapsed[0] >= Duration::from_millis(50));
+		assert_eq!(elapsed[1], Duration::from_secs(3600));
+	}
+
+	#[test]
+	fn threads_spawn_and_sleep() {
+		check_spawn_and_sleep(Threads);
+	}
+
+	#[cfg(feature = "async-std")]
+	#[test]
+	fn async_std_spawn_and_sleep() {
+		check_spawn_and_sleep(AsyncStd);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[test]
+	fn tokio_spawn_and_sleep() {
+		let runtime = tokio::runtime::Builder::new_current_thread()
+			.enable_time()
+			.build()
+			.unwrap();
+		let handle = runtime.handle().clone();
+		// drive the tasks of the current thread runtime from another thread
+		let (stop, stopped) = mpsc::channel::<()>();
+		let driver = thread::spawn(move || {
+			runtime.block_on(async {
+				while stopped.try_recv().is_err() {
+					tokio::time::sleep(Duration::from_millis(10)).await;
+				}
+			})
+		});
+		check_spawn_and_sleep(Tokio::new(handle));
+		stop.send(()).unwrap();
+		driver.join().unwrap();
+	}
+
+	#[cfg(feature = "smol")]
+	#[test]
+	fn smol_spawn_and_sleep() {
+		check_spawn_and_sleep(Smol);
+	}
+}
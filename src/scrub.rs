@@ -0,0 +1,28 @@
+use serde_json::Value;
+use zeroize::Zeroize;
+
+/// Scrub all the strings of a json value
+pub(crate) fn scrub_value(value: &mut Value) {
+	match value {
+		Value::String(s) => s.zeroize(),
+		Value::Array(values) => values.iter_mut().for_each(scrub_value),
+		Value::Object(map) => map.values_mut().for_each(scrub_value),
+		_ => {}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn strings_are_scrubbed() {
+		let mut value = json!({"password": "hunter2", "hosts": ["db-0", "db-1"], "ttl": 3600});
+		scrub_value(&mut value);
+		assert_eq!(
+			value,
+			json!({"password": "", "hosts": ["", ""], "ttl": 3600})
+		);
+	}
+}
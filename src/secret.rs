@@ -1,12 +1,57 @@
-use crate::lease::Lease;
+use crate::{
+	error::{Error, Result},
+	lease::Lease,
+	redact::{parse_error, RedactedValue},
+	scrub::scrub_value,
+};
 
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
-use std::{convert::TryFrom, fmt, time::Duration};
+use std::{convert::TryFrom, fmt, ops::Deref, time::Duration};
+
+/// Metadata of the vault response a secret was extracted from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponseMetadata {
+	pub request_id: String,
+	pub renewable: bool,
+	/// lease duration as returned by vault
+	pub lease_duration: Duration,
+	pub warnings: Vec<String>,
+}
+
+/// response metadata as returned by vault
+#[derive(Default, Deserialize)]
+struct RawMetadata {
+	#[serde(default)]
+	request_id: String,
+	#[serde(default)]
+	renewable: bool,
+	#[serde(default)]
+	lease_duration: u64,
+	#[serde(default)]
+	warnings: Option<Vec<String>>,
+}
+
+impl ResponseMetadata {
+	/// extract the metadata of a vault response (missing fields take their default value)
+	pub fn from_value(value: &Value) -> Self {
+		let raw = RawMetadata::deserialize(value).unwrap_or_default();
+		Self {
+			request_id: raw.request_id,
+			renewable: raw.renewable,
+			lease_duration: Duration::from_secs(raw.lease_duration),
+			warnings: raw.warnings.unwrap_or_default(),
+		}
+	}
+}
 
 /// A secret is a json value tied to an optional lease
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct Secret {
 	pub value: Value,
+	/// id of the lease for dynamic secrets
+	pub lease_id: Option<String>,
+	pub metadata: ResponseMetadata,
 	lease: Option<Lease>,
 }
 
@@ -15,6 +60,8 @@ impl Secret {
 	pub fn new(value: Value, dur: Option<Duration>) -> Self {
 		Self {
 			value,
+			lease_id: None,
+			metadata: ResponseMetadata::default(),
 			lease: dur.and_then(|dur| Some(Lease::new(dur))),
 		}
 	}
@@ -58,6 +105,114 @@ impl Secret {
 	}
 }
 
+impl Secret {
+	/// deserialize the value of the secret into T keeping the lease
+	pub fn deserialize<T: DeserializeOwned>(mut self) -> Result<TypedSecret<T>> {
+		Ok(TypedSecret {
+			value: serde_json::from_value(self.value.take()).map_err(parse_error)?,
+			lease_id: self.lease_id.take(),
+			metadata: std::mem::take(&mut self.metadata),
+			lease: self.lease.take(),
+		})
+	}
+}
+
+/// A secret whose json value has been deserialized into T. The credentials of the dynamic
+/// secrets engines (ex: `database_creds`) are returned as typed secrets keeping the vault lease,
+/// which can be renewed in the background with `LeaseManager::manage`
+pub struct TypedSecret<T> {
+	pub value: T,
+	/// id of the lease for dynamic secrets
+	pub lease_id: Option<String>,
+	pub metadata: ResponseMetadata,
+	lease: Option<Lease>,
+}
+
+impl<T> TypedSecret<T> {
+	/// check if the secret is valid
+	pub fn is_valid(&self) -> bool {
+		self.lease.is_none() || self.lease.as_ref().filter(|l| l.is_valid()).is_some()
+	}
+
+	/// check if the secret need to be renewed
+	pub fn to_renew(&self) -> bool {
+		self.lease.as_ref().filter(|l| l.to_renew()).is_some()
+	}
+
+	pub fn duration(&self) -> Option<Duration> {
+		self.lease
+			.as_ref()
+			.map(|l| l.lease_duration)
+			.filter(|d| *d != Duration::from_secs(0))
+	}
+
+	pub fn renew_delay(&self) -> Option<Duration> {
+		self.lease
+			.as_ref()
+			.map(|l| l.renew_delay)
+			.filter(|d| *d != Duration::from_secs(0))
+	}
+}
+
+/// Give access to the fields of the value
+impl<T> Deref for TypedSecret<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+/// Deserialize the value of a secret keeping its lease
+impl<T: DeserializeOwned> TryFrom<Secret> for TypedSecret<T> {
+	type Error = Error;
+
+	fn try_from(secret: Secret) -> Result<Self> {
+		secret.deserialize()
+	}
+}
+
+/// The value is redacted, only its keys and the length of its values are shown
+impl fmt::Debug for Secret {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Secret")
+			.field("value", &RedactedValue(&self.value))
+			.field("lease_id", &self.lease_id)
+			.field("metadata", &self.metadata)
+			.field("lease", &self.lease)
+			.finish()
+	}
+}
+
+/// Display the lease of the secret, never its value
+impl fmt::Display for Secret {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.lease_id {
+			Some(ref lease_id) => write!(f, "secret with lease {}", lease_id),
+			None => write!(f, "secret"),
+		}
+	}
+}
+
+/// The value is redacted
+impl<T> fmt::Debug for TypedSecret<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TypedSecret")
+			.field("value", &format_args!("<redacted>"))
+			.field("lease_id", &self.lease_id)
+			.field("metadata", &self.metadata)
+			.field("lease", &self.lease)
+			.finish()
+	}
+}
+
+/// Scrub the value from memory
+impl Drop for Secret {
+	fn drop(&mut self) {
+		scrub_value(&mut self.value);
+	}
+}
+
 /// Compare secret by their inner value
 impl PartialEq for Secret {
 	fn eq(&self, other: &Self) -> bool {
@@ -96,6 +251,14 @@ where
 	}
 }
 
+/// Return the raw value of a field of a secret: strings without quotes and other values as json
+pub(crate) fn raw_value(value: &Value) -> String {
+	match value {
+		Value::String(s) => s.clone(),
+		value => value.to_string(),
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -121,6 +284,40 @@ mod test {
 		assert_eq!(secret.is_valid(), true)
 	}
 
+	#[test]
+	fn deserialize_keeps_lease() {
+		#[derive(serde::Deserialize)]
+		struct Creds {
+			username: String,
+		}
+
+		let secret = Secret::new(
+			serde_json::json!({"username": "user", "password": "pass"}),
+			Some(Duration::from_secs(10)),
+		);
+		let creds = secret.deserialize::<Creds>().unwrap();
+		assert_eq!(creds.value.username, "user");
+		assert_eq!(creds.duration(), Some(Duration::from_secs(10)));
+	}
+
+	#[test]
+	fn metadata_from_response() {
+		let metadata = ResponseMetadata::from_value(&serde_json::json!({
+			"request_id": "5e1d1b8c-2a1d-9c0e-5b2b-5a5c4a1e6a2b",
+			"lease_id": "database/creds/readonly/2f6a614c",
+			"renewable": true,
+			"lease_duration": 3600,
+			"data": {},
+			"warnings": null
+		}));
+		assert!(metadata.renewable && metadata.warnings.is_empty());
+		assert_eq!(metadata.lease_duration, Duration::from_secs(3600));
+		assert_eq!(
+			ResponseMetadata::from_value(&Value::Null),
+			ResponseMetadata::default()
+		);
+	}
+
 	#[test]
 	fn with_expired_lease_is_invalid() {
 		let secret = Secret::new(
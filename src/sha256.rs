@@ -0,0 +1,58 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Compute the sha256 digest of data
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+	Sha256::digest(data).into()
+}
+
+/// Compute the hmac-sha256 of data with key
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+	// hmac accepts keys of any length
+	let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac key");
+	mac.update(data);
+	mac.finalize().into_bytes().into()
+}
+
+/// Encode bytes in lower case hexadecimal
+pub fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn sha256_digests() {
+		assert_eq!(
+			hex(&sha256(b"")),
+			"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+		);
+		assert_eq!(
+			hex(&sha256(b"abc")),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+		assert_eq!(
+			hex(&sha256(
+				b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+			)),
+			"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+		);
+	}
+
+	#[test]
+	fn hmac_sha256_rfc4231() {
+		assert_eq!(
+			hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+			"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+		);
+		assert_eq!(
+			hex(&hmac_sha256(
+				&[0xaa; 131],
+				b"Test Using Larger Than Block-Size Key - Hash Key First"
+			)),
+			"60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+		);
+	}
+}
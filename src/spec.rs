@@ -0,0 +1,211 @@
+use crate::{
+	client::VaultClient,
+	env::{export, EnvMapping},
+	error::{Error, Result},
+	secret::Secret,
+	sink::{FileSink, Format},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Engine a secret is read from. In json `{"type": "kv2", "mount": "secret"}`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Engine {
+	/// any engine read with `get_secret`, the path including the mount (ex: `database/creds/app`)
+	#[default]
+	Generic,
+	/// kv version 1 engine mounted at mount
+	Kv1 { mount: String },
+	/// kv version 2 engine mounted at mount (the data of the latest version is read)
+	Kv2 { mount: String },
+}
+
+/// Where a secret goes. In json `{"type": "file", "path": "/etc/app/db.env", "format": "dotenv"}`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Output {
+	/// the secret is only returned
+	#[default]
+	None,
+	/// the secret is rendered to the file at path (see `FileSink`)
+	File {
+		path: String,
+		#[serde(default)]
+		format: Format,
+		/// unix permissions of the file (0o600 by default)
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		mode: Option<u32>,
+	},
+	/// the fields are exported as environment variables of the process (see `EnvMapping`). As
+	/// with `export`, it must only be written before starting threads that read the environment
+	Env {
+		#[serde(default)]
+		prefix: String,
+	},
+}
+
+/// Declarative description of a secret to fetch and where to put it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretSpec {
+	/// role whose token is used to fetch the secret
+	pub role: String,
+	#[serde(default)]
+	pub engine: Engine,
+	pub path: String,
+	/// only keep these fields of the secret (all by default)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub keys: Option<Vec<String>>,
+	#[serde(default)]
+	pub output: Output,
+	/// interval in seconds between two fetches of a secret without lease, for the watchers and
+	/// `exec::run`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub refresh: Option<u64>,
+}
+
+impl Output {
+	/// Return the sink of a file output
+	pub fn sink(&self) -> Option<FileSink> {
+		match self {
+			Output::File { path, format, mode } => {
+				let sink = FileSink::new(path, format.clone());
+				Some(match mode {
+					Some(mode) => sink.mode(*mode),
+					None => sink,
+				})
+			}
+			_ => None,
+		}
+	}
+
+	/// Put secret in the output. An `Env` output sets the environment of the whole process with
+	/// `export`, so it must only be written at start up, before starting other threads (which
+	/// excludes writing it from a watcher or a provider thread)
+	pub fn write(&self, secret: &Secret) -> Result<()> {
+		match self {
+			Output::None => Ok(()),
+			Output::File { .. } => self.sink().map_or(Ok(()), |sink| sink.write(secret)),
+			Output::Env { prefix } => {
+				export(&EnvMapping::new().prefix(prefix).apply(secret)?);
+				Ok(())
+			}
+		}
+	}
+}
+
+impl SecretSpec {
+	/// Keep the selected keys of secret
+	fn select(&self, mut secret: Secret) -> Result<Secret> {
+		let keys = match self.keys {
+			Some(ref keys) => keys,
+			None => return Ok(secret),
+		};
+		let map = secret
+			.value
+			.as_object_mut()
+			.ok_or_else(|| Error::TemplateError("secret is not an object".to_owned()))?;
+		let mut selected = Map::new();
+		for key in keys.iter() {
+			let value = map
+				.remove(key)
+				.ok_or_else(|| Error::TemplateError(format!("no field \"{}\" in secret", key)))?;
+			selected.insert(key.clone(), value);
+		}
+		*map = selected;
+		Ok(secret)
+	}
+}
+
+impl VaultClient {
+	/// Log in the role of spec and fetch its secret bypassing the cache, keeping only the keys
+	/// of the spec. The secret is not put in the output of the spec (see `Output::write`)
+	pub fn fetch_spec(&self, spec: &SecretSpec) -> Result<Secret> {
+		self.login(&spec.role)?;
+		let secret = match spec.engine {
+			Engine::Generic => self.get_secret_uncached(&spec.role, "GET", &spec.path, None)?,
+			Engine::Kv1 { ref mount } => Secret::new(
+				Value::Object(self.kv1_get(&spec.role, mount, &spec.path)?),
+				None,
+			),
+			Engine::Kv2 { ref mount } => Secret::new(
+				Value::Object(self.kv2_get(&spec.role, mount, &spec.path)?.data),
+				None,
+			),
+		};
+		spec.select(secret)
+	}
+
+	/// Log in and fetch asynchronously the secret of spec
+	pub async fn fetch_spec_async(&self, spec: &SecretSpec) -> Result<Secret> {
+		self.login_async(&spec.role).await?;
+		let secret = match spec.engine {
+			Engine::Generic => {
+				self.get_secret_uncached_async(&spec.role, "GET", &spec.path, None)
+					.await?
+			}
+			Engine::Kv1 { ref mount } => Secret::new(
+				Value::Object(self.kv1_get_async(&spec.role, mount, &spec.path).await?),
+				None,
+			),
+			Engine::Kv2 { ref mount } => Secret::new(
+				Value::Object(
+					self.kv2_get_async(&spec.role, mount, &spec.path)
+						.await?
+						.data,
+				),
+				None,
+			),
+		};
+		spec.select(secret)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn spec_from_json() {
+		let spec: SecretSpec = serde_json::from_value(json!({
+			"role": "app",
+			"engine": {"type": "kv2", "mount": "secret"},
+			"path": "app/db",
+			"keys": ["password"],
+			"output": {"type": "file", "path": "/etc/app/db.env", "format": "dotenv"}
+		}))
+		.unwrap();
+		assert_eq!(
+			spec.engine,
+			Engine::Kv2 {
+				mount: "secret".to_owned()
+			}
+		);
+		assert_eq!(spec.output.sink().unwrap().format, Format::Dotenv);
+		let minimal: SecretSpec =
+			serde_json::from_value(json!({"role": "app", "path": "database/creds/app"})).unwrap();
+		assert_eq!(minimal.engine, Engine::Generic);
+		assert_eq!(minimal.output, Output::None);
+		assert_eq!(
+			serde_json::to_value(&minimal).unwrap(),
+			json!({"role": "app", "engine": {"type": "generic"}, "path": "database/creds/app", "output": {"type": "none"}})
+		);
+	}
+
+	#[test]
+	fn keys_are_selected() {
+		let mut spec: SecretSpec =
+			serde_json::from_value(json!({"role": "app", "path": "secret/app"})).unwrap();
+		let secret = || Secret::new(json!({"username": "app", "password": "hunter2"}), None);
+		assert_eq!(spec.select(secret()).unwrap(), secret());
+		spec.keys = Some(vec!["password".to_owned()]);
+		assert_eq!(
+			spec.select(secret()).unwrap().value,
+			json!({"password": "hunter2"})
+		);
+		spec.keys = Some(vec!["other".to_owned()]);
+		assert!(spec.select(secret()).is_err());
+	}
+}
@@ -0,0 +1,169 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+	redact::Redacted,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Optional parameters of a public key signature
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SignOptions {
+	/// comma separated list of users or hosts the certificate is valid for
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub valid_principals: Option<String>,
+	/// `user` or `host`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cert_type: Option<String>,
+	/// requested ttl (ex: `30m`)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ttl: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub key_id: Option<String>,
+}
+
+/// A public key signed by the ssh engine
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedKey {
+	/// the ssh certificate in openssh format
+	pub signed_key: String,
+	pub serial_number: String,
+}
+
+/// A one-time password issued by the ssh engine
+#[derive(Clone, Deserialize)]
+pub struct SshOtp {
+	pub key: String,
+	pub key_type: String,
+	pub username: String,
+	pub ip: String,
+	#[serde(default)]
+	pub port: u16,
+}
+
+impl fmt::Debug for SshOtp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SshOtp")
+			.field("key", &Redacted(&self.key))
+			.field("key_type", &self.key_type)
+			.field("username", &self.username)
+			.field("ip", &self.ip)
+			.field("port", &self.port)
+			.finish()
+	}
+}
+
+/// Body of a public key signature
+fn sign_body(public_key: &str, options: &SignOptions) -> String {
+	let mut body = json!(options);
+	body["public_key"] = Value::from(public_key);
+	body.to_string()
+}
+
+/// Extract the data of an ssh engine response
+fn data_from_value<T: serde::de::DeserializeOwned>(mut value: Value) -> Result<T> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Sign public_key with the role ssh_role of the ssh engine mounted at mount and return the
+	/// certificate
+	pub fn ssh_sign_key(
+		&self,
+		role: &str,
+		mount: &str,
+		ssh_role: &str,
+		public_key: &str,
+		options: &SignOptions,
+	) -> Result<SignedKey> {
+		let request = self.vault_request(
+			"POST",
+			&format!("{}/sign/{}", mount.trim_matches('/'), ssh_role),
+			Some(&self.token(role)?),
+			sign_body(public_key, options),
+		)?;
+		data_from_value(self.send(request)?)
+	}
+
+	/// Sign asynchronously public_key with the role ssh_role of the ssh engine
+	pub async fn ssh_sign_key_async(
+		&self,
+		role: &str,
+		mount: &str,
+		ssh_role: &str,
+		public_key: &str,
+		options: &SignOptions,
+	) -> Result<SignedKey> {
+		let request = self.vault_request(
+			"POST",
+			&format!("{}/sign/{}", mount.trim_matches('/'), ssh_role),
+			Some(&self.token_async(role).await?),
+			sign_body(public_key, options),
+		)?;
+		data_from_value(self.send_async(request).await?)
+	}
+
+	/// Create a one-time password for the host ip with the otp role ssh_role of the ssh engine
+	/// mounted at mount
+	pub fn ssh_otp(&self, role: &str, mount: &str, ssh_role: &str, ip: &str) -> Result<SshOtp> {
+		let request = self.vault_request(
+			"POST",
+			&format!("{}/creds/{}", mount.trim_matches('/'), ssh_role),
+			Some(&self.token(role)?),
+			json!({ "ip": ip }).to_string(),
+		)?;
+		data_from_value(self.send(request)?)
+	}
+
+	/// Create asynchronously a one-time password for the host ip with the otp role ssh_role
+	pub async fn ssh_otp_async(
+		&self,
+		role: &str,
+		mount: &str,
+		ssh_role: &str,
+		ip: &str,
+	) -> Result<SshOtp> {
+		let request = self.vault_request(
+			"POST",
+			&format!("{}/creds/{}", mount.trim_matches('/'), ssh_role),
+			Some(&self.token_async(role).await?),
+			json!({ "ip": ip }).to_string(),
+		)?;
+		data_from_value(self.send_async(request).await?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn sign_body_skips_unset_options() {
+		let options = SignOptions {
+			valid_principals: Some("ubuntu".to_owned()),
+			..Default::default()
+		};
+		assert_eq!(
+			sign_body("ssh-ed25519 AAAA", &options),
+			r#"{"public_key":"ssh-ed25519 AAAA","valid_principals":"ubuntu"}"#
+		);
+	}
+
+	#[test]
+	fn otp_from_response() {
+		let otp: SshOtp = data_from_value(json!({
+			"data": {
+				"ip": "10.0.0.5",
+				"key": "2f7e25a2-24c9-4b7b-0d35-27d5e5203a5c",
+				"key_type": "otp",
+				"port": 22,
+				"username": "ubuntu"
+			}
+		}))
+		.unwrap();
+		assert_eq!(otp.port, 22);
+		assert_eq!(otp.username, "ubuntu");
+	}
+}
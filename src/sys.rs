@@ -0,0 +1,466 @@
+use crate::{
+	base64,
+	client::VaultClient,
+	error::{Error, Result},
+	redact::{Redacted, RedactedList},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use zeroize::ZeroizeOnDrop;
+
+/// Query making `sys/health` answer 200 whatever the state of the server, so that the state can
+/// be read from the body instead of the status code
+const HEALTH_QUERY: &str = "standbyok=true&perfstandbyok=true&sealedcode=200&uninitcode=200&drsecondarycode=200&performancestandbycode=200";
+
+/// Health of a vault server as returned by `sys/health`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HealthStatus {
+	pub initialized: bool,
+	pub sealed: bool,
+	pub standby: bool,
+	#[serde(default)]
+	pub performance_standby: bool,
+	#[serde(default)]
+	pub replication_performance_mode: Option<String>,
+	#[serde(default)]
+	pub replication_dr_mode: Option<String>,
+	/// server time in seconds since the unix epoch
+	pub server_time_utc: u64,
+	pub version: String,
+	#[serde(default)]
+	pub cluster_name: Option<String>,
+	#[serde(default)]
+	pub cluster_id: Option<String>,
+}
+
+impl HealthStatus {
+	/// check if the server is initialized, unsealed and active (not a standby)
+	pub fn is_active(&self) -> bool {
+		self.initialized && !self.sealed && !self.standby
+	}
+}
+
+/// Seal status of a vault server as returned by `sys/seal-status`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SealStatus {
+	/// type of seal (shamir, awskms, transit...)
+	#[serde(rename = "type")]
+	pub seal_type: String,
+	pub initialized: bool,
+	pub sealed: bool,
+	/// number of key shares required to unseal
+	pub t: u32,
+	/// number of key shares
+	pub n: u32,
+	/// number of key shares already given for the current unseal
+	pub progress: u32,
+	#[serde(default)]
+	pub nonce: String,
+	pub version: String,
+	#[serde(default)]
+	pub migration: bool,
+	#[serde(default)]
+	pub recovery_seal: bool,
+	#[serde(default)]
+	pub storage_type: Option<String>,
+	#[serde(default)]
+	pub cluster_name: Option<String>,
+	#[serde(default)]
+	pub cluster_id: Option<String>,
+}
+
+/// Parameters of the initialization of a vault server
+#[derive(Debug, Clone, Serialize)]
+pub struct InitParams {
+	/// number of unseal key shares
+	pub secret_shares: u32,
+	/// number of key shares required to unseal
+	pub secret_threshold: u32,
+	/// pgp public keys (base64) used to encrypt each key share
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pgp_keys: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub root_token_pgp_key: Option<String>,
+	/// recovery shares and threshold for auto-unsealed servers
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub recovery_shares: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub recovery_threshold: Option<u32>,
+}
+
+impl InitParams {
+	pub fn new(secret_shares: u32, secret_threshold: u32) -> Self {
+		Self {
+			secret_shares,
+			secret_threshold,
+			pgp_keys: None,
+			root_token_pgp_key: None,
+			recovery_shares: None,
+			recovery_threshold: None,
+		}
+	}
+}
+
+/// Keys and root token of a newly initialized vault server
+#[derive(Clone, Deserialize, ZeroizeOnDrop)]
+pub struct InitResponse {
+	/// unseal key shares in hexadecimal
+	pub keys: Vec<String>,
+	pub keys_base64: Vec<String>,
+	#[serde(default)]
+	pub recovery_keys: Vec<String>,
+	pub root_token: String,
+}
+
+impl fmt::Debug for InitResponse {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("InitResponse")
+			.field("keys", &RedactedList(&self.keys))
+			.field("keys_base64", &RedactedList(&self.keys_base64))
+			.field("recovery_keys", &RedactedList(&self.recovery_keys))
+			.field("root_token", &Redacted(&self.root_token))
+			.finish()
+	}
+}
+
+/// State of a root token generation as returned by `sys/generate-root/attempt`
+#[derive(Clone, PartialEq, Deserialize)]
+pub struct GenerateRootStatus {
+	pub started: bool,
+	#[serde(default)]
+	pub nonce: String,
+	/// number of key shares already given
+	pub progress: u32,
+	/// number of key shares required
+	pub required: u32,
+	pub complete: bool,
+	/// root token xored with the otp once complete
+	#[serde(default)]
+	pub encoded_token: String,
+	#[serde(default)]
+	pub encoded_root_token: String,
+	#[serde(default)]
+	pub pgp_fingerprint: String,
+	/// one-time password generated by vault when the generation starts
+	#[serde(default)]
+	pub otp: String,
+	#[serde(default)]
+	pub otp_length: usize,
+}
+
+impl fmt::Debug for GenerateRootStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GenerateRootStatus")
+			.field("started", &self.started)
+			.field("nonce", &self.nonce)
+			.field("progress", &self.progress)
+			.field("required", &self.required)
+			.field("complete", &self.complete)
+			.field("encoded_token", &Redacted(&self.encoded_token))
+			.field("encoded_root_token", &Redacted(&self.encoded_root_token))
+			.field("pgp_fingerprint", &self.pgp_fingerprint)
+			.field("otp", &Redacted(&self.otp))
+			.field("otp_length", &self.otp_length)
+			.finish()
+	}
+}
+
+impl GenerateRootStatus {
+	/// Decode the root token of a complete generation with the otp given when it started
+	pub fn decode_token(&self, otp: &str) -> Result<String> {
+		let encoded = if self.encoded_token.is_empty() {
+			&self.encoded_root_token
+		} else {
+			&self.encoded_token
+		};
+		let token = base64::decode(encoded).ok_or(Error::InvalidBase64)?;
+		if token.len() != otp.len() {
+			return Err(Error::InvalidOtp);
+		}
+		let token: Vec<u8> = token.iter().zip(otp.bytes()).map(|(t, o)| t ^ o).collect();
+		String::from_utf8(token).map_err(|_| Error::InvalidOtp)
+	}
+}
+
+/// Deserialize a vault response that is not wrapped in a data field
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+	serde_json::from_value(value).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Return the health of the vault server. No login is needed and a sealed, uninitialized or
+	/// standby server is not considered as an error
+	pub fn health(&self) -> Result<HealthStatus> {
+		let request = self.vault_request(
+			"GET",
+			&format!("sys/health?{}", HEALTH_QUERY),
+			None,
+			String::new(),
+		)?;
+		from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the health of the vault server
+	pub async fn health_async(&self) -> Result<HealthStatus> {
+		let request = self.vault_request(
+			"GET",
+			&format!("sys/health?{}", HEALTH_QUERY),
+			None,
+			String::new(),
+		)?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Return the seal status of the vault server. No login is needed
+	pub fn seal_status(&self) -> Result<SealStatus> {
+		let request = self.vault_request("GET", "sys/seal-status", None, String::new())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the seal status of the vault server
+	pub async fn seal_status_async(&self) -> Result<SealStatus> {
+		let request = self.vault_request("GET", "sys/seal-status", None, String::new())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Initialize a new vault server and return its unseal keys and root token. No login is
+	/// needed
+	pub fn init(&self, params: &InitParams) -> Result<InitResponse> {
+		let request = self.vault_request("PUT", "sys/init", None, json!(params).to_string())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Initialize asynchronously a new vault server
+	pub async fn init_async(&self, params: &InitParams) -> Result<InitResponse> {
+		let request = self.vault_request("PUT", "sys/init", None, json!(params).to_string())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Give an unseal key share and return the seal status. No login is needed
+	pub fn unseal(&self, key: &str) -> Result<SealStatus> {
+		let request =
+			self.vault_request("PUT", "sys/unseal", None, json!({ "key": key }).to_string())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Give asynchronously an unseal key share and return the seal status
+	pub async fn unseal_async(&self, key: &str) -> Result<SealStatus> {
+		let request =
+			self.vault_request("PUT", "sys/unseal", None, json!({ "key": key }).to_string())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Forget the key shares given so far to unseal the server
+	pub fn reset_unseal(&self) -> Result<SealStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/unseal",
+			None,
+			json!({ "reset": true }).to_string(),
+		)?;
+		from_value(self.send(request)?)
+	}
+
+	/// Forget asynchronously the key shares given so far to unseal the server
+	pub async fn reset_unseal_async(&self) -> Result<SealStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/unseal",
+			None,
+			json!({ "reset": true }).to_string(),
+		)?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Seal the vault server with the token of role
+	pub fn seal(&self, role: &str) -> Result<()> {
+		self.request(role, "PUT", "sys/seal", None).map(|_| ())
+	}
+
+	/// Seal asynchronously the vault server with the token of role
+	pub async fn seal_async(&self, role: &str) -> Result<()> {
+		self.request_async(role, "PUT", "sys/seal", None)
+			.await
+			.map(|_| ())
+	}
+
+	/// Return the state of the root token generation. No login is needed
+	pub fn generate_root_status(&self) -> Result<GenerateRootStatus> {
+		let request =
+			self.vault_request("GET", "sys/generate-root/attempt", None, String::new())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the state of the root token generation
+	pub async fn generate_root_status_async(&self) -> Result<GenerateRootStatus> {
+		let request =
+			self.vault_request("GET", "sys/generate-root/attempt", None, String::new())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Start a root token generation. The returned status holds the nonce to give with each
+	/// key share and the otp needed to decode the token
+	pub fn generate_root_start(&self) -> Result<GenerateRootStatus> {
+		let request =
+			self.vault_request("PUT", "sys/generate-root/attempt", None, String::new())?;
+		from_value(self.send(request)?)
+	}
+
+	/// Start asynchronously a root token generation
+	pub async fn generate_root_start_async(&self) -> Result<GenerateRootStatus> {
+		let request =
+			self.vault_request("PUT", "sys/generate-root/attempt", None, String::new())?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Give an unseal key share to the root token generation identified by nonce. The status is
+	/// complete with an encoded token once enough shares were given
+	pub fn generate_root_update(&self, key: &str, nonce: &str) -> Result<GenerateRootStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/generate-root/update",
+			None,
+			json!({ "key": key, "nonce": nonce }).to_string(),
+		)?;
+		from_value(self.send(request)?)
+	}
+
+	/// Give asynchronously an unseal key share to the root token generation identified by nonce
+	pub async fn generate_root_update_async(
+		&self,
+		key: &str,
+		nonce: &str,
+	) -> Result<GenerateRootStatus> {
+		let request = self.vault_request(
+			"PUT",
+			"sys/generate-root/update",
+			None,
+			json!({ "key": key, "nonce": nonce }).to_string(),
+		)?;
+		from_value(self.send_async(request).await?)
+	}
+
+	/// Cancel the root token generation
+	pub fn generate_root_cancel(&self) -> Result<()> {
+		let request =
+			self.vault_request("DELETE", "sys/generate-root/attempt", None, String::new())?;
+		self.send(request).map(|_| ())
+	}
+
+	/// Cancel asynchronously the root token generation
+	pub async fn generate_root_cancel_async(&self) -> Result<()> {
+		let request =
+			self.vault_request("DELETE", "sys/generate-root/attempt", None, String::new())?;
+		self.send_async(request).await.map(|_| ())
+	}
+
+	/// Generate a root token giving all the keys, and return it decoded. The generation is
+	/// cancelled if the keys are not enough
+	pub fn generate_root(&self, keys: &[&str]) -> Result<String> {
+		let mut status = self.generate_root_start()?;
+		let otp = status.otp.clone();
+		for key in keys.iter() {
+			status = self.generate_root_update(key, &status.nonce)?;
+			if status.complete {
+				return status.decode_token(&otp);
+			}
+		}
+		self.generate_root_cancel()?;
+		Err(Error::MissingField("encoded_token"))
+	}
+
+	/// Generate asynchronously a root token giving all the keys, and return it decoded
+	pub async fn generate_root_async(&self, keys: &[&str]) -> Result<String> {
+		let mut status = self.generate_root_start_async().await?;
+		let otp = status.otp.clone();
+		for key in keys.iter() {
+			status = self.generate_root_update_async(key, &status.nonce).await?;
+			if status.complete {
+				return status.decode_token(&otp);
+			}
+		}
+		self.generate_root_cancel_async().await?;
+		Err(Error::MissingField("encoded_token"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn init_response_debug_is_redacted() {
+		let init: InitResponse = from_value(json!({
+			"keys": ["1c2b3a"],
+			"keys_base64": ["HCs6"],
+			"root_token": "s.root"
+		}))
+		.unwrap();
+		let debug = format!("{:?}", init);
+		assert!(!debug.contains("1c2b3a") && !debug.contains("HCs6") && !debug.contains("s.root"));
+	}
+
+	#[test]
+	fn health_from_response() {
+		let health: HealthStatus = from_value(json!({
+			"initialized": true,
+			"sealed": false,
+			"standby": true,
+			"performance_standby": false,
+			"replication_performance_mode": "disabled",
+			"replication_dr_mode": "disabled",
+			"server_time_utc": 1516639589,
+			"version": "1.9.0",
+			"cluster_name": "vault-cluster-3bd69ca2",
+			"cluster_id": "00af5aa8-c87d-b5fc-e82e-97cd8dfaf731"
+		}))
+		.unwrap();
+		assert!(health.initialized && !health.is_active());
+	}
+
+	#[test]
+	fn root_token_from_otp() {
+		let status: GenerateRootStatus = from_value(json!({
+			"started": true,
+			"nonce": "2dbd10f1-8528-6246-09e7-82b25b8aba63",
+			"progress": 3,
+			"required": 3,
+			"complete": true,
+			"encoded_token": "PhtKQVVpQgdgXnx6XAsRUTkIL1A2WB0/CF47",
+			"encoded_root_token": "",
+			"pgp_fingerprint": ""
+		}))
+		.unwrap();
+		assert_eq!(
+			status.decode_token("Vm9oeXp4TkJMd2p3ZlJ6Q0tUc2V").unwrap(),
+			"hvs.0123456789abcdefghijklm"
+		);
+		assert!(matches!(
+			status.decode_token("short"),
+			Err(Error::InvalidOtp)
+		));
+	}
+
+	#[test]
+	fn seal_status_from_response() {
+		let status: SealStatus = from_value(json!({
+			"type": "shamir",
+			"initialized": true,
+			"sealed": true,
+			"t": 3,
+			"n": 5,
+			"progress": 2,
+			"nonce": "",
+			"version": "1.9.0",
+			"migration": false,
+			"recovery_seal": false,
+			"storage_type": "raft"
+		}))
+		.unwrap();
+		assert_eq!((status.t, status.n, status.progress), (3, 5, 2));
+		assert_eq!(status.seal_type, "shamir");
+	}
+}
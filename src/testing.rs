@@ -0,0 +1,309 @@
+use crate::{
+	api::{BoxFuture, VaultApi},
+	auth::Auth,
+	error::{Error, Result},
+	secret::Secret,
+};
+
+use http::StatusCode;
+use serde_json::Value;
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Mutex, MutexGuard},
+	time::Duration,
+};
+
+/// a secret stored in the fake
+#[derive(Debug, Clone)]
+struct Stored {
+	value: Value,
+	/// lease duration of dynamic secrets
+	ttl: Option<Duration>,
+}
+
+/// a token issued by the fake
+#[derive(Debug)]
+struct Token {
+	token: String,
+	/// fake time after which the token is expired
+	expire: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	/// fake time elapsed since the creation
+	now: Duration,
+	secrets: HashMap<String, Stored>,
+	tokens: HashMap<String, Token>,
+	/// scripted failures by path (`auth/<role>/login` for logins)
+	failures: HashMap<String, VecDeque<StatusCode>>,
+	/// requests received (`GET secret/app`, `LOGIN app`...)
+	requests: Vec<String>,
+	/// number of tokens and leases issued
+	issued: usize,
+}
+
+/// In-memory fake of a vault server implementing `VaultApi` with secrets, token and lease ttls
+/// and scripted failures, to test code using this crate without a vault server. Time is
+/// simulated: tokens only expire when the fake clock is advanced with `advance`
+#[derive(Debug, Default)]
+pub struct FakeVault {
+	token_ttl: Option<Duration>,
+	state: Mutex<State>,
+}
+
+impl FakeVault {
+	/// create a fake vault issuing tokens without ttl
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// issue tokens valid during ttl of fake time
+	pub fn token_ttl(mut self, ttl: Duration) -> Self {
+		self.token_ttl = Some(ttl);
+		self
+	}
+
+	fn lock(&self) -> MutexGuard<'_, State> {
+		self.state.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// store a static secret at path
+	pub fn insert(&self, path: &str, value: Value) {
+		self.lock().secrets.insert(
+			path.trim_matches('/').to_owned(),
+			Stored { value, ttl: None },
+		);
+	}
+
+	/// store a dynamic secret at path, returned with a new lease of ttl at each read
+	pub fn insert_leased(&self, path: &str, value: Value, ttl: Duration) {
+		self.lock().secrets.insert(
+			path.trim_matches('/').to_owned(),
+			Stored {
+				value,
+				ttl: Some(ttl),
+			},
+		);
+	}
+
+	/// remove the secret at path
+	pub fn remove(&self, path: &str) {
+		self.lock().secrets.remove(path.trim_matches('/'));
+	}
+
+	/// make the next request to path fail with status, after the failures already scripted.
+	/// Logins of a role use the path `auth/<role>/login`
+	pub fn fail(&self, path: &str, status: StatusCode) {
+		self.lock()
+			.failures
+			.entry(path.trim_matches('/').to_owned())
+			.or_default()
+			.push_back(status);
+	}
+
+	/// advance the fake clock by dur, expiring the tokens whose ttl has elapsed
+	pub fn advance(&self, dur: Duration) {
+		self.lock().now += dur;
+	}
+
+	/// return the requests received so far (`LOGIN <role>`, `RENEW <role>`, `LOGOUT <role>` or
+	/// `<method> <path>`)
+	pub fn requests(&self) -> Vec<String> {
+		self.lock().requests.clone()
+	}
+
+	/// check if role has a valid token
+	pub fn is_logged(&self, role: &str) -> bool {
+		let state = self.lock();
+		state
+			.tokens
+			.get(role)
+			.is_some_and(|token| token.is_valid(state.now))
+	}
+}
+
+impl Token {
+	fn is_valid(&self, now: Duration) -> bool {
+		self.expire.is_none_or(|expire| now < expire)
+	}
+}
+
+impl State {
+	/// record a request to path and return the scripted failure if any
+	fn request(&mut self, request: String, path: &str) -> Result<()> {
+		self.requests.push(request);
+		match self
+			.failures
+			.get_mut(path)
+			.and_then(|failures| failures.pop_front())
+		{
+			Some(status) => Err(Error::from_status(
+				status,
+				path,
+				vec!["scripted failure".to_owned()],
+			)),
+			None => Ok(()),
+		}
+	}
+
+	/// check that role has a valid token to request path
+	fn check_token(&self, role: &str, path: &str) -> Result<()> {
+		match self.tokens.get(role) {
+			None => Err(Error::NotLogged),
+			Some(token) if token.is_valid(self.now) => Ok(()),
+			Some(_) => Err(Error::from_status(
+				StatusCode::FORBIDDEN,
+				path,
+				vec!["permission denied".to_owned()],
+			)),
+		}
+	}
+
+	fn issue(&mut self, role: &str, ttl: Option<Duration>) -> Auth {
+		self.issued += 1;
+		let token = format!("hvs.fake.{}", self.issued);
+		self.tokens.insert(
+			role.to_owned(),
+			Token {
+				token: token.clone(),
+				expire: ttl.map(|ttl| self.now + ttl),
+			},
+		);
+		Auth::new(&token, ttl)
+	}
+}
+
+impl VaultApi for FakeVault {
+	fn login(&self, role: &str) -> Result<Auth> {
+		let mut state = self.lock();
+		state.request(format!("LOGIN {}", role), &format!("auth/{}/login", role))?;
+		Ok(state.issue(role, self.token_ttl))
+	}
+
+	fn login_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+		Box::pin(async move { self.login(role) })
+	}
+
+	fn logout(&self, role: &str) -> Result<()> {
+		let mut state = self.lock();
+		state.request(format!("LOGOUT {}", role), "auth/token/revoke-self")?;
+		state
+			.tokens
+			.remove(role)
+			.map(|_| ())
+			.ok_or(Error::NotLogged)
+	}
+
+	fn logout_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<()>> {
+		Box::pin(async move { self.logout(role) })
+	}
+
+	fn renew_token(&self, role: &str) -> Result<Auth> {
+		let mut state = self.lock();
+		state.request(format!("RENEW {}", role), "auth/token/renew-self")?;
+		state.check_token(role, "auth/token/renew-self")?;
+		let now = state.now;
+		let ttl = self.token_ttl;
+		let token = state.tokens.get_mut(role).ok_or(Error::NotLogged)?;
+		token.expire = ttl.map(|ttl| now + ttl);
+		Ok(Auth::new(&token.token, ttl))
+	}
+
+	fn renew_token_async<'a>(&'a self, role: &'a str) -> BoxFuture<'a, Result<Auth>> {
+		Box::pin(async move { self.renew_token(role) })
+	}
+
+	fn get_secret(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		_kwargs: Option<&Vec<(&str, &str)>>,
+	) -> Result<Secret> {
+		let path = path.trim_matches('/');
+		let mut state = self.lock();
+		state.request(format!("{} {}", method, path), path)?;
+		state.check_token(role, path)?;
+		let stored = state
+			.secrets
+			.get(path)
+			.cloned()
+			.ok_or_else(|| Error::from_status(StatusCode::NOT_FOUND, path, Vec::new()))?;
+		let mut secret = Secret::new(stored.value, stored.ttl);
+		if stored.ttl.is_some() {
+			state.issued += 1;
+			secret.lease_id = Some(format!("{}/{}", path, state.issued));
+		}
+		Ok(secret)
+	}
+
+	fn get_secret_async<'a>(
+		&'a self,
+		role: &'a str,
+		method: &'a str,
+		path: &'a str,
+		kwargs: Option<&'a Vec<(&'a str, &'a str)>>,
+	) -> BoxFuture<'a, Result<Secret>> {
+		Box::pin(async move { self.get_secret(role, method, path, kwargs) })
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn fake_secrets_and_token_ttl() {
+		let vault = FakeVault::new().token_ttl(Duration::from_secs(60));
+		vault.insert("secret/app", json!({"password": "hunter2"}));
+		vault.insert_leased(
+			"database/creds/app",
+			json!({"username": "v-app"}),
+			Duration::from_secs(300),
+		);
+		assert!(matches!(
+			vault.get_secret("app", "GET", "secret/app", None),
+			Err(Error::NotLogged)
+		));
+		vault.login("app").unwrap();
+		let secret = vault.get_secret("app", "GET", "secret/app", None).unwrap();
+		assert_eq!(secret.value["password"], "hunter2");
+		let creds = vault
+			.get_secret("app", "GET", "database/creds/app", None)
+			.unwrap();
+		assert_eq!(creds.lease_id.as_deref(), Some("database/creds/app/2"));
+		assert!(vault
+			.try_get_secret("app", "GET", "secret/other", None)
+			.unwrap()
+			.is_none());
+
+		vault.advance(Duration::from_secs(61));
+		assert!(!vault.is_logged("app"));
+		assert!(vault.renew_token("app").is_err());
+		assert!(matches!(
+			vault.get_secret("app", "GET", "secret/app", None),
+			Err(Error::Forbidden { .. })
+		));
+	}
+
+	#[test]
+	fn scripted_failures() {
+		let vault = FakeVault::new();
+		vault.insert("secret/app", json!({}));
+		vault.fail("auth/app/login", StatusCode::SERVICE_UNAVAILABLE);
+		vault.fail("secret/app", StatusCode::TOO_MANY_REQUESTS);
+		assert!(vault.login("app").is_err());
+		vault.login("app").unwrap();
+		assert!(matches!(
+			vault.get_secret("app", "GET", "secret/app", None),
+			Err(Error::RateLimited { .. })
+		));
+		assert!(vault.get_secret("app", "GET", "/secret/app", None).is_ok());
+		assert_eq!(
+			vault.requests(),
+			vec!["LOGIN app", "LOGIN app", "GET secret/app", "GET secret/app"]
+		);
+	}
+}
@@ -0,0 +1,397 @@
+use crate::{
+	auth::Auth,
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, time::Duration};
+
+/// Properties of a token as returned by `auth/token/lookup-self`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+	pub accessor: String,
+	pub display_name: String,
+	pub policies: Vec<String>,
+	/// time left before the token expires (zero for tokens that never expire)
+	pub ttl: Duration,
+	pub creation_ttl: Duration,
+	pub explicit_max_ttl: Duration,
+	/// number of uses left (zero means unlimited)
+	pub num_uses: u64,
+	pub renewable: bool,
+	pub orphan: bool,
+	pub entity_id: String,
+	pub expire_time: Option<String>,
+	pub meta: HashMap<String, String>,
+}
+
+/// token lookup data as returned by vault
+#[derive(Deserialize)]
+struct LookupData {
+	#[serde(default)]
+	accessor: String,
+	#[serde(default)]
+	display_name: String,
+	#[serde(default)]
+	policies: Vec<String>,
+	ttl: u64,
+	#[serde(default)]
+	creation_ttl: u64,
+	#[serde(default)]
+	explicit_max_ttl: u64,
+	#[serde(default)]
+	num_uses: u64,
+	#[serde(default)]
+	renewable: bool,
+	#[serde(default)]
+	orphan: bool,
+	#[serde(default)]
+	entity_id: String,
+	expire_time: Option<String>,
+	meta: Option<HashMap<String, String>>,
+}
+
+impl TokenInfo {
+	/// extract the token properties from a response of `auth/token/lookup-self`
+	fn from_value(mut value: Value) -> Result<Self> {
+		let data: LookupData = serde_json::from_value(value["data"].take())
+			.map_err(|e| Error::ParseError { source: e })?;
+		Ok(Self {
+			accessor: data.accessor,
+			display_name: data.display_name,
+			policies: data.policies,
+			ttl: Duration::from_secs(data.ttl),
+			creation_ttl: Duration::from_secs(data.creation_ttl),
+			explicit_max_ttl: Duration::from_secs(data.explicit_max_ttl),
+			num_uses: data.num_uses,
+			renewable: data.renewable,
+			orphan: data.orphan,
+			entity_id: data.entity_id,
+			expire_time: data.expire_time,
+			meta: data.meta.unwrap_or_default(),
+		})
+	}
+
+	/// check if the token has a policy
+	pub fn has_policy(&self, policy: &str) -> bool {
+		self.policies.iter().any(|p| p == policy)
+	}
+}
+
+/// Parameters of a token creation. The unset parameters get the vault defaults (policies and
+/// ttl of the parent token)
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TokenOptions {
+	/// token role whose parameters are used (`auth/token/create/<role>`, for orphan tokens too
+	/// as the role decides if its tokens are orphan)
+	#[serde(skip)]
+	pub token_role: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub policies: Option<Vec<String>>,
+	/// requested ttl (ex: `1h`)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ttl: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub explicit_max_ttl: Option<String>,
+	/// renew period of a periodic token (ex: `24h`)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub period: Option<String>,
+	/// number of uses of the token (zero means unlimited)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub num_uses: Option<u64>,
+	/// create a token without parent (needs a root or sudo token, see `create_orphan_token`)
+	#[serde(rename = "no_parent", skip_serializing_if = "Option::is_none")]
+	pub orphan: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub renewable: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub display_name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub meta: Option<HashMap<String, String>>,
+}
+
+/// Path of a token creation
+fn create_path(endpoint: &str, options: &TokenOptions) -> String {
+	match options.token_role {
+		Some(ref token_role) => format!("auth/token/create/{}", token_role),
+		None => format!("auth/token/{}", endpoint),
+	}
+}
+
+/// Extract the capabilities on path from a response of `sys/capabilities-self`
+fn capabilities_from_value(mut value: Value, path: &str) -> Result<Vec<String>> {
+	let capabilities = match value["data"][path].take() {
+		Value::Null => value["capabilities"].take(),
+		capabilities => capabilities,
+	};
+	serde_json::from_value(capabilities).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Return the accessor of the token of role returned at login
+	pub fn token_accessor(&self, role: &str) -> Option<String> {
+		self.auth(role)
+			.map(|mut auth| std::mem::take(&mut auth.accessor))
+			.filter(|accessor| !accessor.is_empty())
+	}
+
+	/// Return, with the token of role, the properties of the token identified by accessor
+	pub fn lookup_accessor(&self, role: &str, accessor: &str) -> Result<TokenInfo> {
+		let request = self.vault_request(
+			"POST",
+			"auth/token/lookup-accessor",
+			Some(&self.token(role)?),
+			json!({ "accessor": accessor }).to_string(),
+		)?;
+		TokenInfo::from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the properties of the token identified by accessor
+	pub async fn lookup_accessor_async(&self, role: &str, accessor: &str) -> Result<TokenInfo> {
+		let request = self.vault_request(
+			"POST",
+			"auth/token/lookup-accessor",
+			Some(&self.token_async(role).await?),
+			json!({ "accessor": accessor }).to_string(),
+		)?;
+		TokenInfo::from_value(self.send_async(request).await?)
+	}
+
+	/// Revoke, with the token of role, the token identified by accessor and its children, and
+	/// remove it from the cache
+	pub fn revoke_by_accessor(&self, role: &str, accessor: &str) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			"auth/token/revoke-accessor",
+			Some(&self.token(role)?),
+			json!({ "accessor": accessor }).to_string(),
+		)?;
+		self.send(request)?;
+		self.forget_accessor(accessor);
+		Ok(())
+	}
+
+	/// Revoke asynchronously the token identified by accessor and remove it from the cache
+	pub async fn revoke_by_accessor_async(&self, role: &str, accessor: &str) -> Result<()> {
+		let request = self.vault_request(
+			"POST",
+			"auth/token/revoke-accessor",
+			Some(&self.token_async(role).await?),
+			json!({ "accessor": accessor }).to_string(),
+		)?;
+		self.send_async(request).await?;
+		self.forget_accessor(accessor);
+		Ok(())
+	}
+
+	/// Remove from the cache the tokens identified by accessor
+	fn forget_accessor(&self, accessor: &str) {
+		self.auth_write()
+			.retain(|_, auth| auth.accessor != accessor);
+	}
+
+	/// Create, with the token of role, a child token and return its Auth. The token is not cached
+	pub fn create_token(&self, role: &str, options: &TokenOptions) -> Result<Auth> {
+		self.post_create(role, &create_path("create", options), options)
+	}
+
+	/// Create asynchronously a child token and return its Auth
+	pub async fn create_token_async(&self, role: &str, options: &TokenOptions) -> Result<Auth> {
+		self.post_create_async(role, &create_path("create", options), options)
+			.await
+	}
+
+	/// Create, with the token of role, a token without parent with `auth/token/create-orphan`,
+	/// which only needs a policy on this path. The token is not revoked with its creator
+	pub fn create_orphan_token(&self, role: &str, options: &TokenOptions) -> Result<Auth> {
+		self.post_create(role, &create_path("create-orphan", options), options)
+	}
+
+	/// Create asynchronously a token without parent
+	pub async fn create_orphan_token_async(
+		&self,
+		role: &str,
+		options: &TokenOptions,
+	) -> Result<Auth> {
+		self.post_create_async(role, &create_path("create-orphan", options), options)
+			.await
+	}
+
+	fn post_create(&self, role: &str, path: &str, options: &TokenOptions) -> Result<Auth> {
+		let request = self.vault_request(
+			"POST",
+			path,
+			Some(&self.token(role)?),
+			json!(options).to_string(),
+		)?;
+		Auth::from_value(self.send(request)?)
+	}
+
+	async fn post_create_async(
+		&self,
+		role: &str,
+		path: &str,
+		options: &TokenOptions,
+	) -> Result<Auth> {
+		let request = self.vault_request(
+			"POST",
+			path,
+			Some(&self.token_async(role).await?),
+			json!(options).to_string(),
+		)?;
+		Auth::from_value(self.send_async(request).await?)
+	}
+
+	/// Return the properties (ttl, policies, number of uses...) of the token of role
+	pub fn lookup_token(&self, role: &str) -> Result<TokenInfo> {
+		let request = self.vault_request(
+			"GET",
+			"auth/token/lookup-self",
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		TokenInfo::from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the properties of the token of role
+	pub async fn lookup_token_async(&self, role: &str) -> Result<TokenInfo> {
+		let request = self.vault_request(
+			"GET",
+			"auth/token/lookup-self",
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		TokenInfo::from_value(self.send_async(request).await?)
+	}
+
+	/// Return the capabilities (`read`, `list`, `deny`...) of the token of role on path
+	pub fn capabilities(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		let request = self.vault_request(
+			"POST",
+			"sys/capabilities-self",
+			Some(&self.token(role)?),
+			json!({ "paths": [path] }).to_string(),
+		)?;
+		capabilities_from_value(self.send(request)?, path)
+	}
+
+	/// Return asynchronously the capabilities of the token of role on path
+	pub async fn capabilities_async(&self, role: &str, path: &str) -> Result<Vec<String>> {
+		let request = self.vault_request(
+			"POST",
+			"sys/capabilities-self",
+			Some(&self.token_async(role).await?),
+			json!({ "paths": [path] }).to_string(),
+		)?;
+		capabilities_from_value(self.send_async(request).await?, path)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+
+	#[test]
+	fn token_info_from_response() {
+		let info = TokenInfo::from_value(json!({
+			"data": {
+				"accessor": "8609694a-cdbc-db9b-d345-e782dbb562ed",
+				"creation_ttl": 2764800,
+				"display_name": "kubernetes-default-app",
+				"entity_id": "7d2e3179-f69b-450c-7179-ac8ee8bd8ca9",
+				"expire_time": "2018-05-19T11:35:54.466476215-04:00",
+				"explicit_max_ttl": 0,
+				"meta": null,
+				"num_uses": 0,
+				"orphan": true,
+				"policies": ["default", "app"],
+				"renewable": true,
+				"ttl": 2764790
+			}
+		}))
+		.unwrap();
+		assert_eq!(info.ttl, Duration::from_secs(2764790));
+		assert!(info.has_policy("app") && info.meta.is_empty());
+	}
+
+	#[test]
+	fn token_creation_body() {
+		let options = TokenOptions {
+			token_role: Some("app".to_owned()),
+			policies: Some(vec!["app".to_owned()]),
+			ttl: Some("1h".to_owned()),
+			orphan: Some(true),
+			..Default::default()
+		};
+		assert_eq!(create_path("create", &options), "auth/token/create/app");
+		assert_eq!(
+			json!(options),
+			json!({"policies": ["app"], "ttl": "1h", "no_parent": true})
+		);
+		assert_eq!(
+			create_path("create-orphan", &TokenOptions::default()),
+			"auth/token/create-orphan"
+		);
+	}
+
+	#[test]
+	fn revoked_accessor_is_forgotten() {
+		let client = Scripted::new().client();
+		let mut auth = crate::auth::Auth::new("s.other", None);
+		auth.accessor = "accessor".to_owned();
+		client.auth_write().insert("app".to_owned(), auth);
+		assert_eq!(client.token_accessor("app").as_deref(), Some("accessor"));
+		client.forget_accessor("accessor");
+		assert!(client.auth("app").is_none());
+	}
+
+	#[test]
+	fn capabilities_from_response() {
+		let capabilities = capabilities_from_value(
+			json!({
+				"capabilities": ["read", "list"],
+				"secret/foo": ["read", "list"],
+				"data": {"capabilities": ["read", "list"], "secret/foo": ["read", "list"]}
+			}),
+			"secret/foo",
+		)
+		.unwrap();
+		assert_eq!(capabilities, vec!["read", "list"]);
+	}
+
+	#[test]
+	fn token_requests_use_the_token_of_role() {
+		let transport = Scripted::new();
+		transport
+			.on(
+				"POST",
+				"auth/token/create/app",
+				200,
+				json!({"auth": {"client_token": "s.child", "lease_duration": 3600, "renewable": true}}),
+			)
+			.on("POST", "auth/token/revoke-accessor", 204, Value::Null);
+		let client = transport.client();
+		let options = TokenOptions {
+			token_role: Some("app".to_owned()),
+			..Default::default()
+		};
+		let auth = client.create_token(TOKEN_ROLE, &options).unwrap();
+		assert_eq!(auth.client_token.as_str(), "s.child");
+		assert_eq!(
+			transport
+				.header("POST auth/token/create/app", "x-vault-token")
+				.as_deref(),
+			Some("s.token")
+		);
+		client.revoke_by_accessor(TOKEN_ROLE, "bnEqQE").unwrap();
+		assert_eq!(
+			transport.body("POST auth/token/revoke-accessor"),
+			Some(json!({"accessor": "bnEqQE"}))
+		);
+		// the created token is not cached, so the static one is still used
+		assert_eq!(client.token(TOKEN_ROLE).unwrap().as_str(), "s.token");
+	}
+}
@@ -0,0 +1,179 @@
+use crate::{
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parameters of a key of the totp engine
+#[derive(Debug, Clone, Deserialize)]
+pub struct TotpKey {
+	#[serde(default)]
+	pub account_name: String,
+	#[serde(default)]
+	pub issuer: String,
+	pub algorithm: String,
+	pub digits: u32,
+	/// validity of a code in seconds
+	pub period: u64,
+}
+
+/// A code generated by the totp engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotpCode {
+	pub code: String,
+	/// validity period of the code, unknown if the token can't read the key
+	pub period: Option<Duration>,
+}
+
+impl TotpCode {
+	/// time left before the code changes
+	pub fn valid_for(&self) -> Option<Duration> {
+		let period = self.period?.as_secs().max(1);
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_else(|_| Duration::from_secs(0))
+			.as_secs();
+		Some(Duration::from_secs(period - now % period))
+	}
+}
+
+/// Return the path of a totp endpoint for a key
+fn totp_path(mount: &str, endpoint: &str, name: &str) -> String {
+	format!("{}/{}/{}", mount.trim_matches('/'), endpoint, name)
+}
+
+/// Extract a field of the data of a totp response
+fn field_from_value<T: serde::de::DeserializeOwned>(
+	mut value: Value,
+	field: &'static str,
+) -> Result<T> {
+	match value["data"][field].take() {
+		Value::Null => Err(Error::MissingField(field)),
+		field => serde_json::from_value(field).map_err(|e| Error::ParseError { source: e }),
+	}
+}
+
+/// Extract the key parameters from a response of `<mount>/keys/<name>`
+fn key_from_value(mut value: Value) -> Result<TotpKey> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Return the period of a key or None if the key can't be read
+fn period_of(key: Result<TotpKey>) -> Result<Option<Duration>> {
+	match key {
+		Ok(key) => Ok(Some(Duration::from_secs(key.period))),
+		Err(Error::Forbidden { .. }) => Ok(None),
+		Err(e) => Err(e),
+	}
+}
+
+impl VaultClient {
+	/// Return the parameters of the key name of the totp engine mounted at mount
+	pub fn totp_key(&self, role: &str, mount: &str, name: &str) -> Result<TotpKey> {
+		let request = self.vault_request(
+			"GET",
+			&totp_path(mount, "keys", name),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		key_from_value(self.send(request)?)
+	}
+
+	/// Return asynchronously the parameters of the key name of the totp engine
+	pub async fn totp_key_async(&self, role: &str, mount: &str, name: &str) -> Result<TotpKey> {
+		let request = self.vault_request(
+			"GET",
+			&totp_path(mount, "keys", name),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		key_from_value(self.send_async(request).await?)
+	}
+
+	/// Generate a code for the key name of the totp engine mounted at mount. The period is
+	/// filled when the token of role can also read the key
+	pub fn totp_generate(&self, role: &str, mount: &str, name: &str) -> Result<TotpCode> {
+		let request = self.vault_request(
+			"GET",
+			&totp_path(mount, "code", name),
+			Some(&self.token(role)?),
+			String::new(),
+		)?;
+		let code = field_from_value(self.send(request)?, "code")?;
+		Ok(TotpCode {
+			code,
+			period: period_of(self.totp_key(role, mount, name))?,
+		})
+	}
+
+	/// Generate asynchronously a code for the key name of the totp engine
+	pub async fn totp_generate_async(
+		&self,
+		role: &str,
+		mount: &str,
+		name: &str,
+	) -> Result<TotpCode> {
+		let request = self.vault_request(
+			"GET",
+			&totp_path(mount, "code", name),
+			Some(&self.token_async(role).await?),
+			String::new(),
+		)?;
+		let code = field_from_value(self.send_async(request).await?, "code")?;
+		Ok(TotpCode {
+			code,
+			period: period_of(self.totp_key_async(role, mount, name).await)?,
+		})
+	}
+
+	/// Check code against the key name of the totp engine mounted at mount
+	pub fn totp_validate(&self, role: &str, mount: &str, name: &str, code: &str) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			&totp_path(mount, "code", name),
+			Some(&self.token(role)?),
+			json!({ "code": code }).to_string(),
+		)?;
+		field_from_value(self.send(request)?, "valid")
+	}
+
+	/// Check asynchronously code against the key name of the totp engine
+	pub async fn totp_validate_async(
+		&self,
+		role: &str,
+		mount: &str,
+		name: &str,
+		code: &str,
+	) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			&totp_path(mount, "code", name),
+			Some(&self.token_async(role).await?),
+			json!({ "code": code }).to_string(),
+		)?;
+		field_from_value(self.send_async(request).await?, "valid")
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn code_from_response() {
+		let code: String = field_from_value(json!({"data": {"code": "810920"}}), "code").unwrap();
+		assert_eq!(code, "810920");
+		assert!(matches!(
+			field_from_value::<bool>(json!({"data": {}}), "valid"),
+			Err(Error::MissingField("valid"))
+		));
+		let code = TotpCode {
+			code,
+			period: Some(Duration::from_secs(30)),
+		};
+		assert!(code.valid_for().unwrap() <= Duration::from_secs(30));
+	}
+}
@@ -0,0 +1,116 @@
+use http::{Method, StatusCode};
+use std::{future::Future, time::Instant};
+
+/// Span of a client operation (login, secret fetch, renewal) carrying the role and the redacted
+/// path. Without the `tracing` feature it does nothing
+pub(crate) struct Span {
+	#[cfg(feature = "tracing")]
+	span: tracing::Span,
+}
+
+/// Guard of an entered span
+pub(crate) struct Entered {
+	#[cfg(feature = "tracing")]
+	_entered: tracing::span::EnteredSpan,
+}
+
+#[cfg(feature = "tracing")]
+impl Span {
+	pub(crate) fn login(role: &str) -> Self {
+		Self {
+			span: tracing::info_span!("vault.login", role),
+		}
+	}
+
+	pub(crate) fn renewal(role: &str) -> Self {
+		Self {
+			span: tracing::info_span!("vault.renewal", role),
+		}
+	}
+
+	pub(crate) fn secret(role: &str, path: &str) -> Self {
+		Self {
+			span: tracing::info_span!("vault.get_secret", role, path = redact_path(path).as_str()),
+		}
+	}
+
+	/// enter the span until the guard is dropped
+	pub(crate) fn enter(self) -> Entered {
+		Entered {
+			_entered: self.span.entered(),
+		}
+	}
+
+	/// run future in the span
+	pub(crate) async fn instrument<F: Future>(self, future: F) -> F::Output {
+		tracing::Instrument::instrument(future, self.span).await
+	}
+}
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+	pub(crate) fn login(_role: &str) -> Self {
+		Self {}
+	}
+
+	pub(crate) fn renewal(_role: &str) -> Self {
+		Self {}
+	}
+
+	pub(crate) fn secret(_role: &str, _path: &str) -> Self {
+		Self {}
+	}
+
+	pub(crate) fn enter(self) -> Entered {
+		Entered {}
+	}
+
+	pub(crate) async fn instrument<F: Future>(self, future: F) -> F::Output {
+		future.await
+	}
+}
+
+/// Emit an event in the current span with the status (None when vault was unreachable) and the
+/// duration of a request started at start
+#[cfg(feature = "tracing")]
+pub(crate) fn request(method: &Method, path: &str, start: Instant, status: Option<StatusCode>) {
+	tracing::debug!(
+		method = method.as_str(),
+		path = redact_path(path).as_str(),
+		status = status.map(|status| status.as_u16()),
+		duration_ms = start.elapsed().as_millis() as u64,
+		"vault request"
+	);
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn request(_method: &Method, _path: &str, _start: Instant, _status: Option<StatusCode>) {
+}
+
+/// Keep the first 2 segments of a path (relative to the api url) and hide the rest (secret names,
+/// lease ids, tokens...)
+pub(crate) fn redact_path(path: &str) -> String {
+	let path = path.trim_matches('/');
+	let mut segments = path.strip_prefix("v1/").unwrap_or(path).split('/');
+	let kept: Vec<&str> = segments.by_ref().take(2).collect();
+	if segments.next().is_some() {
+		format!("{}/*", kept.join("/"))
+	} else {
+		kept.join("/")
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn path_is_redacted() {
+		assert_eq!(redact_path("secret/data/app"), "secret/data/*");
+		assert_eq!(redact_path("/v1/sys/health"), "sys/health");
+		assert_eq!(
+			redact_path("/v1/auth/token/lookup/hvs.1234"),
+			"auth/token/*"
+		);
+	}
+}
@@ -0,0 +1,394 @@
+use crate::{
+	base64,
+	client::VaultClient,
+	error::{Error, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Result of a transit operation, alone or in a batch
+#[derive(Deserialize)]
+struct TransitItem {
+	#[serde(default)]
+	ciphertext: Option<String>,
+	#[serde(default)]
+	plaintext: Option<String>,
+	#[serde(default)]
+	error: Option<String>,
+}
+
+impl TransitItem {
+	fn ciphertext(self) -> Result<String> {
+		match (self.ciphertext, self.error) {
+			(_, Some(e)) if !e.is_empty() => Err(Error::BatchItemError(e)),
+			(Some(ciphertext), _) => Ok(ciphertext),
+			_ => Err(Error::MissingField("ciphertext")),
+		}
+	}
+
+	fn plaintext(self) -> Result<Vec<u8>> {
+		match (self.plaintext, self.error) {
+			(_, Some(e)) if !e.is_empty() => Err(Error::BatchItemError(e)),
+			(Some(plaintext), _) => base64::decode(&plaintext).ok_or(Error::InvalidBase64),
+			_ => Err(Error::MissingField("plaintext")),
+		}
+	}
+}
+
+/// Extract the result of a single transit operation
+fn item_from_value(mut value: Value) -> Result<TransitItem> {
+	serde_json::from_value(value["data"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+/// Extract the results of a batch transit operation
+fn items_from_value(mut value: Value) -> Result<Vec<TransitItem>> {
+	serde_json::from_value(value["data"]["batch_results"].take())
+		.map_err(|e| Error::ParseError { source: e })
+}
+
+/// Return the path of a transit endpoint for a key
+fn transit_path(mount: &str, endpoint: &str, key: &str) -> String {
+	format!("{}/{}/{}", mount.trim_matches('/'), endpoint, key)
+}
+
+/// Body of a signature or a verification with an optional hash algorithm
+fn sign_body(data: &[u8], hash_algorithm: Option<&str>, signature: Option<&str>) -> String {
+	let mut body = json!({ "input": base64::encode(data) });
+	if let Some(hash_algorithm) = hash_algorithm {
+		body["hash_algorithm"] = Value::from(hash_algorithm);
+	}
+	if let Some(signature) = signature {
+		body["signature"] = Value::from(signature);
+	}
+	body.to_string()
+}
+
+/// Extract the signature of a transit sign response
+fn signature_from_value(value: &Value) -> Result<String> {
+	value["data"]["signature"]
+		.as_str()
+		.map(|s| s.to_owned())
+		.ok_or(Error::MissingField("signature"))
+}
+
+/// Body of a batch encryption
+fn encrypt_batch_body(plaintexts: &[&[u8]]) -> String {
+	let batch: Vec<Value> = plaintexts
+		.iter()
+		.map(|p| json!({ "plaintext": base64::encode(p) }))
+		.collect();
+	json!({ "batch_input": batch }).to_string()
+}
+
+/// Body of a batch decryption
+fn decrypt_batch_body(ciphertexts: &[&str]) -> String {
+	let batch: Vec<Value> = ciphertexts
+		.iter()
+		.map(|c| json!({ "ciphertext": c }))
+		.collect();
+	json!({ "batch_input": batch }).to_string()
+}
+
+impl VaultClient {
+	/// Encrypt plaintext with the transit key and return the ciphertext (`vault:v1:...`)
+	pub fn transit_encrypt(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		plaintext: &[u8],
+	) -> Result<String> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "encrypt", key),
+			Some(&self.token(role)?),
+			json!({ "plaintext": base64::encode(plaintext) }).to_string(),
+		)?;
+		item_from_value(self.send(request)?)?.ciphertext()
+	}
+
+	/// Encrypt asynchronously plaintext with the transit key and return the ciphertext
+	pub async fn transit_encrypt_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		plaintext: &[u8],
+	) -> Result<String> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "encrypt", key),
+			Some(&self.token_async(role).await?),
+			json!({ "plaintext": base64::encode(plaintext) }).to_string(),
+		)?;
+		item_from_value(self.send_async(request).await?)?.ciphertext()
+	}
+
+	/// Decrypt a ciphertext with the transit key and return the plaintext
+	pub fn transit_decrypt(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		ciphertext: &str,
+	) -> Result<Vec<u8>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "decrypt", key),
+			Some(&self.token(role)?),
+			json!({ "ciphertext": ciphertext }).to_string(),
+		)?;
+		item_from_value(self.send(request)?)?.plaintext()
+	}
+
+	/// Decrypt asynchronously a ciphertext with the transit key and return the plaintext
+	pub async fn transit_decrypt_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		ciphertext: &str,
+	) -> Result<Vec<u8>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "decrypt", key),
+			Some(&self.token_async(role).await?),
+			json!({ "ciphertext": ciphertext }).to_string(),
+		)?;
+		item_from_value(self.send_async(request).await?)?.plaintext()
+	}
+
+	/// Encrypt several plaintexts in one request. Each item of the batch can fail independently
+	pub fn transit_encrypt_batch(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		plaintexts: &[&[u8]],
+	) -> Result<Vec<Result<String>>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "encrypt", key),
+			Some(&self.token(role)?),
+			encrypt_batch_body(plaintexts),
+		)?;
+		Ok(items_from_value(self.send(request)?)?
+			.into_iter()
+			.map(TransitItem::ciphertext)
+			.collect())
+	}
+
+	/// Encrypt asynchronously several plaintexts in one request
+	pub async fn transit_encrypt_batch_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		plaintexts: &[&[u8]],
+	) -> Result<Vec<Result<String>>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "encrypt", key),
+			Some(&self.token_async(role).await?),
+			encrypt_batch_body(plaintexts),
+		)?;
+		Ok(items_from_value(self.send_async(request).await?)?
+			.into_iter()
+			.map(TransitItem::ciphertext)
+			.collect())
+	}
+
+	/// Decrypt several ciphertexts in one request. Each item of the batch can fail independently
+	pub fn transit_decrypt_batch(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		ciphertexts: &[&str],
+	) -> Result<Vec<Result<Vec<u8>>>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "decrypt", key),
+			Some(&self.token(role)?),
+			decrypt_batch_body(ciphertexts),
+		)?;
+		Ok(items_from_value(self.send(request)?)?
+			.into_iter()
+			.map(TransitItem::plaintext)
+			.collect())
+	}
+
+	/// Decrypt asynchronously several ciphertexts in one request
+	pub async fn transit_decrypt_batch_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		ciphertexts: &[&str],
+	) -> Result<Vec<Result<Vec<u8>>>> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "decrypt", key),
+			Some(&self.token_async(role).await?),
+			decrypt_batch_body(ciphertexts),
+		)?;
+		Ok(items_from_value(self.send_async(request).await?)?
+			.into_iter()
+			.map(TransitItem::plaintext)
+			.collect())
+	}
+
+	/// Sign data with the transit key and return the signature (`vault:v1:...`).
+	/// hash_algorithm defaults to sha2-256
+	pub fn transit_sign(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		data: &[u8],
+		hash_algorithm: Option<&str>,
+	) -> Result<String> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "sign", key),
+			Some(&self.token(role)?),
+			sign_body(data, hash_algorithm, None),
+		)?;
+		signature_from_value(&self.send(request)?)
+	}
+
+	/// Sign asynchronously data with the transit key and return the signature
+	pub async fn transit_sign_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		data: &[u8],
+		hash_algorithm: Option<&str>,
+	) -> Result<String> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "sign", key),
+			Some(&self.token_async(role).await?),
+			sign_body(data, hash_algorithm, None),
+		)?;
+		signature_from_value(&self.send_async(request).await?)
+	}
+
+	/// Check that signature is a valid signature of data by the transit key
+	pub fn transit_verify(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		data: &[u8],
+		signature: &str,
+		hash_algorithm: Option<&str>,
+	) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "verify", key),
+			Some(&self.token(role)?),
+			sign_body(data, hash_algorithm, Some(signature)),
+		)?;
+		Ok(self.send(request)?["data"]["valid"]
+			.as_bool()
+			.unwrap_or(false))
+	}
+
+	/// Check asynchronously that signature is a valid signature of data by the transit key
+	pub async fn transit_verify_async(
+		&self,
+		role: &str,
+		mount: &str,
+		key: &str,
+		data: &[u8],
+		signature: &str,
+		hash_algorithm: Option<&str>,
+	) -> Result<bool> {
+		let request = self.vault_request(
+			"POST",
+			&transit_path(mount, "verify", key),
+			Some(&self.token_async(role).await?),
+			sign_body(data, hash_algorithm, Some(signature)),
+		)?;
+		Ok(self.send_async(request).await?["data"]["valid"]
+			.as_bool()
+			.unwrap_or(false))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+
+	#[test]
+	fn decrypt_batch_results() {
+		let items = items_from_value(json!({
+			"data": {
+				"batch_results": [
+					{"plaintext": "Zm9v"},
+					{"error": "invalid ciphertext"}
+				]
+			}
+		}))
+		.unwrap();
+		let results: Vec<Result<Vec<u8>>> = items.into_iter().map(TransitItem::plaintext).collect();
+		assert_eq!(results[0].as_ref().unwrap(), b"foo");
+		assert!(matches!(results[1], Err(Error::BatchItemError(_))));
+	}
+
+	#[test]
+	fn encrypt_batch_input() {
+		assert_eq!(
+			encrypt_batch_body(&[b"foo", b"bar"]),
+			r#"{"batch_input":[{"plaintext":"Zm9v"},{"plaintext":"YmFy"}]}"#
+		);
+	}
+
+	#[test]
+	fn verify_body() {
+		assert_eq!(
+			sign_body(b"foo", Some("sha2-512"), Some("vault:v1:sig")),
+			r#"{"hash_algorithm":"sha2-512","input":"Zm9v","signature":"vault:v1:sig"}"#
+		);
+	}
+
+	#[test]
+	fn transit_requests_encode_the_plaintexts() {
+		let transport = Scripted::new();
+		transport
+			.on(
+				"POST",
+				"transit/encrypt/app",
+				200,
+				json!({"data": {"ciphertext": "vault:v1:abc"}}),
+			)
+			.on(
+				"POST",
+				"transit/decrypt/app",
+				200,
+				json!({"data": {"plaintext": "Zm9v"}}),
+			);
+		let client = transport.client();
+		let ciphertext = client.transit_encrypt(TOKEN_ROLE, "transit", "app", b"foo");
+		assert_eq!(ciphertext.unwrap(), "vault:v1:abc");
+		assert_eq!(
+			transport.body("POST transit/encrypt/app"),
+			Some(json!({"plaintext": "Zm9v"}))
+		);
+		let plaintext = futures_lite::future::block_on(client.transit_decrypt_async(
+			TOKEN_ROLE,
+			"transit",
+			"app",
+			"vault:v1:abc",
+		));
+		assert_eq!(plaintext.unwrap(), b"foo");
+		assert_eq!(
+			transport.body("POST transit/decrypt/app"),
+			Some(json!({"ciphertext": "vault:v1:abc"}))
+		);
+	}
+}
@@ -0,0 +1,812 @@
+use crate::{
+	api::BoxFuture,
+	error::{Error, Result},
+};
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+use bytes::Bytes;
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+use futures_lite::Stream;
+use futures_lite::{io::AsyncRead, AsyncReadExt};
+use http::{Request, Response, StatusCode};
+#[cfg(feature = "isahc")]
+use isahc::{AsyncReadResponseExt, HttpClient, ReadResponseExt};
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+use std::{convert::TryInto, sync::Mutex};
+use std::{
+	fmt,
+	io::{self, Cursor, Read},
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+/// Http client sending the requests of `VaultClient`. It allows to use another http stack than
+/// isahc, like reqwest with `ReqwestTransport` (`reqwest` or `rustls` feature) or hyper, failures to reach
+/// the server being reported with `Error::TransportError`. Without the `isahc` feature the
+/// builder uses a `ReqwestTransport`, and without both a transport must be given to the builder
+pub trait HttpTransport: fmt::Debug + Send + Sync {
+	/// send a json request and return the response with its body read
+	fn send(&self, request: Request<String>) -> Result<Response<Vec<u8>>>;
+
+	/// send asynchronously a json request and return the response with its body read
+	fn send_async(&self, request: Request<String>) -> BoxFuture<'_, Result<Response<Vec<u8>>>>;
+
+	/// send a request with a streamed body and return the response with its body unread, for
+	/// bodies that are not json or too large to be buffered (raw responses, logs, snapshots)
+	fn send_stream(&self, request: Request<Body>) -> Result<Response<Body>>;
+
+	/// send asynchronously a request with a streamed body and return the response with its body
+	/// unread
+	fn send_stream_async(
+		&self,
+		request: Request<AsyncBody>,
+	) -> BoxFuture<'_, Result<Response<AsyncBody>>>;
+}
+
+/// Content of a body: nothing, bytes or a reader of a known or unknown length
+enum Inner<R> {
+	Empty,
+	Bytes(Cursor<Vec<u8>>),
+	Reader(R, Option<u64>),
+}
+
+impl<R> Inner<R> {
+	fn len(&self) -> Option<u64> {
+		match self {
+			Inner::Empty => Some(0),
+			Inner::Bytes(bytes) => Some(bytes.get_ref().len() as u64),
+			Inner::Reader(_, len) => *len,
+		}
+	}
+}
+
+/// Streamed body of a request or of a response (use `Body::from_reader` to stream a file)
+pub struct Body(Inner<Box<dyn Read + Send + Sync>>);
+
+impl Body {
+	/// the absence of body
+	pub const fn empty() -> Self {
+		Self(Inner::Empty)
+	}
+
+	/// a body reading reader until its end
+	pub fn from_reader<R: Read + Send + Sync + 'static>(reader: R) -> Self {
+		Self(Inner::Reader(Box::new(reader), None))
+	}
+
+	/// a body reading the len bytes of reader
+	pub fn from_reader_sized<R: Read + Send + Sync + 'static>(reader: R, len: u64) -> Self {
+		Self(Inner::Reader(Box::new(reader), Some(len)))
+	}
+
+	/// check if there is no body
+	pub fn is_empty(&self) -> bool {
+		matches!(self.0, Inner::Empty)
+	}
+
+	/// return the length of the body if it is known
+	pub fn len(&self) -> Option<u64> {
+		self.0.len()
+	}
+}
+
+impl Read for Body {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		match self.0 {
+			Inner::Empty => Ok(0),
+			Inner::Bytes(ref mut bytes) => bytes.read(buf),
+			Inner::Reader(ref mut reader, _) => reader.read(buf),
+		}
+	}
+}
+
+impl From<Vec<u8>> for Body {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(Inner::Bytes(Cursor::new(bytes)))
+	}
+}
+
+impl From<String> for Body {
+	fn from(text: String) -> Self {
+		text.into_bytes().into()
+	}
+}
+
+impl From<&str> for Body {
+	fn from(text: &str) -> Self {
+		text.as_bytes().to_vec().into()
+	}
+}
+
+impl fmt::Debug for Body {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("Body").field(&self.len()).finish()
+	}
+}
+
+/// Streamed body of an asynchronous request or response
+pub struct AsyncBody(Inner<Pin<Box<dyn AsyncRead + Send + Sync>>>);
+
+impl AsyncBody {
+	/// the absence of body
+	pub const fn empty() -> Self {
+		Self(Inner::Empty)
+	}
+
+	/// a body reading reader until its end
+	pub fn from_reader<R: AsyncRead + Send + Sync + 'static>(reader: R) -> Self {
+		Self(Inner::Reader(Box::pin(reader), None))
+	}
+
+	/// a body reading the len bytes of reader
+	pub fn from_reader_sized<R: AsyncRead + Send + Sync + 'static>(reader: R, len: u64) -> Self {
+		Self(Inner::Reader(Box::pin(reader), Some(len)))
+	}
+
+	/// check if there is no body
+	pub fn is_empty(&self) -> bool {
+		matches!(self.0, Inner::Empty)
+	}
+
+	/// return the length of the body if it is known
+	pub fn len(&self) -> Option<u64> {
+		self.0.len()
+	}
+
+	/// read the whole body
+	pub(crate) async fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+		let mut bytes = Vec::new();
+		self.read_to_end(&mut bytes).await?;
+		Ok(bytes)
+	}
+}
+
+impl AsyncRead for AsyncBody {
+	fn poll_read(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		match self.0 {
+			Inner::Empty => Poll::Ready(Ok(0)),
+			Inner::Bytes(ref mut bytes) => Poll::Ready(bytes.read(buf)),
+			Inner::Reader(ref mut reader, _) => reader.as_mut().poll_read(cx, buf),
+		}
+	}
+}
+
+impl From<Vec<u8>> for AsyncBody {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(Inner::Bytes(Cursor::new(bytes)))
+	}
+}
+
+impl From<String> for AsyncBody {
+	fn from(text: String) -> Self {
+		text.into_bytes().into()
+	}
+}
+
+impl From<&str> for AsyncBody {
+	fn from(text: &str) -> Self {
+		text.as_bytes().to_vec().into()
+	}
+}
+
+impl fmt::Debug for AsyncBody {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("AsyncBody").field(&self.len()).finish()
+	}
+}
+
+/// Send a request outside of vault (to a cloud metadata service or to aws sts) with the default
+/// isahc client (or reqwest one without the `isahc` feature), and return the status and the body
+/// of the response. Without both features there is no client to send it and `Error::NoTransport`
+/// is returned
+pub(crate) fn fetch(request: Request<String>) -> Result<(StatusCode, String)> {
+	#[cfg(feature = "isahc")]
+	{
+		let mut res = isahc::send(request)?;
+		Ok((res.status(), res.text()?))
+	}
+	#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+	{
+		let res = reqwest::blocking::Client::new()
+			.execute(request.try_into().map_err(Error::transport)?)
+			.map_err(Error::transport)?;
+		Ok((res.status(), res.text().map_err(Error::transport)?))
+	}
+	#[cfg(not(any(feature = "isahc", feature = "reqwest", feature = "rustls")))]
+	{
+		drop(request);
+		Err(Error::NoTransport)
+	}
+}
+
+/// Send asynchronously a request outside of vault and return the status and the body of the
+/// response
+pub(crate) async fn fetch_async(request: Request<String>) -> Result<(StatusCode, String)> {
+	#[cfg(feature = "isahc")]
+	{
+		let mut res = isahc::send_async(request).await?;
+		Ok((res.status(), res.text().await?))
+	}
+	#[cfg(all(any(feature = "reqwest", feature = "rustls"), not(feature = "isahc")))]
+	{
+		let res = reqwest::Client::new()
+			.execute(request.try_into().map_err(Error::transport)?)
+			.await
+			.map_err(Error::transport)?;
+		Ok((res.status(), res.text().await.map_err(Error::transport)?))
+	}
+	#[cfg(not(any(feature = "isahc", feature = "reqwest", feature = "rustls")))]
+	{
+		drop(request);
+		Err(Error::NoTransport)
+	}
+}
+
+/// Default transport using an isahc client configured by the builder
+#[cfg(feature = "isahc")]
+#[derive(Debug, Clone)]
+pub struct IsahcTransport {
+	client: HttpClient,
+}
+
+#[cfg(feature = "isahc")]
+impl IsahcTransport {
+	pub fn new(client: HttpClient) -> Self {
+		Self { client }
+	}
+}
+
+#[cfg(feature = "isahc")]
+impl HttpTransport for IsahcTransport {
+	fn send(&self, request: Request<String>) -> Result<Response<Vec<u8>>> {
+		let mut res = self
+			.client
+			.send(request)
+			.map_err(|e| Error::ClientError { source: e })?;
+		let body = res.bytes().map_err(Error::transport)?;
+		Ok(res.map(|_| body))
+	}
+
+	fn send_async(&self, request: Request<String>) -> BoxFuture<'_, Result<Response<Vec<u8>>>> {
+		Box::pin(async move {
+			let mut res = self
+				.client
+				.send_async(request)
+				.await
+				.map_err(|e| Error::ClientError { source: e })?;
+			let body = res.bytes().await.map_err(Error::transport)?;
+			Ok(res.map(|_| body))
+		})
+	}
+
+	fn send_stream(&self, request: Request<Body>) -> Result<Response<Body>> {
+		let res = self
+			.client
+			.send(request.map(to_isahc))
+			.map_err(|e| Error::ClientError { source: e })?;
+		Ok(res.map(|body| match body.len() {
+			Some(len) => Body::from_reader_sized(body, len),
+			None => Body::from_reader(body),
+		}))
+	}
+
+	fn send_stream_async(
+		&self,
+		request: Request<AsyncBody>,
+	) -> BoxFuture<'_, Result<Response<AsyncBody>>> {
+		Box::pin(async move {
+			let res = self
+				.client
+				.send_async(request.map(to_isahc_async))
+				.await
+				.map_err(|e| Error::ClientError { source: e })?;
+			Ok(res.map(|body| match body.len() {
+				Some(len) => AsyncBody::from_reader_sized(body, len),
+				None => AsyncBody::from_reader(body),
+			}))
+		})
+	}
+}
+
+/// Convert a body to an isahc one
+#[cfg(feature = "isahc")]
+fn to_isahc(body: Body) -> isahc::Body {
+	match body.0 {
+		Inner::Empty => isahc::Body::empty(),
+		Inner::Bytes(bytes) => bytes.into_inner().into(),
+		Inner::Reader(reader, Some(len)) => isahc::Body::from_reader_sized(reader, len),
+		Inner::Reader(reader, None) => isahc::Body::from_reader(reader),
+	}
+}
+
+/// Convert an async body to an isahc one
+#[cfg(feature = "isahc")]
+fn to_isahc_async(body: AsyncBody) -> isahc::AsyncBody {
+	match body.0 {
+		Inner::Empty => isahc::AsyncBody::empty(),
+		Inner::Bytes(bytes) => bytes.into_inner().into(),
+		Inner::Reader(reader, Some(len)) => isahc::AsyncBody::from_reader_sized(reader, len),
+		Inner::Reader(reader, None) => isahc::AsyncBody::from_reader(reader),
+	}
+}
+
+/// Transport using reqwest (with the native tls library, or rustls and the webpki roots with
+/// the `rustls` feature), with an async client for the async apis and a blocking client (which
+/// runs its own runtime in a thread) for the others. The async client must be polled within a
+/// tokio runtime, and the blocking one must not be called from one
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+	client: reqwest::Client,
+	blocking: reqwest::blocking::Client,
+}
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl ReqwestTransport {
+	pub fn new(client: reqwest::Client, blocking: reqwest::blocking::Client) -> Self {
+		Self { client, blocking }
+	}
+}
+
+/// Clients sending json requests without timeouts
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl Default for ReqwestTransport {
+	fn default() -> Self {
+		let mut headers = http::HeaderMap::new();
+		headers.insert(
+			http::header::CONTENT_TYPE,
+			http::HeaderValue::from_static("application/json"),
+		);
+		Self::new(
+			reqwest::Client::builder()
+				.default_headers(headers.clone())
+				.build()
+				.expect("default reqwest client"),
+			reqwest::blocking::Client::builder()
+				.default_headers(headers)
+				.timeout(None)
+				.build()
+				.expect("default reqwest client"),
+		)
+	}
+}
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl HttpTransport for ReqwestTransport {
+	fn send(&self, request: Request<String>) -> Result<Response<Vec<u8>>> {
+		let res = self
+			.blocking
+			.execute(request.try_into().map_err(Error::transport)?)
+			.map_err(Error::transport)?;
+		let head = head(res.status(), res.version(), res.headers());
+		let body = res.bytes().map_err(Error::transport)?;
+		Ok(head.map(|_| body.to_vec()))
+	}
+
+	fn send_async(&self, request: Request<String>) -> BoxFuture<'_, Result<Response<Vec<u8>>>> {
+		Box::pin(async move {
+			let res = self
+				.client
+				.execute(request.try_into().map_err(Error::transport)?)
+				.await
+				.map_err(Error::transport)?;
+			let head = head(res.status(), res.version(), res.headers());
+			let body = res.bytes().await.map_err(Error::transport)?;
+			Ok(head.map(|_| body.to_vec()))
+		})
+	}
+
+	fn send_stream(&self, request: Request<Body>) -> Result<Response<Body>> {
+		let res = self
+			.blocking
+			.execute(
+				request
+					.map(to_reqwest)
+					.try_into()
+					.map_err(Error::transport)?,
+			)
+			.map_err(Error::transport)?;
+		let head = head(res.status(), res.version(), res.headers());
+		let len = res.content_length();
+		// the response is not Sync, it is only read through a mutable reference
+		let body = Unshared(Mutex::new(res));
+		Ok(head.map(|_| match len {
+			Some(len) => Body::from_reader_sized(body, len),
+			None => Body::from_reader(body),
+		}))
+	}
+
+	fn send_stream_async(
+		&self,
+		request: Request<AsyncBody>,
+	) -> BoxFuture<'_, Result<Response<AsyncBody>>> {
+		Box::pin(async move {
+			let mut request = request;
+			if let Some(len) = request.body().len() {
+				request
+					.headers_mut()
+					.entry(http::header::CONTENT_LENGTH)
+					.or_insert_with(|| len.into());
+			}
+			let res = self
+				.client
+				.execute(
+					request
+						.map(to_reqwest_async)
+						.try_into()
+						.map_err(Error::transport)?,
+				)
+				.await
+				.map_err(Error::transport)?;
+			let head = head(res.status(), res.version(), res.headers());
+			let len = res.content_length();
+			let body = StreamReader {
+				stream: Mutex::new(Box::pin(res.bytes_stream())),
+				chunk: Bytes::new(),
+			};
+			Ok(head.map(|_| match len {
+				Some(len) => AsyncBody::from_reader_sized(body, len),
+				None => AsyncBody::from_reader(body),
+			}))
+		})
+	}
+}
+
+/// Return a response without body with the status, version and headers of a reqwest response
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+fn head(status: StatusCode, version: http::Version, headers: &http::HeaderMap) -> Response<()> {
+	let mut head = Response::new(());
+	*head.status_mut() = status;
+	*head.version_mut() = version;
+	*head.headers_mut() = headers.clone();
+	head
+}
+
+/// Convert a body to a reqwest one
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+fn to_reqwest(body: Body) -> reqwest::blocking::Body {
+	match body.0 {
+		Inner::Empty => Vec::new().into(),
+		Inner::Bytes(bytes) => bytes.into_inner().into(),
+		Inner::Reader(reader, Some(len)) => reqwest::blocking::Body::sized(reader, len),
+		Inner::Reader(reader, None) => reqwest::blocking::Body::new(reader),
+	}
+}
+
+/// Convert an async body to a reqwest one, streaming its reader by chunks
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+fn to_reqwest_async(body: AsyncBody) -> reqwest::Body {
+	match body.0 {
+		Inner::Empty => Vec::new().into(),
+		Inner::Bytes(bytes) => bytes.into_inner().into(),
+		reader => reqwest::Body::wrap_stream(Chunks(AsyncBody(reader))),
+	}
+}
+
+/// Reader made Sync by a mutex that is never locked as it is only read through a mutable
+/// reference
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+struct Unshared<R>(Mutex<R>);
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl<R: Read> Read for Unshared<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.0
+			.get_mut()
+			.map_err(|_| io::Error::from(io::ErrorKind::Other))?
+			.read(buf)
+	}
+}
+
+/// Stream of the chunks read from an async body
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+struct Chunks(AsyncBody);
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl Stream for Chunks {
+	type Item = io::Result<Vec<u8>>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let mut chunk = vec![0; 8192];
+		match Pin::new(&mut self.0).poll_read(cx, &mut chunk) {
+			Poll::Ready(Ok(0)) => Poll::Ready(None),
+			Poll::Ready(Ok(len)) => {
+				chunk.truncate(len);
+				Poll::Ready(Some(Ok(chunk)))
+			}
+			Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+			Poll::Pending => Poll::Pending,
+		}
+	}
+}
+
+/// Async reader of the chunks streamed by a reqwest response, made Sync by a mutex that is never
+/// locked as it is only polled through a mutable reference
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+struct StreamReader {
+	stream: Mutex<Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>>,
+	chunk: Bytes,
+}
+
+#[cfg(any(feature = "reqwest", feature = "rustls"))]
+impl AsyncRead for StreamReader {
+	fn poll_read(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		let this = &mut *self;
+		while this.chunk.is_empty() {
+			let stream = this
+				.stream
+				.get_mut()
+				.map_err(|_| io::Error::from(io::ErrorKind::Other))?;
+			match stream.as_mut().poll_next(cx) {
+				Poll::Ready(Some(Ok(chunk))) => this.chunk = chunk,
+				Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(io::Error::other(e))),
+				Poll::Ready(None) => return Poll::Ready(Ok(0)),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+		let len = buf.len().min(this.chunk.len());
+		buf[..len].copy_from_slice(&this.chunk.split_to(len));
+		Poll::Ready(Ok(len))
+	}
+}
+
+/// Scripted transport shared by the tests of the request methods
+#[cfg(test)]
+pub(crate) mod mock {
+	use super::*;
+	use crate::{builder::VaultClientBuilder, client::VaultClient};
+	use http::StatusCode;
+	use serde_json::Value;
+	use std::{
+		collections::{HashMap, VecDeque},
+		sync::{Arc, Mutex},
+	};
+
+	/// scripted response, None for a transport error
+	type Reply = Option<(StatusCode, Value)>;
+
+	/// Transport answering the requests with the json responses scripted for their method and
+	/// path (relative to `/v1/`), the last one being repeated, and recording the requests as
+	/// `METHOD path`. Unscripted requests get a 404
+	#[derive(Debug, Default)]
+	pub(crate) struct Scripted {
+		routes: Mutex<HashMap<String, VecDeque<Reply>>>,
+		requests: Mutex<Vec<(String, Request<String>)>>,
+	}
+
+	impl Scripted {
+		pub(crate) fn new() -> Arc<Self> {
+			Arc::new(Self::default())
+		}
+
+		fn push(&self, method: &str, path: &str, reply: Reply) {
+			self.routes
+				.lock()
+				.unwrap()
+				.entry(format!("{} {}", method, path))
+				.or_default()
+				.push_back(reply);
+		}
+
+		/// answer requests to path with status and body
+		pub(crate) fn on(&self, method: &str, path: &str, status: u16, body: Value) -> &Self {
+			self.push(
+				method,
+				path,
+				Some((StatusCode::from_u16(status).unwrap(), body)),
+			);
+			self
+		}
+
+		/// return the requests received as `METHOD path`
+		pub(crate) fn requests(&self) -> Vec<String> {
+			self.requests
+				.lock()
+				.unwrap()
+				.iter()
+				.map(|(request, _)| request.clone())
+				.collect()
+		}
+
+		/// return the json body of the last request received as `METHOD path`
+		pub(crate) fn body(&self, request: &str) -> Option<Value> {
+			self.requests
+				.lock()
+				.unwrap()
+				.iter()
+				.rev()
+				.find(|(r, _)| r == request)
+				.map(|(_, request)| serde_json::from_str(request.body()).unwrap_or(Value::Null))
+		}
+
+		/// return the value of the header name of the last request received as `METHOD path`
+		pub(crate) fn header(&self, request: &str, name: &str) -> Option<String> {
+			self.requests
+				.lock()
+				.unwrap()
+				.iter()
+				.rev()
+				.find(|(r, _)| r == request)
+				.and_then(|(_, request)| {
+					request.headers().get(name)?.to_str().ok().map(String::from)
+				})
+		}
+
+		/// return a builder of a client using the transport with a static token
+		pub(crate) fn builder(self: &Arc<Self>) -> VaultClientBuilder {
+			VaultClientBuilder::new("http://localhost:8200/v1")
+				.token("s.token")
+				.transport(self.clone())
+		}
+
+		/// return a client using the transport with a static token
+		pub(crate) fn client(self: &Arc<Self>) -> VaultClient {
+			self.builder().build().unwrap()
+		}
+	}
+
+	impl HttpTransport for Scripted {
+		fn send(&self, request: Request<String>) -> Result<Response<Vec<u8>>> {
+			let path = request.uri().path().trim_start_matches("/v1/").to_owned();
+			let key = format!("{} {}", request.method(), path);
+			self.requests.lock().unwrap().push((key.clone(), request));
+			let reply = {
+				let mut routes = self.routes.lock().unwrap();
+				match routes.get_mut(&key) {
+					Some(replies) if replies.len() > 1 => replies.pop_front().unwrap(),
+					Some(replies) => replies.front().cloned().unwrap(),
+					None => Some((StatusCode::NOT_FOUND, serde_json::json!({"errors": []}))),
+				}
+			};
+			match reply {
+				Some((status, body)) => Ok(Response::builder()
+					.status(status)
+					.body(body.to_string().into_bytes())
+					.unwrap()),
+				None => Err(Error::TransportError {
+					source: format!("{} failed", key).into(),
+				}),
+			}
+		}
+
+		fn send_async(&self, request: Request<String>) -> BoxFuture<'_, Result<Response<Vec<u8>>>> {
+			Box::pin(async move { self.send(request) })
+		}
+
+		fn send_stream(&self, request: Request<Body>) -> Result<Response<Body>> {
+			let (parts, mut body) = request.into_parts();
+			let mut text = String::new();
+			body.read_to_string(&mut text).map_err(Error::transport)?;
+			let res = self.send(Request::from_parts(parts, text))?;
+			Ok(res.map(Body::from))
+		}
+
+		fn send_stream_async(
+			&self,
+			request: Request<AsyncBody>,
+		) -> BoxFuture<'_, Result<Response<AsyncBody>>> {
+			Box::pin(async move {
+				let (parts, mut body) = request.into_parts();
+				let bytes = body.read_bytes().await.map_err(Error::transport)?;
+				let text = String::from_utf8_lossy(&bytes).into_owned();
+				let res = self.send(Request::from_parts(parts, text))?;
+				Ok(res.map(AsyncBody::from))
+			})
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+	#[cfg(any(feature = "reqwest", feature = "rustls"))]
+	use std::{io::Write, net::TcpListener, thread};
+
+	#[test]
+	fn requests_use_the_transport() {
+		let transport = mock::Scripted::new();
+		transport.on("GET", "sys/init", 200, json!({"initialized": true}));
+		let client = transport.client();
+		let request = client
+			.vault_request("GET", "sys/init", None, String::new())
+			.unwrap();
+		assert_eq!(client.send(request).unwrap(), json!({"initialized": true}));
+		assert_eq!(transport.requests(), vec!["GET sys/init"]);
+	}
+
+	/// Start a server answering the requests with their body and return its address
+	#[cfg(any(feature = "reqwest", feature = "rustls"))]
+	fn serve_echo(requests: usize) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		thread::spawn(move || {
+			for stream in listener.incoming().take(requests) {
+				let mut stream = stream.unwrap();
+				let mut head = Vec::new();
+				let mut byte = [0];
+				while !head.ends_with(b"\r\n\r\n") {
+					stream.read_exact(&mut byte).unwrap();
+					head.push(byte[0]);
+				}
+				let len = String::from_utf8(head)
+					.unwrap()
+					.lines()
+					.find_map(|line| {
+						line.to_lowercase()
+							.strip_prefix("content-length: ")?
+							.parse()
+							.ok()
+					})
+					.unwrap_or(0);
+				let mut body = vec![0; len];
+				stream.read_exact(&mut body).unwrap();
+				write!(
+					stream,
+					"HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+					len
+				)
+				.unwrap();
+				stream.write_all(&body).unwrap();
+			}
+		});
+		format!("http://{}/v1", addr)
+	}
+
+	#[test]
+	#[cfg(any(feature = "reqwest", feature = "rustls"))]
+	fn reqwest_transport_sends_requests() {
+		let url = serve_echo(2);
+		let transport = ReqwestTransport::default();
+		let request = Request::put(format!("{}/sys/init", url))
+			.body(r#"{"secret_shares":1}"#.to_owned())
+			.unwrap();
+		let res = transport.send(request).unwrap();
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.body(), br#"{"secret_shares":1}"#);
+		let snapshot = Body::from_reader_sized(Cursor::new(b"snapshot".to_vec()), 8);
+		let request = Request::post(format!("{}/sys/storage/raft/snapshot-force", url))
+			.body(snapshot)
+			.unwrap();
+		let mut res = transport.send_stream(request).unwrap();
+		assert_eq!(res.body().len(), Some(8));
+		let mut body = String::new();
+		res.body_mut().read_to_string(&mut body).unwrap();
+		assert_eq!(body, "snapshot");
+	}
+
+	#[test]
+	#[cfg(all(any(feature = "reqwest", feature = "rustls"), feature = "tokio"))]
+	fn reqwest_transport_sends_async_requests() {
+		let url = serve_echo(2);
+		let transport = ReqwestTransport::default();
+		let runtime = tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()
+			.unwrap();
+		runtime.block_on(async {
+			let request = Request::put(format!("{}/sys/init", url))
+				.body(r#"{"secret_shares":1}"#.to_owned())
+				.unwrap();
+			let res = transport.send_async(request).await.unwrap();
+			assert_eq!(res.body(), br#"{"secret_shares":1}"#);
+			let snapshot = AsyncBody::from_reader_sized(
+				futures_lite::io::Cursor::new(b"snapshot".to_vec()),
+				8,
+			);
+			let request = Request::post(format!("{}/sys/storage/raft/snapshot-force", url))
+				.body(snapshot)
+				.unwrap();
+			let mut res = transport.send_stream_async(request).await.unwrap();
+			assert_eq!(res.body_mut().read_bytes().await.unwrap(), b"snapshot");
+		});
+	}
+}
@@ -0,0 +1,176 @@
+use crate::{client::VaultClient, error::Result, secret::Secret};
+
+use std::{
+	sync::{
+		mpsc::{self, Receiver},
+		Arc, Condvar, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+/// delay before retrying after a failed fetch
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Fetches a secret again in a background thread each time it reaches its renew delay (or every
+/// refresh interval for secrets without lease), and delivers the new values (or the errors) over
+/// a channel. The thread stops when the watcher or the receiver is dropped
+#[derive(Debug)]
+pub struct SecretWatcher {
+	stop: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Drop for SecretWatcher {
+	fn drop(&mut self) {
+		let (lock, cvar) = &*self.stop;
+		*lock.lock().unwrap_or_else(|e| e.into_inner()) = true;
+		cvar.notify_one();
+	}
+}
+
+impl VaultClient {
+	/// Watch the secret of role at path. The secret is fetched immediately and again when its
+	/// renew delay is reached, or every refresh interval if it has no lease. The role logs in
+	/// again before each fetch if its token has expired
+	pub fn watch_secret(
+		client: &Arc<VaultClient>,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+		refresh: Duration,
+	) -> (SecretWatcher, Receiver<Result<Secret>>) {
+		let (tx, rx) = mpsc::channel();
+		let stop = Arc::new((Mutex::new(false), Condvar::new()));
+		let client = Arc::clone(client);
+		let role = role.to_owned();
+		let method = method.to_owned();
+		let path = path.to_owned();
+		let kwargs: Option<Vec<(String, String)>> = kwargs.map(|kwargs| {
+			kwargs
+				.iter()
+				.map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+				.collect()
+		});
+		let thread_stop = Arc::clone(&stop);
+		thread::spawn(move || loop {
+			let kwargs: Option<Vec<(&str, &str)>> = kwargs.as_ref().map(|kwargs| {
+				kwargs
+					.iter()
+					.map(|(k, v)| (k.as_str(), v.as_str()))
+					.collect()
+			});
+			let res = client
+				.login(&role)
+				.and_then(|_| client.get_secret_uncached(&role, &method, &path, kwargs.as_ref()));
+			client.notify_refresh(&role, &path, &res);
+			let delay = match res {
+				Ok(ref secret) => secret
+					.renew_delay()
+					.unwrap_or_else(|| client.renewal.jittered(refresh)),
+				Err(_) => client.renewal.jittered(RETRY_DELAY),
+			};
+			if tx.send(res).is_err() {
+				return;
+			}
+			// wait for the delay or for the watcher to be dropped
+			let (lock, cvar) = &*thread_stop;
+			let stopped = lock.lock().unwrap_or_else(|e| e.into_inner());
+			let (stopped, _) = cvar
+				.wait_timeout_while(stopped, delay, |stopped| !*stopped)
+				.unwrap_or_else(|e| e.into_inner());
+			if *stopped {
+				return;
+			}
+		});
+		(SecretWatcher { stop }, rx)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::transport::mock::Scripted;
+	use serde_json::json;
+	use std::sync::mpsc::RecvTimeoutError;
+
+	#[test]
+	fn watcher_delivers_the_refreshed_secrets() {
+		let transport = Scripted::new();
+		let secret = |password: &str| json!({"data": {"password": password}});
+		transport.on("GET", "secret/app", 200, secret("v1")).on(
+			"GET",
+			"secret/app",
+			200,
+			secret("v2"),
+		);
+		let client = Arc::new(transport.client());
+		let (_watcher, rx) = VaultClient::watch_secret(
+			&client,
+			"app",
+			"GET",
+			"secret/app",
+			None,
+			Duration::from_millis(20),
+		);
+		let timeout = Duration::from_secs(5);
+		assert_eq!(
+			rx.recv_timeout(timeout).unwrap().unwrap().value["password"],
+			"v1"
+		);
+		assert_eq!(
+			rx.recv_timeout(timeout).unwrap().unwrap().value["password"],
+			"v2"
+		);
+		assert!(transport.requests().len() >= 2);
+	}
+
+	#[test]
+	fn watcher_delivers_the_errors() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/app",
+			403,
+			json!({"errors": ["permission denied"]}),
+		);
+		let client = Arc::new(transport.client());
+		let (_watcher, rx) = VaultClient::watch_secret(
+			&client,
+			"app",
+			"GET",
+			"secret/app",
+			None,
+			Duration::from_millis(20),
+		);
+		assert!(rx.recv_timeout(Duration::from_secs(5)).unwrap().is_err());
+	}
+
+	#[test]
+	fn dropping_the_watcher_stops_the_thread() {
+		let transport = Scripted::new();
+		transport.on(
+			"GET",
+			"secret/app",
+			200,
+			json!({"data": {"password": "v1"}}),
+		);
+		let client = Arc::new(transport.client());
+		let (watcher, rx) = VaultClient::watch_secret(
+			&client,
+			"app",
+			"GET",
+			"secret/app",
+			None,
+			Duration::from_secs(60),
+		);
+		assert!(rx.recv_timeout(Duration::from_secs(5)).unwrap().is_ok());
+		drop(watcher);
+		// the thread wakes up without waiting for the refresh interval and drops the sender
+		assert_eq!(
+			rx.recv_timeout(Duration::from_secs(5)).unwrap_err(),
+			RecvTimeoutError::Disconnected
+		);
+		assert_eq!(transport.requests().len(), 1);
+	}
+}
@@ -0,0 +1,535 @@
+use crate::{
+	base64,
+	error::{Error, Result},
+	redact::Redacted,
+};
+
+use curl::easy::Easy;
+use sha1::{Digest, Sha1};
+use std::{
+	fmt,
+	io::{self, Read, Write},
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+/// maximum size of the response to the upgrade request and of a message
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_MESSAGE: usize = 16 * 1024 * 1024;
+
+/// delay between two checks of the stop flag while waiting for the socket
+const STOP_CHECK: Duration = Duration::from_secs(1);
+
+/// guid appended by the server to the key of the client to compute its accept key
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// `CURLINFO_SOCKET + 44`, which curl-sys doesn't export
+#[cfg(unix)]
+const CURLINFO_ACTIVESOCKET: curl_sys::CURLINFO = 0x500000 + 44;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Options of the builder applied to the websocket connections, which are opened with curl
+/// instead of the http client. The proxy credentials are not used
+#[derive(Clone, Default)]
+pub(crate) struct Connect {
+	pub(crate) ca_file: Option<PathBuf>,
+	pub(crate) client_cert: Option<(String, String, Option<String>)>,
+	pub(crate) accept_invalid_certs: bool,
+	pub(crate) proxy: Option<String>,
+	pub(crate) unix_socket: Option<PathBuf>,
+	pub(crate) connect_timeout: Option<Duration>,
+	pub(crate) headers: Vec<(String, String)>,
+}
+
+impl fmt::Debug for Connect {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let client_cert = self
+			.client_cert
+			.as_ref()
+			.map(|(cert, key, password)| (cert, key, password.as_deref().map(Redacted)));
+		f.debug_struct("Connect")
+			.field("ca_file", &self.ca_file)
+			.field("client_cert", &client_cert)
+			.field("accept_invalid_certs", &self.accept_invalid_certs)
+			.field("proxy", &self.proxy)
+			.field("unix_socket", &self.unix_socket)
+			.field("connect_timeout", &self.connect_timeout)
+			.field("headers", &self.headers.len())
+			.finish()
+	}
+}
+
+/// Connection to the host of an http(s) url opened by curl, with the tls, proxy and unix socket
+/// options of the builder. The reads and writes block until the socket is ready or the stop
+/// flag is set
+pub(crate) struct CurlStream {
+	easy: Easy,
+	stop: Arc<AtomicBool>,
+}
+
+impl CurlStream {
+	/// Connect to the host of url (without sending anything)
+	pub(crate) fn connect(url: &str, options: &Connect, stop: Arc<AtomicBool>) -> Result<Self> {
+		let mut easy = Easy::new();
+		easy.url(url).map_err(Error::transport)?;
+		easy.connect_only(true).map_err(Error::transport)?;
+		// the upgrade request is sent on the connection, proxies must tunnel it
+		easy.http_proxy_tunnel(true).map_err(Error::transport)?;
+		if let Some(ref proxy) = options.proxy {
+			easy.proxy(proxy).map_err(Error::transport)?;
+		}
+		if let Some(ref path) = options.unix_socket {
+			easy.unix_socket_path(Some(path))
+				.map_err(Error::transport)?;
+		}
+		if let Some(ref path) = options.ca_file {
+			easy.cainfo(path).map_err(Error::transport)?;
+		}
+		if let Some((ref cert, ref key, ref password)) = options.client_cert {
+			easy.ssl_cert(cert).map_err(Error::transport)?;
+			easy.ssl_key(key).map_err(Error::transport)?;
+			if let Some(password) = password {
+				easy.key_password(password).map_err(Error::transport)?;
+			}
+		}
+		if options.accept_invalid_certs {
+			easy.ssl_verify_peer(false).map_err(Error::transport)?;
+			easy.ssl_verify_host(false).map_err(Error::transport)?;
+		}
+		if let Some(timeout) = options.connect_timeout {
+			easy.connect_timeout(timeout).map_err(Error::transport)?;
+		}
+		easy.perform().map_err(Error::transport)?;
+		Ok(Self { easy, stop })
+	}
+
+	/// Wait for the socket to be readable (or writable) or for the stop flag to be set
+	#[cfg(unix)]
+	fn wait(&self, write: bool) -> io::Result<()> {
+		let mut socket: curl_sys::curl_socket_t = curl_sys::CURL_SOCKET_BAD;
+		// SAFETY: the handle is alive and ACTIVESOCKET writes a curl_socket_t
+		let rc = unsafe {
+			curl_sys::curl_easy_getinfo(
+				self.easy.raw(),
+				CURLINFO_ACTIVESOCKET,
+				&mut socket as *mut curl_sys::curl_socket_t,
+			)
+		};
+		if rc != curl_sys::CURLE_OK || socket == curl_sys::CURL_SOCKET_BAD {
+			return Err(io::Error::new(io::ErrorKind::NotConnected, "no socket"));
+		}
+		let mut fd = libc::pollfd {
+			fd: socket,
+			events: if write { libc::POLLOUT } else { libc::POLLIN },
+			revents: 0,
+		};
+		loop {
+			if self.stop.load(Ordering::Relaxed) {
+				return Err(io::ErrorKind::ConnectionAborted.into());
+			}
+			// SAFETY: fd is a valid pollfd for the duration of the call
+			match unsafe { libc::poll(&mut fd, 1, STOP_CHECK.as_millis() as libc::c_int) } {
+				0 => continue,
+				n if n > 0 => return Ok(()),
+				_ => {
+					let e = io::Error::last_os_error();
+					if e.kind() != io::ErrorKind::Interrupted {
+						return Err(e);
+					}
+				}
+			}
+		}
+	}
+
+	/// Wait a little before trying again, curl not exposing a portable socket to wait for
+	#[cfg(not(unix))]
+	fn wait(&self, _write: bool) -> io::Result<()> {
+		if self.stop.load(Ordering::Relaxed) {
+			return Err(io::ErrorKind::ConnectionAborted.into());
+		}
+		std::thread::sleep(Duration::from_millis(10));
+		Ok(())
+	}
+}
+
+impl Read for CurlStream {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		loop {
+			match self.easy.recv(buf) {
+				Ok(n) => return Ok(n),
+				Err(e) if e.is_again() => self.wait(false)?,
+				Err(e) => return Err(io::Error::other(e)),
+			}
+		}
+	}
+}
+
+impl Write for CurlStream {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		loop {
+			match self.easy.send(buf) {
+				Ok(n) => return Ok(n),
+				Err(e) if e.is_again() => self.wait(true)?,
+				Err(e) => return Err(io::Error::other(e)),
+			}
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Client side of a websocket (RFC 6455) over stream, returning the text and binary messages
+/// and answering the pings and the close of the server
+pub(crate) struct WebSocket<S: Read + Write> {
+	stream: S,
+	closed: bool,
+}
+
+impl<S: Read + Write> WebSocket<S> {
+	/// Send on stream the upgrade request of path to host with headers, and check that the server
+	/// switches to the websocket protocol with the accept key matching the key of the request
+	pub(crate) fn handshake(
+		stream: S,
+		host: &str,
+		path: &str,
+		headers: &[(String, String)],
+	) -> Result<Self> {
+		let key = base64::encode(&fastrand::u128(..).to_be_bytes());
+		Self::handshake_with_key(stream, host, path, headers, &key)
+	}
+
+	/// Perform the handshake with the given `Sec-WebSocket-Key`
+	fn handshake_with_key(
+		mut stream: S,
+		host: &str,
+		path: &str,
+		headers: &[(String, String)],
+		key: &str,
+	) -> Result<Self> {
+		let mut request = format!(
+			"GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+			 Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+			path, host, key
+		);
+		for (name, value) in headers.iter() {
+			request.push_str(&format!("{}: {}\r\n", name, value));
+		}
+		request.push_str("\r\n");
+		stream
+			.write_all(request.as_bytes())
+			.and_then(|_| stream.flush())
+			.map_err(Error::transport)?;
+		let head = read_head(&mut stream).map_err(Error::transport)?;
+		let status = head
+			.split(' ')
+			.nth(1)
+			.and_then(|status| status.parse::<u16>().ok())
+			.ok_or_else(|| Error::transport("invalid response to the websocket upgrade"))?;
+		if status != 101 {
+			let body = read_body(&mut stream, &head).map_err(Error::transport)?;
+			let status = http::StatusCode::from_u16(status).map_err(Error::transport)?;
+			return Err(crate::client::vault_error(status, path, &body));
+		}
+		if header(&head, "sec-websocket-accept") != Some(accept_key(key).as_str()) {
+			return Err(Error::transport(
+				"invalid Sec-WebSocket-Accept in the response to the websocket upgrade",
+			));
+		}
+		Ok(Self {
+			stream,
+			closed: false,
+		})
+	}
+
+	/// Return the next message, or None once the server has closed the websocket
+	pub(crate) fn read_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+		let mut message = Vec::new();
+		while !self.closed {
+			let (fin, opcode, payload) = match read_frame(&mut self.stream) {
+				Ok(frame) => frame,
+				Err(e) => {
+					self.closed = true;
+					return Err(e);
+				}
+			};
+			match opcode {
+				OP_CONTINUATION | OP_TEXT | OP_BINARY => {
+					if message.len() + payload.len() > MAX_MESSAGE {
+						self.closed = true;
+						return Err(io::Error::new(
+							io::ErrorKind::InvalidData,
+							"message too large",
+						));
+					}
+					message.extend(payload);
+					if fin {
+						return Ok(Some(message));
+					}
+				}
+				OP_CLOSE => {
+					// echo the status code of the server
+					self.closed = true;
+					let _ =
+						write_frame(&mut self.stream, OP_CLOSE, &payload[..payload.len().min(2)]);
+				}
+				OP_PING => write_frame(&mut self.stream, OP_PONG, &payload)?,
+				// pongs
+				_ => {}
+			}
+		}
+		Ok(None)
+	}
+
+	/// Close the websocket with the normal closure status
+	pub(crate) fn close(&mut self) {
+		if !self.closed {
+			self.closed = true;
+			let _ = write_frame(&mut self.stream, OP_CLOSE, &1000u16.to_be_bytes());
+		}
+	}
+}
+
+/// Read the status line and the headers of an http response
+fn read_head<R: Read>(stream: &mut R) -> io::Result<String> {
+	let mut head = Vec::new();
+	let mut byte = [0];
+	// read byte by byte so as not to consume the first frames
+	while !head.ends_with(b"\r\n\r\n") {
+		if head.len() > MAX_HEAD {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"response head too large",
+			));
+		}
+		stream.read_exact(&mut byte)?;
+		head.push(byte[0]);
+	}
+	Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Return the value of the header name in the head of a response
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+	head.lines()
+		.filter_map(|line| line.split_once(':'))
+		.find(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+		.map(|(_, value)| value.trim())
+}
+
+/// Return the `Sec-WebSocket-Accept` expected from the server for the key of the client
+pub(crate) fn accept_key(key: &str) -> String {
+	base64::encode(&Sha1::digest(
+		format!("{}{}", key, WEBSOCKET_GUID).as_bytes(),
+	))
+}
+
+/// Read the body of a response whose head has a content length
+fn read_body<R: Read>(stream: &mut R, head: &str) -> io::Result<String> {
+	let len = header(head, "content-length")
+		.and_then(|value| value.parse::<usize>().ok())
+		.unwrap_or(0)
+		.min(MAX_HEAD);
+	let mut body = vec![0; len];
+	stream.read_exact(&mut body)?;
+	Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Read a frame and return its fin flag, its opcode and its unmasked payload
+fn read_frame<R: Read>(stream: &mut R) -> io::Result<(bool, u8, Vec<u8>)> {
+	let mut head = [0; 2];
+	stream.read_exact(&mut head)?;
+	let len = match head[1] & 0x7f {
+		126 => {
+			let mut len = [0; 2];
+			stream.read_exact(&mut len)?;
+			u16::from_be_bytes(len) as usize
+		}
+		127 => {
+			let mut len = [0; 8];
+			stream.read_exact(&mut len)?;
+			u64::from_be_bytes(len) as usize
+		}
+		len => len as usize,
+	};
+	if len > MAX_MESSAGE {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			"frame too large",
+		));
+	}
+	let mut mask = None;
+	if head[1] & 0x80 != 0 {
+		let mut key = [0; 4];
+		stream.read_exact(&mut key)?;
+		mask = Some(key);
+	}
+	let mut payload = vec![0; len];
+	stream.read_exact(&mut payload)?;
+	if let Some(mask) = mask {
+		apply_mask(&mut payload, mask);
+	}
+	Ok((head[0] & 0x80 != 0, head[0] & 0x0f, payload))
+}
+
+/// Write a final frame with a payload masked as required for the frames sent by clients
+fn write_frame<W: Write>(stream: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+	let mut frame = vec![0x80 | opcode];
+	match payload.len() {
+		len if len < 126 => frame.push(0x80 | len as u8),
+		len if len <= u16::MAX as usize => {
+			frame.push(0x80 | 126);
+			frame.extend(&(len as u16).to_be_bytes());
+		}
+		len => {
+			frame.push(0x80 | 127);
+			frame.extend(&(len as u64).to_be_bytes());
+		}
+	}
+	let mask = fastrand::u32(..).to_be_bytes();
+	frame.extend(&mask);
+	let start = frame.len();
+	frame.extend(payload);
+	apply_mask(&mut frame[start..], mask);
+	stream.write_all(&frame)?;
+	stream.flush()
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+	for (i, byte) in payload.iter_mut().enumerate() {
+		*byte ^= mask[i % 4];
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::io::Cursor;
+
+	/// Stream reading what the server sent and recording what the client writes
+	struct Pipe {
+		input: Cursor<Vec<u8>>,
+		output: Vec<u8>,
+	}
+
+	impl Read for Pipe {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			self.input.read(buf)
+		}
+	}
+
+	impl Write for Pipe {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.output.write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	/// Encode an unmasked frame as sent by a server
+	fn server_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+		let mut frame = vec![if fin { 0x80 } else { 0 } | opcode, payload.len() as u8];
+		frame.extend(payload);
+		frame
+	}
+
+	/// key of the example of RFC 6455
+	const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+	#[test]
+	fn accept_key_of_the_rfc() {
+		assert_eq!(accept_key(KEY), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+	}
+
+	#[test]
+	fn wrong_accept_key_is_refused() {
+		let input = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+			Sec-WebSocket-Accept: AAAAAAAAAAAAAAAAAAAAAAAAAAA=\r\n\r\n";
+		let pipe = Pipe {
+			input: Cursor::new(input.to_vec()),
+			output: Vec::new(),
+		};
+		assert!(matches!(
+			WebSocket::handshake_with_key(pipe, "vault:8200", "/v1/sys/events", &[], KEY),
+			Err(Error::TransportError { .. })
+		));
+	}
+
+	#[test]
+	fn messages_are_reassembled_and_pings_answered() {
+		let mut input = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+			Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n"
+			.to_vec();
+		input.extend(server_frame(false, OP_TEXT, b"{\"id\""));
+		input.extend(server_frame(true, OP_PING, b"hi"));
+		input.extend(server_frame(true, OP_CONTINUATION, b":\"1\"}"));
+		input.extend(server_frame(true, OP_CLOSE, &1000u16.to_be_bytes()));
+		let pipe = Pipe {
+			input: Cursor::new(input),
+			output: Vec::new(),
+		};
+		let headers = vec![("X-Vault-Token".to_owned(), "s.token".to_owned())];
+		let mut ws =
+			WebSocket::handshake_with_key(pipe, "vault:8200", "/v1/sys/events", &headers, KEY)
+				.unwrap();
+		let request = String::from_utf8_lossy(&ws.stream.output).into_owned();
+		assert!(request.starts_with("GET /v1/sys/events HTTP/1.1\r\nHost: vault:8200\r\n"));
+		assert!(request.contains(&format!("\r\nSec-WebSocket-Key: {}\r\n", KEY)));
+		assert!(request.contains("\r\nX-Vault-Token: s.token\r\n"));
+		ws.stream.output.clear();
+		assert_eq!(ws.read_message().unwrap().unwrap(), b"{\"id\":\"1\"}");
+		assert!(ws.read_message().unwrap().is_none());
+		// the pong and the close sent back are masked
+		let mut output = Cursor::new(ws.stream.output.clone());
+		assert_eq!(
+			read_frame(&mut output).unwrap(),
+			(true, OP_PONG, b"hi".to_vec())
+		);
+		assert_eq!(
+			read_frame(&mut output).unwrap(),
+			(true, OP_CLOSE, 1000u16.to_be_bytes().to_vec())
+		);
+	}
+
+	#[test]
+	fn refused_upgrade_is_a_vault_error() {
+		let body = r#"{"errors":["permission denied"]}"#;
+		let input = format!(
+			"HTTP/1.1 403 Forbidden\r\nContent-Length: {}\r\n\r\n{}",
+			body.len(),
+			body
+		);
+		let pipe = Pipe {
+			input: Cursor::new(input.into_bytes()),
+			output: Vec::new(),
+		};
+		assert!(matches!(
+			WebSocket::handshake(pipe, "vault:8200", "/v1/sys/events", &[]),
+			Err(Error::Forbidden { errors, .. }) if errors == vec!["permission denied"]
+		));
+	}
+
+	#[test]
+	fn long_frames() {
+		let payload = vec![7; 70000];
+		let mut frame = Vec::new();
+		write_frame(&mut frame, OP_BINARY, &payload).unwrap();
+		assert_eq!(frame[1], 0x80 | 127);
+		assert_eq!(
+			read_frame(&mut Cursor::new(frame)).unwrap(),
+			(true, OP_BINARY, payload)
+		);
+	}
+}
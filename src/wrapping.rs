@@ -0,0 +1,172 @@
+use crate::{
+	client::{kwargs_body, secret_from_value, VaultClient},
+	error::{Error, Result},
+	redact::Redacted,
+	secret::Secret,
+};
+
+use http::HeaderValue;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fmt, time::Duration};
+use zeroize::ZeroizeOnDrop;
+
+/// Information about a wrapped response
+#[derive(Clone, PartialEq, Deserialize, ZeroizeOnDrop)]
+pub struct WrapInfo {
+	/// single use token to give to `unwrap`
+	pub token: String,
+	pub accessor: String,
+	/// ttl of the wrapping token in seconds
+	pub ttl: u64,
+	pub creation_time: String,
+	pub creation_path: String,
+}
+
+impl fmt::Debug for WrapInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("WrapInfo")
+			.field("token", &Redacted(&self.token))
+			.field("accessor", &self.accessor)
+			.field("ttl", &self.ttl)
+			.field("creation_time", &self.creation_time)
+			.field("creation_path", &self.creation_path)
+			.finish()
+	}
+}
+
+/// Extract the wrap information of a wrapped response
+fn wrap_info_from_value(mut value: Value) -> Result<WrapInfo> {
+	serde_json::from_value(value["wrap_info"].take()).map_err(|e| Error::ParseError { source: e })
+}
+
+impl VaultClient {
+	/// Get a secret wrapped in a single use token valid for ttl instead of the secret itself
+	pub fn get_secret_wrapped(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+		ttl: Duration,
+	) -> Result<WrapInfo> {
+		let body = kwargs_body(kwargs);
+		let mut request =
+			self.vault_request(method, path, Some(&self.token(role)?), body.to_string())?;
+		request
+			.headers_mut()
+			.insert("X-Vault-Wrap-TTL", HeaderValue::from(ttl.as_secs()));
+		wrap_info_from_value(self.send(request)?)
+	}
+
+	/// Get asynchronously a secret wrapped in a single use token valid for ttl
+	pub async fn get_secret_wrapped_async(
+		&self,
+		role: &str,
+		method: &str,
+		path: &str,
+		kwargs: Option<&Vec<(&str, &str)>>,
+		ttl: Duration,
+	) -> Result<WrapInfo> {
+		let body = kwargs_body(kwargs);
+		let mut request = self.vault_request(
+			method,
+			path,
+			Some(&self.token_async(role).await?),
+			body.to_string(),
+		)?;
+		request
+			.headers_mut()
+			.insert("X-Vault-Wrap-TTL", HeaderValue::from(ttl.as_secs()));
+		wrap_info_from_value(self.send_async(request).await?)
+	}
+
+	/// Unwrap the secret wrapped in token. No login is needed as the wrapping token is used
+	pub fn unwrap(&self, token: &str) -> Result<Secret> {
+		let request =
+			self.vault_request("POST", "sys/wrapping/unwrap", Some(token), String::new())?;
+		Ok(secret_from_value(self.send(request)?, &self.renewal))
+	}
+
+	/// Unwrap asynchronously the secret wrapped in token
+	pub async fn unwrap_async(&self, token: &str) -> Result<Secret> {
+		let request =
+			self.vault_request("POST", "sys/wrapping/unwrap", Some(token), String::new())?;
+		Ok(secret_from_value(
+			self.send_async(request).await?,
+			&self.renewal,
+		))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{client::TOKEN_ROLE, transport::mock::Scripted};
+	use serde_json::json;
+
+	#[test]
+	fn wrap_info_from_response() {
+		let info = wrap_info_from_value(json!({
+			"data": null,
+			"wrap_info": {
+				"token": "hvs.wrapped",
+				"accessor": "bnEqQE",
+				"ttl": 300,
+				"creation_time": "2016-09-28T14:41:00.56961496-04:00",
+				"creation_path": "secret/foo"
+			}
+		}))
+		.unwrap();
+		assert_eq!(info.token, "hvs.wrapped");
+		assert_eq!(info.ttl, 300);
+	}
+
+	#[test]
+	fn wrapped_secrets_are_unwrapped_with_the_wrapping_token() {
+		let transport = Scripted::new();
+		transport
+			.on(
+				"GET",
+				"secret/foo",
+				200,
+				json!({"wrap_info": {
+					"token": "hvs.wrapped",
+					"accessor": "bnEqQE",
+					"ttl": 300,
+					"creation_time": "2016-09-28T14:41:00.56961496-04:00",
+					"creation_path": "secret/foo"
+				}}),
+			)
+			.on(
+				"POST",
+				"sys/wrapping/unwrap",
+				200,
+				json!({"data": {"foo": "bar"}}),
+			);
+		let client = transport.client();
+		let info = client
+			.get_secret_wrapped(
+				TOKEN_ROLE,
+				"GET",
+				"secret/foo",
+				None,
+				Duration::from_secs(300),
+			)
+			.unwrap();
+		assert_eq!(
+			transport
+				.header("GET secret/foo", "x-vault-wrap-ttl")
+				.as_deref(),
+			Some("300")
+		);
+		let secret = futures_lite::future::block_on(client.unwrap_async(&info.token)).unwrap();
+		assert_eq!(secret.value["foo"], "bar");
+		assert_eq!(
+			transport
+				.header("POST sys/wrapping/unwrap", "x-vault-token")
+				.as_deref(),
+			Some("hvs.wrapped")
+		);
+	}
+}